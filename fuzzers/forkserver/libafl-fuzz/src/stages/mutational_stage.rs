@@ -48,7 +48,7 @@ where
     }
 
     /// Gets the number of iterations as a random number
-    fn iterations(&self, state: &mut S) -> Result<usize, Error> {
+    fn iterations(&mut self, state: &mut S) -> Result<usize, Error> {
         match self {
             Self::StdMutational(m, _) => m.iterations(state),
             Self::PowerMutational(p, _) => p.iterations(state),
@@ -31,10 +31,11 @@ use serde::{Deserialize, Serialize};
 use crate::events::llmp::COMPRESS_THRESHOLD;
 use crate::{
     events::{
-        llmp::{LLMP_TAG_EVENT_TO_BOTH, _LLMP_TAG_EVENT_TO_BROKER},
+        llmp::{_LLMP_TAG_EVENT_TO_BROKER, LLMP_TAG_EVENT_TO_BOTH},
         AdaptiveSerializer, CustomBufEventResult, CustomBufHandlerFn, Event, EventConfig,
         EventFirer, EventManager, EventManagerHooksTuple, EventManagerId, EventProcessor,
-        EventRestarter, HasCustomBufHandlers, HasEventManagerId, ProgressReporter,
+        EventRestarter, HasCustomBufHandlers, HasEventManagerId, ImportSourcesMetadata,
+        ProgressReporter,
     },
     executors::{Executor, HasObservers},
     fuzzer::{Evaluator, EvaluatorObservers, ExecutionProcessor},
@@ -418,7 +419,6 @@ where
                 client_config,
                 exit_kind,
                 observers_buf,
-                #[cfg(feature = "std")]
                 forward_id,
                 ..
             } => {
@@ -455,6 +455,7 @@ where
                     };
                     if let Some(item) = res.1 {
                         *state.imported_mut() += 1;
+                        ImportSourcesMetadata::record_in(state, forward_id.unwrap_or(client_id));
                         log::debug!("Added received Testcase {evt_name} as item #{item}");
                     } else {
                         log::debug!("Testcase {evt_name} was discarded");
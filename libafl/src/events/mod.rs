@@ -404,6 +404,61 @@ where
     }
 }
 
+/// The maximum number of clients an [`ImportSourcesMetadata`] counts separately
+pub const MAX_IMPORT_SOURCES: usize = 64;
+
+/// The number of testcases imported from each client, recorded by the event managers
+/// alongside [`crate::state::HasImported::imported`], e.g., to report which clients' finds are the most valuable.
+///
+/// A testcase forwarded by a broker or main node is counted for the client that found it.
+/// At most [`MAX_IMPORT_SOURCES`] clients are counted separately, the imports from any further clients are counted together.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ImportSourcesMetadata {
+    counts: Vec<(ClientId, usize)>,
+    other: usize,
+}
+
+libafl_bolts::impl_serdeany!(ImportSourcesMetadata);
+
+impl ImportSourcesMetadata {
+    /// Counts a testcase imported from `client_id`
+    pub fn record(&mut self, client_id: ClientId) {
+        if let Some((_, count)) = self.counts.iter_mut().find(|(id, _)| *id == client_id) {
+            *count += 1;
+        } else if self.counts.len() < MAX_IMPORT_SOURCES {
+            self.counts.push((client_id, 1));
+        } else {
+            self.other += 1;
+        }
+    }
+
+    /// The number of testcases imported from each client counted separately, in the order of their first import
+    #[must_use]
+    pub fn counts(&self) -> &[(ClientId, usize)] {
+        &self.counts
+    }
+
+    /// The number of testcases imported from the clients beyond [`MAX_IMPORT_SOURCES`]
+    #[must_use]
+    pub fn other(&self) -> usize {
+        self.other
+    }
+
+    /// Counts a testcase imported from `client_id` in the [`ImportSourcesMetadata`] of the `state`
+    pub fn record_in<S>(state: &mut S, client_id: ClientId)
+    where
+        S: HasMetadata,
+    {
+        state
+            .metadata_or_insert_with(ImportSourcesMetadata::default)
+            .record(client_id);
+    }
+}
+
 /// [`EventFirer`] fires an event.
 pub trait EventFirer: UsesState {
     /// Send off an [`Event`] to the broker
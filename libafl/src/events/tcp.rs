@@ -45,7 +45,7 @@ use crate::{
     events::{
         BrokerEventResult, Event, EventConfig, EventFirer, EventManager, EventManagerHooksTuple,
         EventManagerId, EventProcessor, EventRestarter, HasCustomBufHandlers, HasEventManagerId,
-        ProgressReporter,
+        ImportSourcesMetadata, ProgressReporter,
     },
     executors::{Executor, HasObservers},
     fuzzer::{EvaluatorObservers, ExecutionProcessor},
@@ -641,6 +641,7 @@ where
                 };
                 if let Some(item) = _res.1 {
                     *state.imported_mut() += 1;
+                    ImportSourcesMetadata::record_in(state, forward_id.unwrap_or(client_id));
                     log::info!("Added received Testcase as item #{item}");
                 }
             }
@@ -22,6 +22,13 @@ pub mod inprocess;
 #[cfg(feature = "std")]
 pub mod timer;
 
+/// The [`ExecutorHook::priority`] of hooks that don't specify one
+pub const DEFAULT_HOOK_PRIORITY: i32 = 0;
+
+/// The [`ExecutorHook::priority`] of hooks resetting the coverage state of the target, e.g., the
+/// ngram or ctx state of the `sancov` instrumentation, so that they run before other hooks.
+pub const COVERAGE_RESET_HOOK_PRIORITY: i32 = 100;
+
 /// The hook that runs before and after the executor runs the target
 pub trait ExecutorHook<S>
 where
@@ -33,9 +40,22 @@ where
     fn pre_exec(&mut self, state: &mut S, input: &S::Input);
     /// The hook that runs before runs the target
     fn post_exec(&mut self, state: &mut S, input: &S::Input);
+
+    /// The priority of this hook in an [`ExecutorHooksTuple`].
+    ///
+    /// Hooks with a higher priority are initialized and run first, both before and after the
+    /// target runs. Hooks with the same priority run in the order of the tuple.
+    /// Hooks resetting coverage state use [`COVERAGE_RESET_HOOK_PRIORITY`], so that other hooks
+    /// never see the state of the previous execution. Defaults to [`DEFAULT_HOOK_PRIORITY`].
+    #[inline]
+    fn priority(&self) -> i32 {
+        DEFAULT_HOOK_PRIORITY
+    }
 }
 
 /// The hook that runs before and after the executor runs the target
+///
+/// The hooks run in the order of their [`ExecutorHook::priority`], from the highest to the lowest.
 pub trait ExecutorHooksTuple<S>
 where
     S: UsesInput,
@@ -46,6 +66,17 @@ where
     fn pre_exec_all(&mut self, state: &mut S, input: &S::Input);
     /// The hooks that runs after runs the target
     fn post_exec_all(&mut self, state: &mut S, input: &S::Input);
+
+    /// The highest [`ExecutorHook::priority`] of these hooks, `None` if there are no hooks
+    fn max_priority(&self) -> Option<i32>;
+    /// The highest [`ExecutorHook::priority`] of these hooks that is lower than `priority`
+    fn next_priority_below(&self, priority: i32) -> Option<i32>;
+    /// Init the hooks with the given priority
+    fn init_with_priority<E: HasObservers>(&mut self, priority: i32, state: &mut S);
+    /// Runs the hooks with the given priority before the target
+    fn pre_exec_with_priority(&mut self, priority: i32, state: &mut S, input: &S::Input);
+    /// Runs the hooks with the given priority after the target
+    fn post_exec_with_priority(&mut self, priority: i32, state: &mut S, input: &S::Input);
 }
 
 impl<S> ExecutorHooksTuple<S> for ()
@@ -55,6 +86,16 @@ where
     fn init_all<E: HasObservers>(&mut self, _state: &mut S) {}
     fn pre_exec_all(&mut self, _state: &mut S, _input: &S::Input) {}
     fn post_exec_all(&mut self, _state: &mut S, _input: &S::Input) {}
+
+    fn max_priority(&self) -> Option<i32> {
+        None
+    }
+    fn next_priority_below(&self, _priority: i32) -> Option<i32> {
+        None
+    }
+    fn init_with_priority<E: HasObservers>(&mut self, _priority: i32, _state: &mut S) {}
+    fn pre_exec_with_priority(&mut self, _priority: i32, _state: &mut S, _input: &S::Input) {}
+    fn post_exec_with_priority(&mut self, _priority: i32, _state: &mut S, _input: &S::Input) {}
 }
 
 impl<Head, Tail, S> ExecutorHooksTuple<S> for (Head, Tail)
@@ -64,17 +105,122 @@ where
     Tail: ExecutorHooksTuple<S>,
 {
     fn init_all<E: HasObservers>(&mut self, state: &mut S) {
-        self.0.init::<E>(state);
-        self.1.init_all::<E>(state);
+        let mut priority = self.max_priority();
+        while let Some(p) = priority {
+            self.init_with_priority::<E>(p, state);
+            priority = self.next_priority_below(p);
+        }
     }
 
     fn pre_exec_all(&mut self, state: &mut S, input: &S::Input) {
-        self.0.pre_exec(state, input);
-        self.1.pre_exec_all(state, input);
+        let mut priority = self.max_priority();
+        while let Some(p) = priority {
+            self.pre_exec_with_priority(p, state, input);
+            priority = self.next_priority_below(p);
+        }
     }
 
     fn post_exec_all(&mut self, state: &mut S, input: &S::Input) {
-        self.0.post_exec(state, input);
-        self.1.post_exec_all(state, input);
+        let mut priority = self.max_priority();
+        while let Some(p) = priority {
+            self.post_exec_with_priority(p, state, input);
+            priority = self.next_priority_below(p);
+        }
+    }
+
+    fn max_priority(&self) -> Option<i32> {
+        let head = self.0.priority();
+        Some(self.1.max_priority().map_or(head, |tail| tail.max(head)))
+    }
+
+    fn next_priority_below(&self, priority: i32) -> Option<i32> {
+        let head = self.0.priority();
+        let tail = self.1.next_priority_below(priority);
+        if head < priority {
+            Some(tail.map_or(head, |tail| tail.max(head)))
+        } else {
+            tail
+        }
+    }
+
+    fn init_with_priority<E: HasObservers>(&mut self, priority: i32, state: &mut S) {
+        if self.0.priority() == priority {
+            self.0.init::<E>(state);
+        }
+        self.1.init_with_priority::<E>(priority, state);
+    }
+
+    fn pre_exec_with_priority(&mut self, priority: i32, state: &mut S, input: &S::Input) {
+        if self.0.priority() == priority {
+            self.0.pre_exec(state, input);
+        }
+        self.1.pre_exec_with_priority(priority, state, input);
+    }
+
+    fn post_exec_with_priority(&mut self, priority: i32, state: &mut S, input: &S::Input) {
+        if self.0.priority() == priority {
+            self.0.post_exec(state, input);
+        }
+        self.1.post_exec_with_priority(priority, state, input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use crate::{
+        executors::{
+            hooks::{ExecutorHook, ExecutorHooksTuple, COVERAGE_RESET_HOOK_PRIORITY},
+            HasObservers,
+        },
+        inputs::NopInput,
+        state::NopState,
+    };
+
+    /// Records the order in which the hooks run
+    struct OrderHook<'a> {
+        id: u8,
+        priority: i32,
+        log: &'a RefCell<Vec<u8>>,
+    }
+
+    impl ExecutorHook<NopState<NopInput>> for OrderHook<'_> {
+        fn init<E: HasObservers>(&mut self, _state: &mut NopState<NopInput>) {}
+
+        fn pre_exec(&mut self, _state: &mut NopState<NopInput>, _input: &NopInput) {
+            self.log.borrow_mut().push(self.id);
+        }
+
+        fn post_exec(&mut self, _state: &mut NopState<NopInput>, _input: &NopInput) {}
+
+        fn priority(&self) -> i32 {
+            self.priority
+        }
+    }
+
+    #[test]
+    fn test_hook_priority() {
+        let log = RefCell::new(Vec::new());
+        let hook = |id, priority| OrderHook {
+            id,
+            priority,
+            log: &log,
+        };
+        let mut hooks = (
+            hook(0, 0),
+            (
+                hook(1, -5),
+                (
+                    hook(2, COVERAGE_RESET_HOOK_PRIORITY),
+                    (hook(3, 0), (hook(4, 7), ())),
+                ),
+            ),
+        );
+
+        let mut state = NopState::new();
+        hooks.pre_exec_all(&mut state, &NopInput {});
+        assert_eq!(*log.borrow(), [2, 4, 0, 3, 1]);
     }
 }
@@ -1,6 +1,5 @@
 //! Executors take input, and run it in the target.
 
-#[cfg(unix)]
 use alloc::vec::Vec;
 use core::{fmt::Debug, time::Duration};
 
@@ -144,6 +143,33 @@ where
     }
 }
 
+/// An [`Executor`] that can run a whole batch of inputs in one go, e.g., all of them in a single
+/// fork server round, to save the per-execution overhead.
+///
+/// See [`crate::stages::MutationalStage::perform_mutational_batched`].
+pub trait BatchExecutor<EM, Z>: Executor<EM, Z> + HasObservers
+where
+    EM: UsesState<State = Self::State>,
+    Z: UsesState<State = Self::State>,
+{
+    /// Runs all `inputs`, returning the [`ExitKind`] of each of them, in order.
+    /// Like [`Executor::run_target`], this should also count the executions.
+    fn run_batch(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        inputs: &[Self::Input],
+    ) -> Result<Vec<ExitKind>, Error>;
+
+    /// Loads what was observed while running the `idx`-th input of the last batch into the observers,
+    /// so that its results can be processed.
+    ///
+    /// This is called in between the `pre_exec` and `post_exec` of the observers.
+    fn load_batch_observations(&mut self, state: &mut Self::State, idx: usize)
+        -> Result<(), Error>;
+}
+
 /// A trait that allows to get/set an `Executor`'s timeout thresold
 pub trait HasTimeout {
     /// Get a timeout
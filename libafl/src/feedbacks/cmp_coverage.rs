@@ -0,0 +1,268 @@
+//! The [`CmpCoverageFeedback`] considers runs interesting that reach comparisons never seen before.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::ToString,
+    vec::Vec,
+};
+
+use libafl_bolts::{
+    tuples::{Handle, Handled, MatchName, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+use crate::{
+    corpus::Testcase,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverHandle, StateInitializer},
+    observers::{CmpMap, CmpObserver},
+    Error, HasNamedMetadata,
+};
+
+/// The prefix of the metadata names
+pub const CMP_COVERAGE_FEEDBACK_PREFIX: &str = "cmpcoveragefeedback_metadata_";
+
+/// The comparisons seen by a [`CmpCoverageFeedback`] so far, as a bitset of their indices
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct CmpCoverageMetadata {
+    seen: Vec<u64>,
+}
+
+libafl_bolts::impl_serdeany!(CmpCoverageMetadata);
+
+impl CmpCoverageMetadata {
+    /// Create a new, empty [`CmpCoverageMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns if the comparison at `idx` has been seen before
+    #[must_use]
+    pub fn is_seen(&self, idx: usize) -> bool {
+        self.seen
+            .get(idx / 64)
+            .is_some_and(|word| word & (1 << (idx % 64)) != 0)
+    }
+
+    /// Marks the comparison at `idx` as seen
+    pub fn set_seen(&mut self, idx: usize) {
+        if self.seen.len() <= idx / 64 {
+            self.seen.resize(idx / 64 + 1, 0);
+        }
+        self.seen[idx / 64] |= 1 << (idx % 64);
+    }
+
+    /// The number of comparisons seen so far
+    #[must_use]
+    pub fn seen_count(&self) -> usize {
+        self.seen
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns if any of the first `count` comparisons of the `map` was executed, but not seen before
+    #[must_use]
+    pub fn has_unseen<CM>(&self, map: &CM, count: usize) -> bool
+    where
+        CM: CmpMap,
+    {
+        (0..count.min(map.len())).any(|idx| map.executions_for(idx) > 0 && !self.is_seen(idx))
+    }
+
+    /// Marks the executed comparisons among the first `count` comparisons of the `map` as seen
+    pub fn add_executed<CM>(&mut self, map: &CM, count: usize)
+    where
+        CM: CmpMap,
+    {
+        for idx in 0..count.min(map.len()) {
+            if map.executions_for(idx) > 0 {
+                self.set_seen(idx);
+            }
+        }
+    }
+}
+
+/// A [`CmpCoverageFeedback`] keeps track of the comparisons executed during the campaign,
+/// and considers a run interesting if it executes a comparison that was not seen before,
+/// i.e., if [`CmpMap::executions_for`] is non-zero for a new index of the [`CmpObserver`]'s map.
+///
+/// This rewards reaching new comparisons, even before they are solved.
+/// Combine it with an edges feedback using, e.g., [`crate::feedback_or`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CmpCoverageFeedback<O> {
+    name: Cow<'static, str>,
+    o_ref: Handle<O>,
+    #[cfg(feature = "track_hit_feedbacks")]
+    // The previous run's result of `Self::is_interesting`
+    last_result: Option<bool>,
+}
+
+impl<O> CmpCoverageFeedback<O>
+where
+    O: Named,
+{
+    /// Returns a new [`CmpCoverageFeedback`] for the comparisons of the given observer
+    #[must_use]
+    pub fn new(observer: &O) -> Self {
+        Self {
+            name: Cow::Owned(CMP_COVERAGE_FEEDBACK_PREFIX.to_owned() + observer.name()),
+            o_ref: observer.handle(),
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+        }
+    }
+}
+
+impl<O, S> StateInitializer<S> for CmpCoverageFeedback<O>
+where
+    S: HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        state.add_named_metadata(&self.name, CmpCoverageMetadata::new());
+        Ok(())
+    }
+}
+
+impl<O, EM, I, OT, S> Feedback<EM, I, OT, S> for CmpCoverageFeedback<O>
+where
+    O: CmpObserver,
+    O::Map: CmpMap,
+    OT: MatchName,
+    S: HasNamedMetadata,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or_else(|| Error::key_not_found("CmpObserver not found".to_string()))?;
+        let meta = state
+            .named_metadata_map()
+            .get::<CmpCoverageMetadata>(&self.name)
+            .ok_or_else(|| Error::key_not_found("CmpCoverageMetadata not found".to_string()))?;
+
+        let res = meta.has_unseen(observer.cmp_map(), observer.usable_count());
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+
+    fn append_metadata(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        _testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        let observer = observers
+            .get(&self.o_ref)
+            .ok_or_else(|| Error::key_not_found("CmpObserver not found".to_string()))?;
+        let meta = state
+            .named_metadata_map_mut()
+            .get_mut::<CmpCoverageMetadata>(&self.name)
+            .ok_or_else(|| Error::key_not_found("CmpCoverageMetadata not found".to_string()))?;
+
+        meta.add_executed(observer.cmp_map(), observer.usable_count());
+        Ok(())
+    }
+}
+
+impl<O> Named for CmpCoverageFeedback<O> {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<O> HasObserverHandle for CmpCoverageFeedback<O> {
+    type Observer = O;
+
+    #[inline]
+    fn observer_handle(&self) -> &Handle<O> {
+        &self.o_ref
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use libafl_bolts::Error;
+
+    use crate::{
+        feedbacks::CmpCoverageMetadata,
+        observers::{CmpMap, CmpValues},
+    };
+
+    /// The number of executions of each cmp
+    #[derive(Debug)]
+    struct ExecutionsCmpMap(Vec<usize>);
+
+    impl CmpMap for ExecutionsCmpMap {
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn executions_for(&self, idx: usize) -> usize {
+            self.0[idx]
+        }
+
+        fn usable_executions_for(&self, idx: usize) -> usize {
+            self.0[idx]
+        }
+
+        fn values_of(&self, _idx: usize, _execution: usize) -> Option<CmpValues> {
+            None
+        }
+
+        fn reset(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_cmp_coverage_metadata() {
+        let mut meta = CmpCoverageMetadata::new();
+        let mut map = ExecutionsCmpMap(vec![0, 3, 0, 1]);
+
+        // the first run reaches two new cmps
+        assert!(meta.has_unseen(&map, map.0.len()));
+        meta.add_executed(&map, map.0.len());
+        assert_eq!(meta.seen_count(), 2);
+        assert!(meta.is_seen(1) && meta.is_seen(3));
+
+        // reaching the same cmps again is not interesting
+        assert!(!meta.has_unseen(&map, map.0.len()));
+
+        map.0[2] = 1;
+        assert!(meta.has_unseen(&map, map.0.len()));
+        // unless the cmp is not usable
+        assert!(!meta.has_unseen(&map, 2));
+
+        meta.set_seen(100);
+        assert!(meta.is_seen(100));
+        assert!(!meta.is_seen(99));
+    }
+}
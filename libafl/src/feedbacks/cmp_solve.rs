@@ -0,0 +1,233 @@
+//! The [`CmpSolveFeedback`] considers runs interesting that satisfy comparisons the original input failed.
+
+use alloc::borrow::Cow;
+
+use libafl_bolts::Named;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "track_hit_feedbacks")]
+use crate::feedbacks::premature_last_result_err;
+use crate::{
+    corpus::Testcase,
+    executors::ExitKind,
+    feedbacks::{CmpCoverageMetadata, Feedback, StateInitializer},
+    observers::AFLppCmpValuesMetadata,
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// The name of the [`CmpSolveFeedback`], and of the [`CmpCoverageMetadata`] holding the cmps it solved so far
+pub const CMP_SOLVE_FEEDBACK_NAME: &str = "CmpSolveFeedback";
+
+/// A [`CmpSolveFeedback`] closes the input-to-state loop: it considers a run interesting if it satisfied a
+/// comparison that the original input failed, according to the [`AFLppCmpValuesMetadata`] of the state,
+/// see [`AFLppCmpValuesMetadata::solved_cmps`].
+///
+/// This rewards crossing checks, e.g., nested magic values, even before new edges are reached.
+/// Each cmp is rewarded once, the solved cmps are kept in a named [`CmpCoverageMetadata`].
+/// Combine it with an edges feedback using, e.g., [`crate::feedback_or`].
+///
+/// The `new_cmpvals` of the metadata have to stem from the current run, so the executor has to run an
+/// `AFLppCmpLogObserver` for mutated inputs, next to the one for the original input of the testcase.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CmpSolveFeedback {
+    name: Cow<'static, str>,
+    #[cfg(feature = "track_hit_feedbacks")]
+    // The previous run's result of `Self::is_interesting`
+    last_result: Option<bool>,
+}
+
+impl CmpSolveFeedback {
+    /// Creates a new [`CmpSolveFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: Cow::Borrowed(CMP_SOLVE_FEEDBACK_NAME),
+            #[cfg(feature = "track_hit_feedbacks")]
+            last_result: None,
+        }
+    }
+}
+
+impl Default for CmpSolveFeedback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> StateInitializer<S> for CmpSolveFeedback
+where
+    S: HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        state.add_named_metadata(&self.name, CmpCoverageMetadata::new());
+        Ok(())
+    }
+}
+
+impl<EM, I, OT, S> Feedback<EM, I, OT, S> for CmpSolveFeedback
+where
+    S: HasMetadata + HasNamedMetadata,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error> {
+        let solved = state.named_metadata::<CmpCoverageMetadata>(&self.name)?;
+        let res = state
+            .metadata::<AFLppCmpValuesMetadata>()
+            .is_ok_and(|meta| {
+                meta.solved_cmps()
+                    .into_iter()
+                    .any(|idx| !solved.is_seen(idx))
+            });
+        #[cfg(feature = "track_hit_feedbacks")]
+        {
+            self.last_result = Some(res);
+        }
+        Ok(res)
+    }
+
+    #[cfg(feature = "track_hit_feedbacks")]
+    fn last_result(&self) -> Result<bool, Error> {
+        self.last_result.ok_or(premature_last_result_err())
+    }
+
+    fn append_metadata(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        _testcase: &mut Testcase<I>,
+    ) -> Result<(), Error> {
+        let Ok(meta) = state.metadata::<AFLppCmpValuesMetadata>() else {
+            return Ok(());
+        };
+        let newly_solved = meta.solved_cmps();
+        let solved = state.named_metadata_mut::<CmpCoverageMetadata>(&self.name)?;
+        for idx in newly_solved {
+            solved.set_seen(idx);
+        }
+        Ok(())
+    }
+}
+
+impl Named for CmpSolveFeedback {
+    #[inline]
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use hashbrown::HashMap;
+    use libafl_bolts::{rands::StdRand, Error};
+
+    use crate::{
+        corpus::{InMemoryCorpus, Testcase},
+        executors::ExitKind,
+        feedbacks::{CmpSolveFeedback, Feedback, StateInitializer},
+        inputs::BytesInput,
+        observers::{AFLppCmpValuesMetadata, CmpMap, CmpValues},
+        state::StdState,
+        HasMetadata,
+    };
+
+    /// The values logged for each execution of each cmp
+    #[derive(Debug)]
+    struct DummyCmpMap(Vec<Vec<CmpValues>>);
+
+    impl CmpMap for DummyCmpMap {
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn executions_for(&self, idx: usize) -> usize {
+            self.0[idx].len()
+        }
+
+        fn usable_executions_for(&self, idx: usize) -> usize {
+            self.0[idx].len()
+        }
+
+        fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues> {
+            self.0[idx].get(execution).cloned()
+        }
+
+        fn reset(&mut self) -> Result<(), Error> {
+            self.0.iter_mut().for_each(Vec::clear);
+            Ok(())
+        }
+    }
+
+    /// The logged values of the map by cmp, like the cmplog observer stores them
+    fn cmpvals_of(map: &DummyCmpMap) -> HashMap<usize, Vec<CmpValues>> {
+        (0..map.len())
+            .filter(|idx| map.usable_executions_for(*idx) > 0)
+            .map(|idx| {
+                let values = (0..map.usable_executions_for(idx))
+                    .filter_map(|execution| map.values_of(idx, execution))
+                    .collect();
+                (idx, values)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cmp_solve_feedback() -> Result<(), Error> {
+        let orig = DummyCmpMap(vec![
+            vec![CmpValues::U32((0x4d5a, 0x1234, false))],
+            vec![CmpValues::U8((1, 1, false))],
+            vec![CmpValues::U16((7, 8, false))],
+            vec![],
+        ]);
+        let new = DummyCmpMap(vec![
+            // the magic value check flips from unequal to equal
+            vec![
+                CmpValues::U32((0x4d5a, 0x1234, false)),
+                CmpValues::U32((0x4d5a, 0x4d5a, false)),
+            ],
+            // already satisfied by the original input
+            vec![CmpValues::U8((1, 1, false))],
+            // still failing
+            vec![CmpValues::U16((7, 9, false))],
+            // not reached by the original input
+            vec![CmpValues::U8((2, 2, false))],
+        ]);
+
+        let mut meta = AFLppCmpValuesMetadata::new();
+        meta.orig_cmpvals = cmpvals_of(&orig);
+        meta.new_cmpvals = cmpvals_of(&new);
+        assert_eq!(meta.solved_cmps(), vec![0]);
+
+        let mut feedback = CmpSolveFeedback::new();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut (),
+            &mut (),
+        )?;
+        feedback.init_state(&mut state)?;
+        let input = BytesInput::new(vec![]);
+
+        // without logged cmps, nothing is solved
+        assert!(!feedback.is_interesting(&mut state, &mut (), &input, &(), &ExitKind::Ok)?);
+
+        state.add_metadata(meta);
+        assert!(feedback.is_interesting(&mut state, &mut (), &input, &(), &ExitKind::Ok)?);
+        feedback.append_metadata(&mut state, &mut (), &(), &mut Testcase::new(input.clone()))?;
+
+        // solving the same cmp again is not rewarded
+        assert!(!feedback.is_interesting(&mut state, &mut (), &input, &(), &ExitKind::Ok)?);
+
+        Ok(())
+    }
+}
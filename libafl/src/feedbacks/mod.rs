@@ -9,6 +9,8 @@ use alloc::borrow::Cow;
 use alloc::vec::Vec;
 use core::{fmt::Debug, marker::PhantomData};
 
+pub use cmp_coverage::{CmpCoverageFeedback, CmpCoverageMetadata};
+pub use cmp_solve::CmpSolveFeedback;
 #[cfg(feature = "std")]
 pub use concolic::ConcolicFeedback;
 pub use differential::DiffFeedback;
@@ -31,6 +33,8 @@ use crate::{corpus::Testcase, executors::ExitKind, observers::TimeObserver, Erro
 #[cfg(feature = "std")]
 pub mod capture_feedback;
 
+pub mod cmp_coverage;
+pub mod cmp_solve;
 #[cfg(feature = "std")]
 pub mod concolic;
 #[cfg(feature = "std")]
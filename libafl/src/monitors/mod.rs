@@ -957,6 +957,10 @@ pub enum PerfFeature {
     /// Time spent getting the feedback from `is_interesting` from all objectives
     GetObjectivesInterestingAll = 9,
 
+    /// Time spent by a `StdCmpObserver` adding the logged comparisons to the `CmpValuesMetadata`.
+    /// This is a part of [`PerfFeature::PostExecObservers`].
+    CmpObserverPostExec = 10,
+
     /// Used as a counter to know how many elements are in [`PerfFeature`]. Must be the
     /// last value in the enum.
     Count, // !! No more values here since Count is last! !!
@@ -981,6 +985,7 @@ impl From<PerfFeature> for usize {
             PerfFeature::GetObjectivesInterestingAll => {
                 PerfFeature::GetObjectivesInterestingAll as usize
             }
+            PerfFeature::CmpObserverPostExec => PerfFeature::CmpObserverPostExec as usize,
             PerfFeature::Count => PerfFeature::Count as usize,
         }
     }
@@ -1000,6 +1005,7 @@ impl From<usize> for PerfFeature {
             7 => PerfFeature::PostExecObservers,
             8 => PerfFeature::GetFeedbackInterestingAll,
             9 => PerfFeature::GetObjectivesInterestingAll,
+            10 => PerfFeature::CmpObserverPostExec,
             _ => panic!("Unknown PerfFeature: {val}"),
         }
     }
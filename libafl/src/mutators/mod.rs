@@ -105,6 +105,15 @@ pub trait Mutator<I, S>: Named {
     fn post_exec(&mut self, _state: &mut S, _new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
         Ok(())
     }
+
+    /// The names of the mutations applied by the last [`Mutator::mutate`], in the order they were applied,
+    /// if this mutator keeps a log of them, e.g., the [`LoggerScheduledMutator`].
+    ///
+    /// Returns `None` by default.
+    #[inline]
+    fn mutation_log(&self) -> Option<Vec<Cow<'static, str>>> {
+        None
+    }
 }
 
 /// A mutator that takes input, and returns a vector of mutated inputs.
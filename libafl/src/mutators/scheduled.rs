@@ -10,7 +10,7 @@ use core::{
 use libafl_bolts::{
     rands::Rand,
     tuples::{tuple_list, tuple_list_type, HasConstLen, NamedTuple},
-    Named,
+    HasLen, Named,
 };
 use serde::{Deserialize, Serialize};
 
@@ -199,6 +199,89 @@ pub fn tokens_mutations() -> tuple_list_type!(TokenInsert, TokenReplace) {
     tuple_list!(TokenInsert::new(), TokenReplace::new())
 }
 
+/// A [`Mutator`] that applies the embedded mutators in turn, one per call, e.g., for the
+/// [`crate::stages::RoundRobinMutationalStage`].
+///
+/// Only the mutator of the last call gets the `post_exec` call.
+#[derive(Clone, Debug)]
+pub struct RoundRobinMutator<MT> {
+    name: Cow<'static, str>,
+    mutators: MT,
+    next: usize,
+    last: Option<usize>,
+}
+
+impl<MT> Named for RoundRobinMutator<MT> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<I, MT, S> Mutator<I, S> for RoundRobinMutator<MT>
+where
+    MT: MutatorsTuple<I, S>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let idx = self.next;
+        self.next = (idx + 1) % self.mutators.len();
+        self.last = Some(idx);
+        self.mutators.get_and_mutate(idx.into(), state, input)
+    }
+
+    fn post_exec(&mut self, state: &mut S, new_corpus_id: Option<CorpusId>) -> Result<(), Error> {
+        match self.last.take() {
+            Some(idx) => self.mutators.get_and_post_exec(idx, state, new_corpus_id),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<MT> RoundRobinMutator<MT>
+where
+    MT: HasLen,
+{
+    /// Create a new [`RoundRobinMutator`], starting with the first of the `mutators`
+    ///
+    /// # Errors
+    /// Will return [`Error::IllegalArgument`] if there are no mutators.
+    pub fn new(mutators: MT) -> Result<Self, Error> {
+        if mutators.is_empty() {
+            return Err(Error::illegal_argument(
+                "A RoundRobinMutator needs at least one mutator",
+            ));
+        }
+        Ok(Self {
+            name: Cow::Borrowed("RoundRobinMutator"),
+            mutators,
+            next: 0,
+            last: None,
+        })
+    }
+
+    /// The index of the mutator used for the next call
+    #[must_use]
+    pub fn next_index(&self) -> usize {
+        self.next
+    }
+
+    /// Continue with the mutator at `idx`, modulo the number of mutators
+    pub fn set_next_index(&mut self, idx: usize) {
+        self.next = idx % self.mutators.len();
+    }
+}
+
+impl<MT> RoundRobinMutator<MT> {
+    /// The mutators
+    pub fn mutators(&self) -> &MT {
+        &self.mutators
+    }
+
+    /// The mutators (mutable)
+    pub fn mutators_mut(&mut self) -> &mut MT {
+        &mut self.mutators
+    }
+}
+
 /// A logging [`Mutator`] that wraps around a [`StdScheduledMutator`].
 #[derive(Debug)]
 pub struct LoggerScheduledMutator<SM> {
@@ -238,6 +321,15 @@ where
         self.mutation_log.clear();
         Ok(())
     }
+
+    fn mutation_log(&self) -> Option<Vec<Cow<'static, str>>> {
+        Some(
+            self.mutation_log
+                .iter()
+                .filter_map(|idx| self.scheduled.mutations().name(idx.0).cloned())
+                .collect(),
+        )
+    }
 }
 
 impl<SM> ComposedByMutations for LoggerScheduledMutator<SM>
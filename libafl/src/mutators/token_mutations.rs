@@ -430,7 +430,10 @@ impl TokenReplace {
 /// A `I2SRandReplace` [`Mutator`] replaces a random matching input-2-state comparison operand with the other.
 /// It needs a valid [`CmpValuesMetadata`] in the state.
 #[derive(Debug, Default)]
-pub struct I2SRandReplace;
+pub struct I2SRandReplace {
+    prefix_replacements: bool,
+    nul_terminated: bool,
+}
 
 impl<I, S> Mutator<I, S> for I2SRandReplace
 where
@@ -458,15 +461,30 @@ where
 
         let idx = state.rand_mut().below(cmps_len);
 
+        let prefix_idx = if self.prefix_replacements {
+            let meta = state.metadata_map().get::<CmpValuesMetadata>().unwrap();
+            let prefixes = meta.list[idx]
+                .nul_trimmed(self.nul_terminated)
+                .byte_prefixes()
+                .count();
+            NonZero::new(prefixes).map(|prefixes| state.rand_mut().below(prefixes))
+        } else {
+            None
+        };
+
         let off = state.rand_mut().below(size);
         let len = input.bytes().len();
         let bytes = input.bytes_mut();
 
         let meta = state.metadata_map().get::<CmpValuesMetadata>().unwrap();
-        let cmp_values = &meta.list[idx];
+        let cmp_values = if self.nul_terminated {
+            Cow::Owned(meta.list[idx].nul_trimmed(true))
+        } else {
+            Cow::Borrowed(&meta.list[idx])
+        };
 
         let mut result = MutationResult::Skipped;
-        match cmp_values {
+        match &*cmp_values {
             CmpValues::U8((v1, v2, v1_is_const)) => {
                 for byte in bytes.iter_mut().take(len).skip(off) {
                     if !v1_is_const && *byte == *v1 {
@@ -567,6 +585,24 @@ where
                     }
                 }
             }
+            CmpValues::Bytes(_) if prefix_idx.is_some() => {
+                let (p0, p1) = cmp_values.byte_prefixes().nth(prefix_idx.unwrap()).unwrap();
+                let size = p0.len();
+                if len >= size {
+                    let bytes = input.bytes_mut();
+                    for i in off..=len - size {
+                        if bytes[i..i + size] == *p0 {
+                            bytes[i..i + size].copy_from_slice(p1);
+                            result = MutationResult::Mutated;
+                            break;
+                        } else if bytes[i..i + size] == *p1 {
+                            bytes[i..i + size].copy_from_slice(p0);
+                            result = MutationResult::Mutated;
+                            break;
+                        }
+                    }
+                }
+            }
             CmpValues::Bytes(v) => {
                 'outer: for i in off..len {
                     let mut size = core::cmp::min(v.0.len(), len - i);
@@ -593,12 +629,33 @@ where
                     }
                 }
             }
+            CmpValues::Vector { v0, v1, .. } => {
+                result = replace_vector_operand(input.bytes_mut(), off, v0, v1);
+            }
         }
 
         Ok(result)
     }
 }
 
+/// Replaces the first occurrence of an operand of a [`CmpValues::Vector`] at or after `off` with the other one
+fn replace_vector_operand(bytes: &mut [u8], off: usize, v0: &[u8], v1: &[u8]) -> MutationResult {
+    let size = v0.len();
+    if size == 0 || size != v1.len() || bytes.len() < size {
+        return MutationResult::Skipped;
+    }
+    for i in off..=bytes.len() - size {
+        if bytes[i..i + size] == *v0 {
+            bytes[i..i + size].copy_from_slice(v1);
+            return MutationResult::Mutated;
+        } else if bytes[i..i + size] == *v1 {
+            bytes[i..i + size].copy_from_slice(v0);
+            return MutationResult::Mutated;
+        }
+    }
+    MutationResult::Skipped
+}
+
 impl Named for I2SRandReplace {
     fn name(&self) -> &Cow<'static, str> {
         static NAME: Cow<'static, str> = Cow::Borrowed("I2SRandReplace");
@@ -610,7 +667,27 @@ impl I2SRandReplace {
     /// Creates a new `I2SRandReplace` struct.
     #[must_use]
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Creates a new `I2SRandReplace` struct that replaces a random-length common prefix of
+    /// [`CmpValues::Bytes`] operands, see [`CmpValues::byte_prefixes`], instead of the whole buffer.
+    /// This helps with length-bounded comparisons, like `strncmp`, where replacing the full buffer overshoots.
+    #[must_use]
+    pub fn with_prefix_replacements() -> Self {
+        Self {
+            prefix_replacements: true,
+            ..Self::default()
+        }
+    }
+
+    /// Trims the operands of [`CmpValues::Bytes`] at their first nul byte before replacing them,
+    /// see [`CmpValues::nul_trimmed`], for targets comparing nul-terminated strings, like `strcmp`,
+    /// where the logged buffer is wider than the string. Off by default.
+    #[must_use]
+    pub fn nul_terminated(mut self, nul_terminated: bool) -> Self {
+        self.nul_terminated = nul_terminated;
+        self
     }
 }
 
@@ -801,6 +878,9 @@ where
                     }
                 }
             }
+            CmpValues::Vector { v0, v1, .. } => {
+                result = replace_vector_operand(input.bytes_mut(), off, &v0, &v1);
+            }
         }
 
         Ok(result)
@@ -1,16 +1,21 @@
 //! The `CmpObserver` provides access to the logged values of CMP instructions
-use alloc::{borrow::Cow, vec::Vec};
+use alloc::{borrow::Cow, boxed::Box, vec::Vec};
 use core::{
-    fmt::Debug,
-    ops::{Deref, DerefMut},
+    cmp::Reverse,
+    fmt::{self, Debug},
+    ops::{BitOr, Deref, DerefMut},
 };
 
 use c2rust_bitfields::BitfieldStruct;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use libafl_bolts::{ownedref::OwnedRefMut, AsSlice, HasLen, Named};
 use serde::{Deserialize, Serialize};
 
-use crate::{executors::ExitKind, observers::Observer, Error, HasMetadata};
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+use crate::{
+    executors::ExitKind, observers::Observer, state::MaybeHasClientPerfMonitor, Error, HasMetadata,
+};
 
 /// A bytes string for cmplog with up to 32 elements.
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -27,6 +32,25 @@ impl CmplogBytes {
         debug_assert!(len <= 32, "Len too big: {len}, max: 32");
         CmplogBytes { buf, len }
     }
+
+    /// Iterates over the bytes
+    pub fn iter(&self) -> core::slice::Iter<'_, u8> {
+        self.as_ref().iter()
+    }
+
+    /// The bytes up to, but excluding, the first nul byte, e.g., for the operand of a `strcmp`,
+    /// whose logged buffer may hold garbage after the terminator.
+    #[must_use]
+    pub fn trim_at_nul(&self) -> Self {
+        let len = self
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(self.len as usize);
+        Self {
+            buf: self.buf,
+            len: len as u8,
+        }
+    }
 }
 
 impl<'a> AsSlice<'a> for CmplogBytes {
@@ -45,6 +69,22 @@ impl HasLen for CmplogBytes {
     }
 }
 
+impl AsRef<[u8]> for CmplogBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf[0..(self.len as usize)]
+    }
+}
+
+impl<'a> IntoIterator for &'a CmplogBytes {
+    type Item = &'a u8;
+
+    type IntoIter = core::slice::Iter<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 /// Compare values collected during a run
 #[derive(Eq, PartialEq, Debug, Serialize, Deserialize, Clone)]
 pub enum CmpValues {
@@ -58,6 +98,16 @@ pub enum CmpValues {
     U64((u64, u64, bool)),
     /// Two vecs of u8 values/byte
     Bytes((CmplogBytes, CmplogBytes)),
+    /// A wide (e.g., AVX or AVX-512) vector comparison, of operands with `width` bits each.
+    /// The bytes are in little endian order, like the lanes of the vector.
+    Vector {
+        /// The width of each operand, in bits
+        width: u16,
+        /// The bytes of side 1 of the comparison
+        v0: Vec<u8>,
+        /// The bytes of side 2 of the comparison
+        v1: Vec<u8>,
+    },
 }
 
 impl CmpValues {
@@ -70,6 +120,18 @@ impl CmpValues {
         )
     }
 
+    /// Returns the width of the operands of numeric values, in bytes, or `None` for non-numeric values
+    #[must_use]
+    pub fn numeric_bytes(&self) -> Option<usize> {
+        match self {
+            CmpValues::U8(_) => Some(1),
+            CmpValues::U16(_) => Some(2),
+            CmpValues::U32(_) => Some(4),
+            CmpValues::U64(_) => Some(8),
+            CmpValues::Bytes(_) | CmpValues::Vector { .. } => None,
+        }
+    }
+
     /// Converts the value to a u64 tuple
     #[must_use]
     pub fn to_u64_tuple(&self) -> Option<(u64, u64, bool)> {
@@ -78,13 +140,476 @@ impl CmpValues {
             CmpValues::U16(t) => Some((u64::from(t.0), u64::from(t.1), t.2)),
             CmpValues::U32(t) => Some((u64::from(t.0), u64::from(t.1), t.2)),
             CmpValues::U64(t) => Some(*t),
-            CmpValues::Bytes(_) => None,
+            CmpValues::Bytes(_) | CmpValues::Vector { .. } => None,
+        }
+    }
+
+    /// A key for a total order of [`CmpValues`] that is stable across runs, builds and platforms,
+    /// e.g., to sort comparisons before serializing or diffing them: `values.sort_by_key(CmpValues::sort_key)`.
+    ///
+    /// The key is `(variant, side 1, side 2)`, so the variants are ordered like they are declared.
+    /// Numeric operands are ordered by value, with the const flag of side 1 above its 64 bits.
+    /// [`CmpValues::Bytes`] and [`CmpValues::Vector`] operands are ordered by length, then by their first 7 bytes,
+    /// then by a (fixed, FNV-1a) hash of all bytes, and, for vectors, the width, so different operands only
+    /// compare equal on a hash collision.
+    ///
+    /// Does not allocate.
+    #[must_use]
+    pub fn sort_key(&self) -> (u8, u128, u128) {
+        let numeric = |v0: u64, v1: u64, v0_is_const: bool| {
+            (
+                u128::from(v0) | u128::from(v0_is_const) << 64,
+                u128::from(v1),
+            )
+        };
+        let (variant, (side1, side2)) = match self {
+            CmpValues::U8((v0, v1, c)) => (0, numeric(u64::from(*v0), u64::from(*v1), *c)),
+            CmpValues::U16((v0, v1, c)) => (1, numeric(u64::from(*v0), u64::from(*v1), *c)),
+            CmpValues::U32((v0, v1, c)) => (2, numeric(u64::from(*v0), u64::from(*v1), *c)),
+            CmpValues::U64((v0, v1, c)) => (3, numeric(*v0, *v1, *c)),
+            CmpValues::Bytes((v0, v1)) => (
+                4,
+                (
+                    bytes_sort_key(0, v0.as_slice()),
+                    bytes_sort_key(0, v1.as_slice()),
+                ),
+            ),
+            CmpValues::Vector { width, v0, v1 } => {
+                (5, (bytes_sort_key(*width, v0), bytes_sort_key(*width, v1)))
+            }
+        };
+        (variant, side1, side2)
+    }
+
+    /// Returns if both operands are equal, i.e., the comparison is already satisfied.
+    /// For [`CmpValues::Bytes`], the logged slices are compared.
+    /// For [`CmpValues::Vector`], all lanes have to be equal.
+    #[must_use]
+    pub fn has_equal_operands(&self) -> bool {
+        match self {
+            CmpValues::U8(t) => t.0 == t.1,
+            CmpValues::U16(t) => t.0 == t.1,
+            CmpValues::U32(t) => t.0 == t.1,
+            CmpValues::U64(t) => t.0 == t.1,
+            CmpValues::Bytes(t) => t.0.as_slice() == t.1.as_slice(),
+            CmpValues::Vector { v0, v1, .. } => v0 == v1,
+        }
+    }
+
+    /// A cheap estimate of how hard this comparison is to solve with input-to-state replacements,
+    /// e.g., to attempt the cheapest comparisons first. Lower is easier, `0` means the operands are already equal.
+    ///
+    /// The score is the number of bytes in which the operands differ, at the width of the comparison:
+    /// a single-byte equality scores at most `1`, an 8-byte magic value up to `8`.
+    /// For [`CmpValues::Bytes`], each byte by which the lengths of the operands differ counts, too.
+    /// Scores beyond [`u8::MAX`] saturate. See [`CmpValues::difficulty_hint_with_op`] to take the operator into account.
+    #[must_use]
+    pub fn difficulty_hint(&self) -> u8 {
+        let differing = match self {
+            CmpValues::U8(t) => differing_bytes(&[t.0], &[t.1]),
+            CmpValues::U16(t) => differing_bytes(&t.0.to_le_bytes(), &t.1.to_le_bytes()),
+            CmpValues::U32(t) => differing_bytes(&t.0.to_le_bytes(), &t.1.to_le_bytes()),
+            CmpValues::U64(t) => differing_bytes(&t.0.to_le_bytes(), &t.1.to_le_bytes()),
+            CmpValues::Bytes(t) => differing_bytes(t.0.as_slice(), t.1.as_slice()),
+            CmpValues::Vector { v0, v1, .. } => differing_bytes(v0, v1),
+        };
+        u8::try_from(differing).unwrap_or(u8::MAX)
+    }
+
+    /// Like [`CmpValues::difficulty_hint`], but also takes the operator `op` of the comparison into account.
+    ///
+    /// Inequalities are open-ended: there is no single value to replace an operand with, and equal
+    /// operands may not satisfy them, so they score `8` more (and at least `9`). Floating point
+    /// comparisons score `4` more, as their bytes rarely appear in the input as they are.
+    #[must_use]
+    pub fn difficulty_hint_with_op(&self, op: CmpOp) -> u8 {
+        let mut hint = self.difficulty_hint();
+        if op.contains(CmpOp::GREATER) || op.contains(CmpOp::LESSER) {
+            hint = hint.max(1).saturating_add(8);
+        }
+        if op.contains(CmpOp::FP) {
+            hint = hint.saturating_add(4);
+        }
+        hint
+    }
+
+    /// Splits a [`CmpValues::Vector`] into the comparisons of its `lane_size` bytes wide lanes,
+    /// e.g., `4` for a vector of `u32`s, so that the lanes can be solved separately.
+    ///
+    /// Returns `None` for other values, if `lane_size` is not `1`, `2`, `4` or `8`,
+    /// or if the operands can not be split into lanes of this size.
+    #[must_use]
+    pub fn split_lanes(&self, lane_size: usize) -> Option<Vec<CmpValues>> {
+        let CmpValues::Vector { v0, v1, .. } = self else {
+            return None;
+        };
+        if !matches!(lane_size, 1 | 2 | 4 | 8) || v0.len() != v1.len() || v0.len() % lane_size != 0
+        {
+            return None;
+        }
+        Some(
+            v0.chunks_exact(lane_size)
+                .zip(v1.chunks_exact(lane_size))
+                .map(|(l0, l1)| match lane_size {
+                    1 => CmpValues::U8((l0[0], l1[0], false)),
+                    2 => CmpValues::U16((
+                        u16::from_le_bytes(l0.try_into().unwrap()),
+                        u16::from_le_bytes(l1.try_into().unwrap()),
+                        false,
+                    )),
+                    4 => CmpValues::U32((
+                        u32::from_le_bytes(l0.try_into().unwrap()),
+                        u32::from_le_bytes(l1.try_into().unwrap()),
+                        false,
+                    )),
+                    _ => CmpValues::U64((
+                        u64::from_le_bytes(l0.try_into().unwrap()),
+                        u64::from_le_bytes(l1.try_into().unwrap()),
+                        false,
+                    )),
+                })
+                .collect(),
+        )
+    }
+
+    /// If `nul_terminated` is set, trims both operands of [`CmpValues::Bytes`] at their first nul byte,
+    /// see [`CmplogBytes::trim_at_nul`], so that only the meaningful part of a string comparison, like `strcmp`,
+    /// is replaced. Other values, or all values if `nul_terminated` is not set, are returned as they are.
+    #[must_use]
+    pub fn nul_trimmed(&self, nul_terminated: bool) -> Self {
+        match self {
+            CmpValues::Bytes((v0, v1)) if nul_terminated => {
+                CmpValues::Bytes((v0.trim_at_nul(), v1.trim_at_nul()))
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Yields the common-length prefixes of both operands of [`CmpValues::Bytes`], shortest first,
+    /// to cross length-bounded comparisons like `strncmp(s, "GET ", 4)`, where the whole logged buffer is too long.
+    /// Yields nothing for numeric values.
+    pub fn byte_prefixes(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        let (v0, v1): (&[u8], &[u8]) = match self {
+            CmpValues::Bytes((v0, v1)) => (v0.as_slice(), v1.as_slice()),
+            _ => (&[], &[]),
+        };
+        let len = core::cmp::min(v0.len(), v1.len());
+        (1..=len).map(move |n| (&v0[..n], &v1[..n]))
+    }
+
+    /// Returns the candidate operand pairs to solve this comparison with, starting with the values themselves.
+    ///
+    /// If `attr` marks a transform comparison, the operands may have been byteswapped or sign-extended
+    /// before they were compared, so they may not appear in the input as logged. For these, the byteswapped
+    /// pair is added, as well as the pairs truncated to each narrower width of which both operands
+    /// are the sign extension.
+    ///
+    /// If `attr` marks an overflowing comparison, the interesting values are likely at the wrap boundary,
+    /// so the pairs with one operand replaced by a boundary value are added, see [`CmpOp::OVERFLOW`].
+    #[must_use]
+    pub fn normalize(&self, attr: CmpOp) -> Vec<CmpValues> {
+        let mut candidates = vec![self.clone()];
+        if attr.is_overflow() {
+            candidates.extend(self.boundary_candidates());
+        }
+        if !attr.is_transform() {
+            return candidates;
+        }
+
+        let (width, swapped) = match self {
+            CmpValues::U16(t) => (
+                16,
+                CmpValues::U16((t.0.swap_bytes(), t.1.swap_bytes(), t.2)),
+            ),
+            CmpValues::U32(t) => (
+                32,
+                CmpValues::U32((t.0.swap_bytes(), t.1.swap_bytes(), t.2)),
+            ),
+            CmpValues::U64(t) => (
+                64,
+                CmpValues::U64((t.0.swap_bytes(), t.1.swap_bytes(), t.2)),
+            ),
+            _ => return candidates,
+        };
+        candidates.push(swapped);
+
+        let (v0, v1, v0_is_const) = self.to_u64_tuple().unwrap();
+        for narrow in [8, 16, 32] {
+            if narrow >= width
+                || !is_sign_extension(v0, narrow, width)
+                || !is_sign_extension(v1, narrow, width)
+            {
+                continue;
+            }
+            // Truncating is what we want here, we just checked that nothing is lost
+            #[allow(clippy::cast_possible_truncation)]
+            let candidate = match narrow {
+                8 => CmpValues::U8((v0 as u8, v1 as u8, v0_is_const)),
+                16 => CmpValues::U16((v0 as u16, v1 as u16, v0_is_const)),
+                _ => CmpValues::U32((v0 as u32, v1 as u32, v0_is_const)),
+            };
+            candidates.push(candidate);
+        }
+
+        candidates.dedup();
+        candidates
+    }
+
+    /// The numeric pairs with one operand kept and the other replaced by the other operand `+ 1` or `- 1`,
+    /// or the unsigned or signed minimum or maximum of the width of the comparison
+    fn boundary_candidates(&self) -> Vec<CmpValues> {
+        let width = match self {
+            CmpValues::U8(_) => 8,
+            CmpValues::U16(_) => 16,
+            CmpValues::U32(_) => 32,
+            CmpValues::U64(_) => 64,
+            CmpValues::Bytes(_) | CmpValues::Vector { .. } => return vec![],
+        };
+        let (v0, v1, v0_is_const) = self.to_u64_tuple().unwrap();
+        let mask = u64::MAX >> (64 - width);
+        let signed_max = mask >> 1;
+
+        let mut candidates = vec![];
+        for (kept, replaced, kept_first) in [(v0, v1, true), (v1, v0, false)] {
+            for boundary in [
+                replaced.wrapping_add(1) & mask,
+                replaced.wrapping_sub(1) & mask,
+                0,
+                mask,
+                signed_max,
+                signed_max + 1,
+            ] {
+                let (c0, c1) = if kept_first {
+                    (kept, boundary)
+                } else {
+                    (boundary, kept)
+                };
+                // Both operands fit into the width of the comparison
+                #[allow(clippy::cast_possible_truncation)]
+                let candidate = match width {
+                    8 => CmpValues::U8((c0 as u8, c1 as u8, v0_is_const)),
+                    16 => CmpValues::U16((c0 as u16, c1 as u16, v0_is_const)),
+                    32 => CmpValues::U32((c0 as u32, c1 as u32, v0_is_const)),
+                    _ => CmpValues::U64((c0, c1, v0_is_const)),
+                };
+                if candidate != *self && !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+        candidates
+    }
+}
+
+/// Shows numeric operands in hex, at the width of the comparison, and byte operands as hex next
+/// to their printable ASCII characters, e.g., `U32(0x464c457f, 0x00000000, v0 const)` or
+/// `Bytes(7f454c46 |.ELF|, 4d5a |MZ|)`. Use [`Debug`] for the exact values.
+impl fmt::Display for CmpValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, bytes, v0, v1, v0_is_const) = match *self {
+            CmpValues::U8((v0, v1, c)) => ("U8", 1, u64::from(v0), u64::from(v1), c),
+            CmpValues::U16((v0, v1, c)) => ("U16", 2, u64::from(v0), u64::from(v1), c),
+            CmpValues::U32((v0, v1, c)) => ("U32", 4, u64::from(v0), u64::from(v1), c),
+            CmpValues::U64((v0, v1, c)) => ("U64", 8, v0, v1, c),
+            CmpValues::Bytes((ref v0, ref v1)) => {
+                f.write_str("Bytes(")?;
+                fmt_hex_ascii(f, v0.as_ref())?;
+                f.write_str(", ")?;
+                fmt_hex_ascii(f, v1.as_ref())?;
+                return f.write_str(")");
+            }
+            CmpValues::Vector {
+                width,
+                ref v0,
+                ref v1,
+            } => {
+                write!(f, "Vector{width}(")?;
+                fmt_hex_ascii(f, v0)?;
+                f.write_str(", ")?;
+                fmt_hex_ascii(f, v1)?;
+                return f.write_str(")");
+            }
+        };
+        let digits = bytes * 2;
+        write!(
+            f,
+            "{name}({v0:#0width$x}, {v1:#0width$x}",
+            width = digits + 2
+        )?;
+        if v0_is_const {
+            f.write_str(", v0 const")?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Writes `bytes` as hex, followed by their printable ASCII characters, with `.` for the others
+fn fmt_hex_ascii(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for b in bytes {
+        write!(f, "{b:02x}")?;
+    }
+    f.write_str(" |")?;
+    for &b in bytes {
+        let c = if b.is_ascii_graphic() || b == b' ' {
+            char::from(b)
+        } else {
+            '.'
+        };
+        write!(f, "{c}")?;
+    }
+    f.write_str("|")
+}
+
+/// The number of bytes in which `v0` and `v1` differ, counting the bytes beyond the shorter one
+fn differing_bytes(v0: &[u8], v1: &[u8]) -> usize {
+    let common = v0.iter().zip(v1).filter(|(b0, b1)| b0 != b1).count();
+    common + v0.len().abs_diff(v1.len())
+}
+
+/// Returns if the `width` bits wide `value` is the sign extension of its lowest `narrow` bits
+fn is_sign_extension(value: u64, narrow: u32, width: u32) -> bool {
+    let shift = 64 - narrow;
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    let extended = (((value << shift) as i64) >> shift) as u64;
+    let mask = u64::MAX >> (64 - width);
+    extended & mask == value
+}
+
+/// The half of a [`CmpValues::sort_key`] for a bytes operand: the length (saturating at `255`) in the top byte,
+/// the first 7 bytes below it, and a hash of `tag` and all bytes in the lower 64 bits
+fn bytes_sort_key(tag: u16, bytes: &[u8]) -> u128 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut prefix = [0; 8];
+    prefix[0] = u8::try_from(bytes.len()).unwrap_or(u8::MAX);
+    let prefix_len = bytes.len().min(7);
+    prefix[1..=prefix_len].copy_from_slice(&bytes[..prefix_len]);
+
+    let hash = tag
+        .to_le_bytes()
+        .iter()
+        .chain(bytes)
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        });
+
+    u128::from(u64::from_be_bytes(prefix)) << 64 | u128::from(hash)
+}
+
+/// The operator of a comparison, as OR-ed bitflags like the `attribute` of an [`AFLppCmpLogHeader`].
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct CmpOp(u8);
+
+impl CmpOp {
+    /// The comparison is (also) checking for equality
+    pub const EQUAL: Self = Self(1);
+    /// The comparison is a greater-than comparison
+    pub const GREATER: Self = Self(2);
+    /// The comparison is a less-than comparison
+    pub const LESSER: Self = Self(4);
+    /// The operands are floating point numbers
+    pub const FP: Self = Self(8);
+    /// The floating point operands were modified
+    pub const FP_MOD: Self = Self(16);
+    /// The integer operands were modified
+    pub const INT_MOD: Self = Self(32);
+    /// The operands were transformed before the comparison, e.g., byteswapped or sign-extended
+    pub const TRANSFORM: Self = Self(64);
+    /// The comparison overflowed.
+    ///
+    /// This is not part of the `attribute` of an [`AFLppCmpLogHeader`], which has no overflow bit, so the
+    /// `LibAFL` cmplog runtime does not log it. Set it if the overflow is known from another source, e.g., the
+    /// `overflow` bit of an AFL++ `cmp_header`.
+    pub const OVERFLOW: Self = Self(128);
+
+    /// Creates a [`CmpOp`] from the raw bitflags
+    #[must_use]
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Creates a [`CmpOp`] from the `attribute` of an [`AFLppCmpLogHeader`].
+    ///
+    /// Note that the header only holds the lower four bits, the other flags, like [`CmpOp::TRANSFORM`],
+    /// are only set while solving the comparison.
+    #[must_use]
+    pub fn from_attribute(attribute: u32) -> Self {
+        Self(u8::try_from(attribute).unwrap_or(u8::MAX))
+    }
+
+    /// The raw bitflags
+    #[must_use]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Returns if all flags of `other` are set
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns if the operands were transformed before the comparison
+    #[must_use]
+    pub const fn is_transform(self) -> bool {
+        self.contains(Self::TRANSFORM)
+    }
+
+    /// Returns if the comparison overflowed, see [`CmpOp::OVERFLOW`]
+    #[must_use]
+    pub const fn is_overflow(self) -> bool {
+        self.contains(Self::OVERFLOW)
+    }
+}
+
+impl BitOr for CmpOp {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// The kind of code a comparison was logged from, as the operands need different solving strategies
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum CmpKind {
+    /// An instruction, like a `cmp`, with numeric operands
+    #[default]
+    Instruction,
+    /// The arguments of a routine, like `memcmp` or `strcmp`, usually byte buffers
+    Routine,
+}
+
+impl CmpKind {
+    /// Guesses the kind of a comparison from its logged values, if the map does not know better:
+    /// [`CmpValues::Bytes`] are usually logged from routines.
+    #[must_use]
+    pub fn of_values(values: &CmpValues) -> Self {
+        match values {
+            CmpValues::Bytes(_) => Self::Routine,
+            _ => Self::Instruction,
         }
     }
 }
 
+/// The default maximum amount of entries in the [`struct@CmpValuesMetadata`]
+pub const DEFAULT_CMP_VALUES_MAX_ENTRIES: usize = 65536;
+
+/// Numeric operands below this value are too common to be useful tokens, see [`CmpValuesMetadata::to_tokens`]
+pub const CMP_TOKENS_MIN_NUMERIC: u64 = 0x100;
+
+fn default_cmp_values_max_entries() -> usize {
+    DEFAULT_CMP_VALUES_MAX_ENTRIES
+}
+
+fn default_detect_loops() -> bool {
+    true
+}
+
 /// A state metadata holding a list of values logged from comparisons
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 #[cfg_attr(
     any(not(feature = "serdeany_autoreg"), miri),
     allow(clippy::unsafe_derive_deserialize)
@@ -93,6 +618,31 @@ pub struct CmpValuesMetadata {
     /// A `list` of values.
     #[serde(skip)]
     pub list: Vec<CmpValues>,
+    /// The [`CmpKind`] of each value in the `list`, as added by [`CmpValuesMetadata::add_from`]
+    #[serde(skip)]
+    pub kinds: Vec<CmpKind>,
+    /// The maximum amount of values [`CmpValuesMetadata::add_from`] will add to the `list`
+    #[serde(default = "default_cmp_values_max_entries")]
+    max_entries: usize,
+    /// If set, [`CmpValuesMetadata::add_from`] drops values with equal operands
+    #[serde(default)]
+    skip_equal_operands: bool,
+    /// The `(idx, execution)` position in the [`CmpMap`] of each value in the `list`, as added by
+    /// [`CmpValuesMetadata::add_from`] if [`CmpValuesMetadata::record_positions`] is set
+    #[serde(skip)]
+    pub positions: Vec<(usize, usize)>,
+    /// If set, [`CmpValuesMetadata::add_from`] records the `positions` of the values
+    #[serde(default)]
+    record_positions: bool,
+    /// If set, [`CmpValuesMetadata::add_from`] keeps comparisons of ambiguous width as raw bytes
+    #[serde(default)]
+    keep_ambiguous: bool,
+    /// [`CmpValuesMetadata::add_from`] drops numeric values narrower than this, in bytes
+    #[serde(default)]
+    min_numeric_bytes: usize,
+    /// If set, [`CmpValuesMetadata::add_from`] drops the values of cmps that look like loop counters
+    #[serde(default = "default_detect_loops")]
+    detect_loops: bool,
 }
 
 libafl_bolts::impl_serdeany!(CmpValuesMetadata);
@@ -110,71 +660,300 @@ impl DerefMut for CmpValuesMetadata {
     }
 }
 
+impl Default for CmpValuesMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CmpValuesMetadata {
-    /// Creates a new [`struct@CmpValuesMetadata`]
+    /// Creates a new [`struct@CmpValuesMetadata`], holding at most [`DEFAULT_CMP_VALUES_MAX_ENTRIES`] values
     #[must_use]
     pub fn new() -> Self {
-        Self { list: vec![] }
+        Self::with_max_entries(DEFAULT_CMP_VALUES_MAX_ENTRIES)
+    }
+
+    /// Creates a new [`struct@CmpValuesMetadata`], holding at most `max_entries` values.
+    ///
+    /// To use it with a `CmpObserver`, add it to the state before fuzzing.
+    #[must_use]
+    pub fn with_max_entries(max_entries: usize) -> Self {
+        Self {
+            list: vec![],
+            kinds: vec![],
+            max_entries,
+            skip_equal_operands: false,
+            positions: vec![],
+            record_positions: false,
+            keep_ambiguous: false,
+            min_numeric_bytes: 0,
+            detect_loops: true,
+        }
+    }
+
+    /// The maximum amount of values [`CmpValuesMetadata::add_from`] will add
+    #[must_use]
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Sets the maximum amount of values [`CmpValuesMetadata::add_from`] will add
+    pub fn set_max_entries(&mut self, max_entries: usize) {
+        self.max_entries = max_entries;
+    }
+
+    /// Whether [`CmpValuesMetadata::add_from`] drops values with equal operands
+    #[must_use]
+    pub fn skip_equal_operands(&self) -> bool {
+        self.skip_equal_operands
+    }
+
+    /// Sets whether [`CmpValuesMetadata::add_from`] drops values with equal operands, see [`CmpValues::has_equal_operands`].
+    ///
+    /// These comparisons are already satisfied, so skipping them focuses input-to-state replacements
+    /// on the comparisons that currently fail. Off by default.
+    pub fn set_skip_equal_operands(&mut self, skip_equal_operands: bool) {
+        self.skip_equal_operands = skip_equal_operands;
+    }
+
+    /// Whether [`CmpValuesMetadata::add_from`] records the `positions` of the values
+    #[must_use]
+    pub fn record_positions(&self) -> bool {
+        self.record_positions
+    }
+
+    /// Sets whether [`CmpValuesMetadata::add_from`] records the `(idx, execution)` position of each value
+    /// in the `positions`, parallel to the `list`. Off by default.
+    ///
+    /// The executions of a cmp are logged in the order they happened, so a solver can use the positions
+    /// to attempt comparisons that depend on earlier ones, e.g., in a loop, in the order they fired.
+    pub fn set_record_positions(&mut self, record_positions: bool) {
+        self.record_positions = record_positions;
+        if !record_positions {
+            self.positions.clear();
+        }
+    }
+
+    /// Whether [`CmpValuesMetadata::add_from`] keeps comparisons of ambiguous width as raw bytes
+    #[must_use]
+    pub fn keep_ambiguous(&self) -> bool {
+        self.keep_ambiguous
+    }
+
+    /// Sets whether [`CmpValuesMetadata::add_from`] keeps the comparisons for which the [`CmpMap`] has no
+    /// values, e.g., because the logged width is inconsistent or unknown, but raw operands, see [`CmpMap::raw_operands_of`].
+    /// They are added as [`CmpValues::Bytes`] of the little-endian 8-byte operands, so that input-to-state
+    /// replacements can still try them as bytes. Off by default.
+    pub fn set_keep_ambiguous(&mut self, keep_ambiguous: bool) {
+        self.keep_ambiguous = keep_ambiguous;
+    }
+
+    /// The minimum width of the numeric values [`CmpValuesMetadata::add_from`] adds, in bytes
+    #[must_use]
+    pub fn min_numeric_bytes(&self) -> usize {
+        self.min_numeric_bytes
+    }
+
+    /// Sets the minimum width of the numeric values [`CmpValuesMetadata::add_from`] adds, in bytes,
+    /// see [`CmpValues::numeric_bytes`]. Non-numeric values are always added. `0` by default, i.e., all are added.
+    ///
+    /// Narrow comparisons, like single-byte checks for `0`, are easily solved by chance, so dropping
+    /// them focuses input-to-state replacements on the wide magic values, e.g., `2` keeps `U16` and wider.
+    pub fn set_min_numeric_bytes(&mut self, min_numeric_bytes: usize) {
+        self.min_numeric_bytes = min_numeric_bytes;
+    }
+
+    /// Whether [`CmpValuesMetadata::add_from`] drops the values of cmps that look like loop counters
+    #[must_use]
+    pub fn detect_loops(&self) -> bool {
+        self.detect_loops
+    }
+
+    /// Sets whether [`CmpValuesMetadata::add_from`] drops the values of cmps that look like loop counters, i.e.,
+    /// whose operands increase or decrease by one with each of more than four executions. On by default.
+    ///
+    /// If off, the executions are not analyzed at all and every logged value is added, e.g., for targets without
+    /// induction variable comparisons, or if the heuristic drops useful comparisons.
+    pub fn set_detect_loops(&mut self, detect_loops: bool) {
+        self.detect_loops = detect_loops;
+    }
+
+    /// Iterates over the values in the `list` together with their `(idx, execution)` position, in the order
+    /// of the executions of each cmp, see [`CmpValuesMetadata::set_record_positions`].
+    ///
+    /// Empty if the positions were not recorded.
+    pub fn iter_with_positions(&self) -> impl Iterator<Item = ((usize, usize), &CmpValues)> {
+        self.positions.iter().copied().zip(self.list.iter())
     }
 
     /// Add comparisons to a metadata from a `CmpObserver`. `cmp_map` is mutable in case
     /// it is needed for a custom map, but this is not utilized for `CmpObserver` or
     /// `AFLppCmpLogObserver`.
+    ///
+    /// At most [`CmpValuesMetadata::max_entries`] values are added. If there are more,
+    /// the values of the comparisons with the most executions are kept.
     pub fn add_from<CM>(&mut self, usable_count: usize, cmp_map: &mut CM)
     where
         CM: CmpMap,
     {
         self.list.clear();
-        let count = usable_count;
-        for i in 0..count {
-            let execs = cmp_map.usable_executions_for(i);
-            if execs > 0 {
-                // Recongize loops and discard if needed
-                if execs > 4 {
-                    let mut increasing_v0 = 0;
-                    let mut increasing_v1 = 0;
-                    let mut decreasing_v0 = 0;
-                    let mut decreasing_v1 = 0;
-
-                    let mut last: Option<CmpValues> = None;
-                    for j in 0..execs {
-                        if let Some(val) = cmp_map.values_of(i, j) {
-                            if let Some(l) = last.and_then(|x| x.to_u64_tuple()) {
-                                if let Some(v) = val.to_u64_tuple() {
-                                    if l.0.wrapping_add(1) == v.0 {
-                                        increasing_v0 += 1;
-                                    }
-                                    if l.1.wrapping_add(1) == v.1 {
-                                        increasing_v1 += 1;
-                                    }
-                                    if l.0.wrapping_sub(1) == v.0 {
-                                        decreasing_v0 += 1;
-                                    }
-                                    if l.1.wrapping_sub(1) == v.1 {
-                                        decreasing_v1 += 1;
-                                    }
-                                }
-                            }
-                            last = Some(val);
-                        }
+        self.kinds.clear();
+        self.positions.clear();
+        let mut cmps = logged_cmps(usable_count, cmp_map, self.detect_loops);
+        let total_execs: usize = cmps.iter().map(|&(_, execs)| execs).sum();
+
+        if total_execs > self.max_entries {
+            // prefer the comparisons that were hit the most
+            cmps.sort_by_key(|&(i, _)| Reverse(cmp_map.executions_for(i)));
+        }
+
+        for (i, execs) in cmps {
+            for j in 0..execs {
+                if self.list.len() >= self.max_entries {
+                    return;
+                }
+                let val = cmp_map.values_of(i, j).or_else(|| {
+                    if self.keep_ambiguous {
+                        cmp_map
+                            .raw_operands_of(i, j)
+                            .map(|(v0, v1)| raw_operands_values(v0, v1))
+                    } else {
+                        None
+                    }
+                });
+                if let Some(val) = val {
+                    if self.skip_equal_operands && val.has_equal_operands() {
+                        continue;
                     }
-                    // We check for execs-2 because the logged execs may wrap and have something like
-                    // 8 9 10 3 4 5 6 7
-                    if increasing_v0 >= execs - 2
-                        || increasing_v1 >= execs - 2
-                        || decreasing_v0 >= execs - 2
-                        || decreasing_v1 >= execs - 2
+                    if val
+                        .numeric_bytes()
+                        .is_some_and(|bytes| bytes < self.min_numeric_bytes)
                     {
                         continue;
                     }
+                    self.list.push(val);
+                    self.kinds.push(cmp_map.kind_of(i));
+                    if self.record_positions {
+                        self.positions.push((i, j));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Iterates over the values in the `list` together with their [`CmpKind`], e.g., to solve
+    /// [`CmpKind::Routine`] operands with token replacements and [`CmpKind::Instruction`] operands
+    /// with integer replacements.
+    ///
+    /// Values that were not added by [`CmpValuesMetadata::add_from`] get the kind
+    /// guessed by [`CmpKind::of_values`].
+    pub fn iter_with_kinds(&self) -> impl Iterator<Item = (CmpKind, &CmpValues)> {
+        self.list.iter().enumerate().map(|(i, values)| {
+            let kind = self
+                .kinds
+                .get(i)
+                .copied()
+                .unwrap_or_else(|| CmpKind::of_values(values));
+            (kind, values)
+        })
+    }
+
+    /// Extracts dictionary tokens from the logged comparisons, e.g., to extend the [`crate::mutators::Tokens`].
+    ///
+    /// The tokens are the non-empty operands of [`CmpValues::Bytes`] comparisons, and the little-endian
+    /// encodings of numeric operands of at least [`CMP_TOKENS_MIN_NUMERIC`], with the width of the comparison.
+    /// Each token is returned once, in the order of the `list`.
+    #[must_use]
+    pub fn to_tokens(&self) -> Vec<Vec<u8>> {
+        let mut tokens = vec![];
+        let mut seen = HashSet::new();
+        let mut add = |token: Vec<u8>| {
+            if !token.is_empty() && seen.insert(token.clone()) {
+                tokens.push(token);
+            }
+        };
+        for values in &self.list {
+            match values {
+                CmpValues::Bytes((v0, v1)) => {
+                    add(v0.as_slice().to_vec());
+                    add(v1.as_slice().to_vec());
+                }
+                CmpValues::U16((v0, v1, _)) => {
+                    for v in [*v0, *v1] {
+                        if u64::from(v) >= CMP_TOKENS_MIN_NUMERIC {
+                            add(v.to_le_bytes().to_vec());
+                        }
+                    }
+                }
+                CmpValues::U32((v0, v1, _)) => {
+                    for v in [*v0, *v1] {
+                        if u64::from(v) >= CMP_TOKENS_MIN_NUMERIC {
+                            add(v.to_le_bytes().to_vec());
+                        }
+                    }
                 }
-                for j in 0..execs {
-                    if let Some(val) = cmp_map.values_of(i, j) {
-                        self.list.push(val);
+                CmpValues::U64((v0, v1, _)) => {
+                    for v in [*v0, *v1] {
+                        if v >= CMP_TOKENS_MIN_NUMERIC {
+                            add(v.to_le_bytes().to_vec());
+                        }
+                    }
+                }
+                CmpValues::U8(_) | CmpValues::Vector { .. } => {}
+            }
+        }
+        tokens
+    }
+
+    /// Checks if the logged values of the cmp at `idx` are increasing or decreasing by one, i.e., a loop.
+    ///
+    /// The steps are computed at the width of the operands, so that signed counters running through zero
+    /// (e.g., an `i32` going from `0` to `-1`, logged as `0xffffffff`) are recognized, too.
+    fn is_loop<CM>(cmp_map: &CM, idx: usize, execs: usize) -> bool
+    where
+        CM: CmpMap,
+    {
+        let mut increasing_v0 = 0;
+        let mut increasing_v1 = 0;
+        let mut decreasing_v0 = 0;
+        let mut decreasing_v1 = 0;
+
+        let mut last: Option<CmpValues> = None;
+        for j in 0..execs {
+            if let Some(val) = cmp_map.values_of(idx, j) {
+                if let Some(l) = last.and_then(|x| x.to_u64_tuple()) {
+                    if let Some(v) = val.to_u64_tuple() {
+                        let mask = match val {
+                            CmpValues::U8(_) => u64::from(u8::MAX),
+                            CmpValues::U16(_) => u64::from(u16::MAX),
+                            CmpValues::U32(_) => u64::from(u32::MAX),
+                            _ => u64::MAX,
+                        };
+                        // a step of `mask` is a step of `-1` at the width of the operands
+                        let step = |from: u64, to: u64| to.wrapping_sub(from) & mask;
+                        if step(l.0, v.0) == 1 {
+                            increasing_v0 += 1;
+                        }
+                        if step(l.1, v.1) == 1 {
+                            increasing_v1 += 1;
+                        }
+                        if step(l.0, v.0) == mask {
+                            decreasing_v0 += 1;
+                        }
+                        if step(l.1, v.1) == mask {
+                            decreasing_v1 += 1;
+                        }
                     }
                 }
+                last = Some(val);
             }
         }
+        // We check for execs-2 because the logged execs may wrap and have something like
+        // 8 9 10 3 4 5 6 7
+        increasing_v0 >= execs - 2
+            || increasing_v1 >= execs - 2
+            || decreasing_v0 >= execs - 2
+            || decreasing_v1 >= execs - 2
     }
 }
 
@@ -198,78 +977,521 @@ pub trait CmpMap: Debug {
     /// Get the logged values for a cmp
     fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues>;
 
+    /// Get the raw operands logged for an instruction cmp, independent of its width, e.g., to recover
+    /// the comparisons [`CmpMap::values_of`] drops because their width is ambiguous.
+    ///
+    /// `None` by default, and for cmps that did not log integer operands.
+    fn raw_operands_of(&self, idx: usize, execution: usize) -> Option<(u64, u64)> {
+        let _ = (idx, execution);
+        None
+    }
+
     /// Reset the state
     fn reset(&mut self) -> Result<(), Error>;
-}
 
-/// A [`CmpObserver`] observes the traced comparisons during the current execution using a [`CmpMap`]
-pub trait CmpObserver {
-    /// The underlying map
-    type Map;
-    /// Get the number of usable cmps (all by default)
-    fn usable_count(&self) -> usize;
+    /// Reset the state of the first `count` cmps only, e.g., the ones used by the last execution.
+    ///
+    /// Resets the whole map by default.
+    fn reset_used(&mut self, count: usize) -> Result<(), Error> {
+        let _ = count;
+        self.reset()
+    }
 
-    /// Get the `CmpMap`
-    fn cmp_map(&self) -> &Self::Map;
+    /// The number of cmps up to the highest one written since the last reset, if the map keeps
+    /// track of it while it is written.
+    ///
+    /// If known, the [`StdCmpObserver`] only resets this prefix with [`CmpMap::reset_used`].
+    /// `None` by default, e.g., for maps the target writes at hashed indices.
+    fn used_len(&self) -> Option<usize> {
+        None
+    }
 
-    /// Get the mut `CmpMap`
-    fn cmp_map_mut(&mut self) -> &mut Self::Map;
+    /// Get the kind of code a cmp was logged from.
+    ///
+    /// By default, the kind is guessed from the first logged values, see [`CmpKind::of_values`].
+    fn kind_of(&self, idx: usize) -> CmpKind {
+        self.values_of(idx, 0)
+            .map_or(CmpKind::Instruction, |values| CmpKind::of_values(&values))
+    }
+
+    /// Copy the logged cmps into `dst`, e.g., to keep the map of the original input while the map is
+    /// overwritten by the run of a mutated input, and compare both with [`diff_cmp_maps`] afterwards.
+    ///
+    /// Fails with [`Error::unsupported`] by default, for maps that can't be copied, e.g., views of other maps.
+    fn snapshot_into(&self, dst: &mut Self) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        let _ = dst;
+        Err(Error::unsupported("This CmpMap can't be snapshotted"))
+    }
 }
 
-/// A standard [`CmpObserver`] observer
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(bound = "CM: serde::de::DeserializeOwned + Serialize")]
-pub struct StdCmpObserver<'a, CM> {
-    cmp_map: OwnedRefMut<'a, CM>,
-    size: Option<OwnedRefMut<'a, usize>>,
-    name: Cow<'static, str>,
-    add_meta: bool,
+/// The raw operands of a comparison of ambiguous width, as [`CmpValues::Bytes`] of their 8 little-endian bytes
+fn raw_operands_values(v0: u64, v1: u64) -> CmpValues {
+    let bytes = |v: u64| {
+        let mut buf = [0; 32];
+        buf[..8].copy_from_slice(&v.to_le_bytes());
+        CmplogBytes::from_buf_and_len(buf, 8)
+    };
+    CmpValues::Bytes((bytes(v0), bytes(v1)))
 }
 
-impl<CM> CmpObserver for StdCmpObserver<'_, CM>
+/// Returns `(idx, usable_executions)` for the cmps up to `usable_count` with logged executions,
+/// without the ones that look like loop counters, see [`collect_cmp_values`]
+fn logged_cmps<CM>(usable_count: usize, cmp_map: &CM, detect_loops: bool) -> Vec<(usize, usize)>
 where
-    CM: HasLen,
+    CM: CmpMap,
 {
-    type Map = CM;
-
-    /// Get the number of usable cmps (all by default)
-    fn usable_count(&self) -> usize {
-        match &self.size {
-            None => self.cmp_map.as_ref().len(),
-            Some(o) => *o.as_ref(),
+    let mut cmps = vec![];
+    for i in 0..usable_count {
+        let execs = cmp_map.usable_executions_for(i);
+        // Recongize loops and discard if needed
+        if execs > 0
+            && !(detect_loops && execs > 4 && CmpValuesMetadata::is_loop(cmp_map, i, execs))
+        {
+            cmps.push((i, execs));
         }
     }
-
-    fn cmp_map(&self) -> &Self::Map {
-        self.cmp_map.as_ref()
-    }
-
-    fn cmp_map_mut(&mut self) -> &mut Self::Map {
-        self.cmp_map.as_mut()
-    }
+    cmps
 }
 
-impl<CM, I, S> Observer<I, S> for StdCmpObserver<'_, CM>
+/// Passes the values logged for the first `usable_count` cmps of a [`CmpMap`] to `sink`, one execution at a time,
+/// in the order of the cmps.
+///
+/// Like [`CmpValuesMetadata::add_from`], the values of cmps that look like loop counters, i.e., whose
+/// operands increase or decrease by one with each of more than four executions, are left out, as
+/// replacing them rarely helps. This is the building block for custom metadata collected by a custom
+/// [`CmpObserver`], e.g., to tag the values by their source or to keep only some of them:
+///
+/// ```rust
+/// use libafl::observers::cmp::{collect_cmp_values, CmpMap, CmpValues};
+///
+/// /// Keeps the logged string comparisons only
+/// #[derive(Debug, Default)]
+/// struct StringCmpsMetadata {
+///     list: Vec<CmpValues>,
+/// }
+///
+/// impl StringCmpsMetadata {
+///     fn add_from<CM: CmpMap>(&mut self, usable_count: usize, cmp_map: &CM) {
+///         self.list.clear();
+///         collect_cmp_values(usable_count, cmp_map, |values| {
+///             if matches!(values, CmpValues::Bytes(_)) {
+///                 self.list.push(values);
+///             }
+///         });
+///     }
+/// }
+/// ```
+pub fn collect_cmp_values<CM, F>(usable_count: usize, cmp_map: &CM, mut sink: F)
+where
+    CM: CmpMap,
+    F: FnMut(CmpValues),
+{
+    for (i, execs) in logged_cmps(usable_count, cmp_map, true) {
+        for j in 0..execs {
+            if let Some(values) = cmp_map.values_of(i, j) {
+                sink(values);
+            }
+        }
+    }
+}
+
+/// Compares the values logged in two [`CmpMap`]s, e.g., for the original and a mutated input,
+/// and returns `(idx, orig_values, new_values)` for each logged execution of a cmp whose values differ.
+///
+/// Only the executions logged in both maps are compared, i.e., a cmp that was hit more often in
+/// one of the maps only reports differences in the executions both have in common.
+#[must_use]
+pub fn diff_cmp_maps<CM>(orig: &CM, new: &CM) -> Vec<(usize, CmpValues, CmpValues)>
+where
+    CM: CmpMap,
+{
+    let mut diffs = vec![];
+    for idx in 0..orig.len().min(new.len()) {
+        let execs = orig
+            .usable_executions_for(idx)
+            .min(new.usable_executions_for(idx));
+        for execution in 0..execs {
+            if let (Some(orig_values), Some(new_values)) = (
+                orig.values_of(idx, execution),
+                new.values_of(idx, execution),
+            ) {
+                if orig_values != new_values {
+                    diffs.push((idx, orig_values, new_values));
+                }
+            }
+        }
+    }
+    diffs
+}
+
+/// A [`CmpMap`] with `W` cmps logging up to `H` executions each, e.g., for custom instrumentation
+/// that does not use the fixed dimensions of the AFL++ cmplog map.
+///
+/// Like in AFL++, the executions of a cmp beyond the first `H` overwrite the logged values in a ring,
+/// while the number of executions keeps counting.
+#[derive(Debug, Clone)]
+pub struct ConstCmpMap<const W: usize, const H: usize> {
+    hits: [usize; W],
+    values: [[Option<CmpValues>; H]; W],
+    // one past the highest cmp pushed since the last reset
+    used: usize,
+}
+
+impl<const W: usize, const H: usize> ConstCmpMap<W, H> {
+    /// Creates a new, empty [`ConstCmpMap`]
+    ///
+    /// The map is returned by value, so it may be built on the stack: it takes
+    /// `W * (H * size_of::<Option<CmpValues>>() + size_of::<usize>())` bytes, i.e., megabytes already for
+    /// a few thousand cmps, and more than 100 MB for the dimensions of the AFL++ cmplog map.
+    /// Use [`ConstCmpMap::boxed`] for anything but small maps.
+    ///
+    /// # Panics
+    /// Panics if `H` is 0.
+    #[must_use]
+    pub fn new() -> Self {
+        assert!(
+            H > 0,
+            "A ConstCmpMap needs to log at least one execution per cmp"
+        );
+        Self {
+            hits: [0; W],
+            values: core::array::from_fn(|_| core::array::from_fn(|_| None)),
+            used: 0,
+        }
+    }
+
+    /// Creates a new, empty [`ConstCmpMap`] on the heap.
+    /// Unlike [`ConstCmpMap::new`], it is never placed on the stack, so it works for maps of any size.
+    ///
+    /// # Panics
+    /// Panics if `H` is 0.
+    #[must_use]
+    pub fn boxed() -> Box<Self> {
+        assert!(
+            H > 0,
+            "A ConstCmpMap needs to log at least one execution per cmp"
+        );
+        let mut map = Box::<Self>::new_uninit();
+        let ptr = map.as_mut_ptr();
+        // # Safety
+        // Each field is initialized in place, without references to the uninitialized memory.
+        unsafe {
+            let hits = (&raw mut (*ptr).hits).cast::<usize>();
+            for idx in 0..W {
+                hits.add(idx).write(0);
+            }
+            let values = (&raw mut (*ptr).values).cast::<Option<CmpValues>>();
+            for idx in 0..W * H {
+                values.add(idx).write(None);
+            }
+            (&raw mut (*ptr).used).write(0);
+            map.assume_init()
+        }
+    }
+
+    /// Logs the values of an execution of the cmp `idx`
+    ///
+    /// # Panics
+    /// Panics if `idx` is not smaller than `W`.
+    pub fn push(&mut self, idx: usize, values: CmpValues) {
+        let hits = self.hits[idx];
+        self.values[idx][hits % H] = Some(values);
+        self.hits[idx] = hits.saturating_add(1);
+        self.used = self.used.max(idx + 1);
+    }
+
+    /// The number of executions of each cmp since the last reset
+    #[must_use]
+    pub fn hits(&self) -> &[usize; W] {
+        &self.hits
+    }
+}
+
+impl<const W: usize, const H: usize> Default for ConstCmpMap<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> CmpMap for ConstCmpMap<W, H> {
+    fn len(&self) -> usize {
+        W
+    }
+
+    fn executions_for(&self, idx: usize) -> usize {
+        self.hits[idx]
+    }
+
+    fn usable_executions_for(&self, idx: usize) -> usize {
+        self.hits[idx].min(H)
+    }
+
+    fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues> {
+        if execution >= self.usable_executions_for(idx) {
+            return None;
+        }
+        self.values[idx][execution].clone()
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        // For performance, we reset just the hits, the values of cmps without hits are not usable
+        self.hits.fill(0);
+        self.used = 0;
+        Ok(())
+    }
+
+    fn reset_used(&mut self, count: usize) -> Result<(), Error> {
+        self.hits[..count.min(W)].fill(0);
+        if count >= self.used {
+            self.used = 0;
+        }
+        Ok(())
+    }
+
+    fn used_len(&self) -> Option<usize> {
+        Some(self.used)
+    }
+
+    fn snapshot_into(&self, dst: &mut Self) -> Result<(), Error> {
+        dst.clone_from(self);
+        Ok(())
+    }
+}
+
+impl<const W: usize, const H: usize> HasLen for ConstCmpMap<W, H> {
+    fn len(&self) -> usize {
+        W
+    }
+}
+
+impl<const W: usize, const H: usize> Serialize for ConstCmpMap<W, H> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let values: Vec<&[Option<CmpValues>]> = self.values.iter().map(|row| &row[..]).collect();
+        (&self.hits[..], values).serialize(serializer)
+    }
+}
+
+impl<'de, const W: usize, const H: usize> Deserialize<'de> for ConstCmpMap<W, H> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (hits, values) =
+            <(Vec<usize>, Vec<Vec<Option<CmpValues>>>)>::deserialize(deserializer)?;
+        if values.len() != W || values.iter().any(|row| row.len() != H) {
+            return Err(serde::de::Error::custom(
+                "The dimensions of the ConstCmpMap do not match",
+            ));
+        }
+        let used = hits.iter().rposition(|&h| h > 0).map_or(0, |idx| idx + 1);
+        let hits = hits.try_into().map_err(|_| {
+            serde::de::Error::custom("The dimensions of the ConstCmpMap do not match")
+        })?;
+        let mut rows = values.into_iter();
+        let values = core::array::from_fn(|_| {
+            let mut row = rows.next().unwrap().into_iter();
+            core::array::from_fn(|_| row.next().unwrap())
+        });
+        Ok(Self { hits, values, used })
+    }
+}
+
+/// A [`CmpObserver`] observes the traced comparisons during the current execution using a [`CmpMap`]
+pub trait CmpObserver {
+    /// The underlying map
+    type Map;
+    /// Get the number of usable cmps (all by default)
+    fn usable_count(&self) -> usize;
+
+    /// Get the `CmpMap`
+    fn cmp_map(&self) -> &Self::Map;
+
+    /// Get the mut `CmpMap`
+    fn cmp_map_mut(&mut self) -> &mut Self::Map;
+}
+
+/// A standard [`CmpObserver`] observer
+///
+/// To get to the logged comparisons from a stage (e.g., for a `RedQueen`-style stage), keep a
+/// [`Handle`](libafl_bolts::tuples::Handle) to the observer, obtained with
+/// [`Handled::handle`](libafl_bolts::tuples::Handled::handle) when building the stage.
+/// In `perform`, look the observer up in the executor's observers with it, after running the input:
+/// ```
+/// use libafl::observers::cmp::{CmpMap, CmpObserver, CmpValues, StdCmpObserver};
+/// use libafl_bolts::{
+///     ownedref::OwnedRefMut,
+///     tuples::{tuple_list, Handle, Handled, MatchNameRef},
+///     Error, HasLen,
+/// };
+///
+/// /// A map holding at most one value per cmp, as logged by the target
+/// #[derive(Debug, serde::Serialize)]
+/// struct MyCmpMap(Vec<CmpValues>);
+/// # impl HasLen for MyCmpMap {
+/// #     fn len(&self) -> usize {
+/// #         self.0.len()
+/// #     }
+/// # }
+/// # impl CmpMap for MyCmpMap {
+/// #     fn len(&self) -> usize {
+/// #         self.0.len()
+/// #     }
+/// #     fn executions_for(&self, _idx: usize) -> usize {
+/// #         1
+/// #     }
+/// #     fn usable_executions_for(&self, _idx: usize) -> usize {
+/// #         1
+/// #     }
+/// #     fn values_of(&self, idx: usize, _execution: usize) -> Option<CmpValues> {
+/// #         self.0.get(idx).cloned()
+/// #     }
+/// #     fn reset(&mut self) -> Result<(), Error> {
+/// #         self.0.clear();
+/// #         Ok(())
+/// #     }
+/// # }
+///
+/// let mut map = MyCmpMap(vec![CmpValues::U8((1, 2, false))]);
+/// let observer = StdCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), false);
+///
+/// // When creating the stage
+/// let handle: Handle<StdCmpObserver<MyCmpMap>> = observer.handle();
+///
+/// // In the stage, `observers` would be `executor.observers()`, which can also be indexed: `observers[&handle]`
+/// let observers = tuple_list!(observer);
+/// let cmp_observer = observers.get(&handle).unwrap();
+/// for idx in 0..cmp_observer.usable_count() {
+///     let values = cmp_observer.cmp_map().values_of(idx, 0);
+///     assert_eq!(values, Some(CmpValues::U8((1, 2, false))));
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "CM: serde::de::DeserializeOwned + Serialize")]
+#[allow(clippy::struct_excessive_bools)]
+pub struct StdCmpObserver<'a, CM> {
+    cmp_map: OwnedRefMut<'a, CM>,
+    size: Option<OwnedRefMut<'a, usize>>,
+    name: Cow<'static, str>,
+    add_meta: bool,
+    #[serde(default)]
+    skip_on_crash: bool,
+    #[serde(default)]
+    skip_equal_operands: bool,
+    #[serde(default)]
+    keep_ambiguous: bool,
+    #[serde(default = "default_detect_loops")]
+    detect_loops: bool,
+}
+
+impl<CM> CmpObserver for StdCmpObserver<'_, CM>
+where
+    CM: HasLen,
+{
+    type Map = CM;
+
+    /// Get the number of usable cmps (all by default)
+    ///
+    /// If a size is set that exceeds the length of the map, it is clamped to the length of the map.
+    fn usable_count(&self) -> usize {
+        let len = self.cmp_map.as_ref().len();
+        match &self.size {
+            None => len,
+            Some(o) => (*o.as_ref()).min(len),
+        }
+    }
+
+    fn cmp_map(&self) -> &Self::Map {
+        self.cmp_map.as_ref()
+    }
+
+    fn cmp_map_mut(&mut self) -> &mut Self::Map {
+        self.cmp_map.as_mut()
+    }
+}
+
+/// With the `introspection` feature, the time spent adding the values to the [`CmpValuesMetadata`]
+/// is measured as [`PerfFeature::CmpObserverPostExec`].
+impl<CM, I, S> Observer<I, S> for StdCmpObserver<'_, CM>
 where
     CM: Serialize + CmpMap + HasLen,
-    S: HasMetadata,
+    S: HasMetadata + MaybeHasClientPerfMonitor,
 {
     fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
-        self.cmp_map.as_mut().reset()?;
-        Ok(())
+        self.reset_cmp_map()
     }
 
-    fn post_exec(&mut self, state: &mut S, _input: &I, _exit_kind: &ExitKind) -> Result<(), Error> {
-        if self.add_meta {
-            #[allow(clippy::option_if_let_else)] // we can't mutate state in a closure
-            let meta = state.metadata_or_insert_with(CmpValuesMetadata::new);
+    fn post_exec(&mut self, state: &mut S, _input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        // We can't use `start_timer!`, it would reset the timer of the surrounding `PostExecObservers`
+        #[cfg(feature = "introspection")]
+        let start = libafl_bolts::cpu::read_time_counter();
+
+        self.add_cmp_values(state, *exit_kind);
 
-            meta.add_from(self.usable_count(), self.cmp_map_mut());
+        #[cfg(feature = "introspection")]
+        if self.add_meta {
+            state.introspection_monitor_mut().update_feature(
+                PerfFeature::CmpObserverPostExec,
+                libafl_bolts::cpu::read_time_counter() - start,
+            );
         }
         Ok(())
     }
 }
 
+impl<CM> StdCmpObserver<'_, CM>
+where
+    CM: CmpMap + HasLen,
+{
+    /// Clears the map before an execution.
+    /// Only the cmps up to the last one logged by the previous execution need to be cleared.
+    fn reset_cmp_map(&mut self) -> Result<(), Error> {
+        match self.cmp_map.as_ref().used_len() {
+            Some(count) => self.cmp_map.as_mut().reset_used(count),
+            None => self.cmp_map.as_mut().reset(),
+        }
+    }
+
+    /// Adds the logged values to the [`CmpValuesMetadata`] of the state after an execution, if enabled
+    fn add_cmp_values<S>(&mut self, state: &mut S, exit_kind: ExitKind)
+    where
+        S: HasMetadata,
+    {
+        if !self.add_meta {
+            return;
+        }
+        if self.skip_on_crash && matches!(exit_kind, ExitKind::Crash | ExitKind::Timeout) {
+            // The operands of a crashing run may be garbage, don't keep the values of an older run either
+            if let Ok(meta) = state.metadata_mut::<CmpValuesMetadata>() {
+                meta.list.clear();
+                meta.kinds.clear();
+                meta.positions.clear();
+            }
+            return;
+        }
+
+        #[allow(clippy::option_if_let_else)] // we can't mutate state in a closure
+        let meta = state.metadata_or_insert_with(CmpValuesMetadata::new);
+        if self.skip_equal_operands {
+            meta.set_skip_equal_operands(true);
+        }
+        if self.keep_ambiguous {
+            meta.set_keep_ambiguous(true);
+        }
+        if !self.detect_loops {
+            meta.set_detect_loops(false);
+        }
+
+        let usable_count = self.usable_count();
+        meta.add_from(usable_count, self.cmp_map_mut());
+    }
+}
+
 impl<CM> Named for StdCmpObserver<'_, CM> {
     fn name(&self) -> &Cow<'static, str> {
         &self.name
@@ -288,10 +1510,16 @@ where
             size: None,
             cmp_map: map,
             add_meta,
+            skip_on_crash: false,
+            skip_equal_operands: false,
+            keep_ambiguous: false,
+            detect_loops: true,
         }
     }
 
     /// Creates a new [`StdCmpObserver`] with the given name, map and reference to variable size.
+    ///
+    /// Only the first `size` cmps of the map are used, see [`CmpObserver::usable_count`].
     #[must_use]
     pub fn with_size(
         name: &'static str,
@@ -304,85 +1532,867 @@ where
             size: Some(size),
             cmp_map,
             add_meta,
+            skip_on_crash: false,
+            skip_equal_operands: false,
+            keep_ambiguous: false,
+            detect_loops: true,
         }
     }
-}
 
-/* From AFL++ cmplog.h
+    /// Creates a new [`StdCmpObserver`] with the given name, map and a reference to a variable size.
+    ///
+    /// Since the size is borrowed for the lifetime of the observer, use [`Self::set_usable_count`]
+    /// to update it afterwards.
+    #[must_use]
+    pub fn with_size_ref(
+        name: &'static str,
+        cmp_map: OwnedRefMut<'a, CM>,
+        add_meta: bool,
+        size: &'a mut usize,
+    ) -> Self {
+        Self::with_size(name, cmp_map, add_meta, OwnedRefMut::Ref(size))
+    }
 
-#define CMP_MAP_W 65536
-#define CMP_MAP_H 32
-#define CMP_MAP_RTN_H (CMP_MAP_H / 4)
+    /// Don't add the comparisons of executions that crashed or timed out to the [`CmpValuesMetadata`],
+    /// since their operands may be garbage. Off by default.
+    ///
+    /// The values of the previous execution are cleared instead.
+    #[must_use]
+    pub fn skip_on_crash(mut self, skip_on_crash: bool) -> Self {
+        self.skip_on_crash = skip_on_crash;
+        self
+    }
 
-struct cmp_header {
+    /// Don't add comparisons with equal operands to the [`CmpValuesMetadata`], since they are already satisfied.
+    /// Off by default.
+    ///
+    /// If set, this enables [`CmpValuesMetadata::set_skip_equal_operands`] on the metadata.
+    #[must_use]
+    pub fn skip_equal_operands(mut self, skip_equal_operands: bool) -> Self {
+        self.skip_equal_operands = skip_equal_operands;
+        self
+    }
 
-  unsigned hits : 24;
-  unsigned id : 24;
-  unsigned shape : 5;
-  unsigned type : 2;
-  unsigned attribute : 4;
-  unsigned overflow : 1;
-  unsigned reserved : 4;
+    /// Add comparisons of ambiguous width as raw bytes to the [`CmpValuesMetadata`], instead of dropping them.
+    /// Off by default.
+    ///
+    /// If set, this enables [`CmpValuesMetadata::set_keep_ambiguous`] on the metadata.
+    #[must_use]
+    pub fn keep_ambiguous(mut self, keep_ambiguous: bool) -> Self {
+        self.keep_ambiguous = keep_ambiguous;
+        self
+    }
 
-} __attribute__((packed));
+    /// Add the values of cmps that look like loop counters to the [`CmpValuesMetadata`], too, without
+    /// analyzing their executions. Loops are detected by default.
+    ///
+    /// If set to `false`, this disables [`CmpValuesMetadata::set_detect_loops`] on the metadata.
+    #[must_use]
+    pub fn detect_loops(mut self, detect_loops: bool) -> Self {
+        self.detect_loops = detect_loops;
+        self
+    }
 
-struct cmp_operands {
+    /// Sets the number of usable cmps, i.e., only the first `count` cmps of the map will be used.
+    ///
+    /// If this observer refers to a variable size, the referenced value is updated.
+    pub fn set_usable_count(&mut self, count: usize) {
+        match &mut self.size {
+            Some(size) => *size.as_mut() = count,
+            None => self.size = Some(OwnedRefMut::Owned(Box::new(count))),
+        }
+    }
+}
 
-  u64 v0;
-  u64 v1;
-  u64 v0_128;
-  u64 v1_128;
+/// A wrapper around a [`CmpObserver`] that only adds the comparisons at the indices of the [`CmpMap`]
+/// for which `filter` returns `true` to the [`struct@CmpValuesMetadata`].
+///
+/// Use it to focus on the comparisons of a target of interest, e.g., with `|idx| range.contains(&idx)`
+/// for a range of indices.
+/// The wrapped observer should not add the metadata itself, e.g., create a [`StdCmpObserver`] with
+/// `add_meta` set to `false`, otherwise it may overwrite the filtered values.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "O: Serialize",
+    deserialize = "O: serde::de::DeserializeOwned, F: Default"
+))]
+pub struct FilteredCmpObserver<O, F> {
+    inner: O,
+    #[serde(skip)]
+    filter: F,
+}
 
-} __attribute__((packed));
+impl<O, F> Debug for FilteredCmpObserver<O, F>
+where
+    O: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteredCmpObserver")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
 
-struct cmpfn_operands {
+impl<O, F> FilteredCmpObserver<O, F>
+where
+    O: CmpObserver,
+    F: Fn(usize) -> bool,
+{
+    /// Creates a new [`FilteredCmpObserver`], wrapping the given observer
+    pub fn new(inner: O, filter: F) -> Self {
+        Self { inner, filter }
+    }
 
-  u8 v0[31];
-  u8 v0_len;
-  u8 v1[31];
-  u8 v1_len;
+    /// The wrapped observer
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
 
-} __attribute__((packed));
+    /// The wrapped observer (mutable)
+    pub fn inner_mut(&mut self) -> &mut O {
+        &mut self.inner
+    }
+}
 
-typedef struct cmp_operands cmp_map_list[CMP_MAP_H];
+impl<O, F> Named for FilteredCmpObserver<O, F>
+where
+    O: Named,
+{
+    fn name(&self) -> &Cow<'static, str> {
+        self.inner.name()
+    }
+}
 
-struct cmp_map {
+impl<O, F> CmpObserver for FilteredCmpObserver<O, F>
+where
+    O: CmpObserver,
+{
+    type Map = O::Map;
 
-  struct cmp_header   headers[CMP_MAP_W];
-  struct cmp_operands log[CMP_MAP_W][CMP_MAP_H];
+    fn usable_count(&self) -> usize {
+        self.inner.usable_count()
+    }
 
-};
-*/
+    fn cmp_map(&self) -> &Self::Map {
+        self.inner.cmp_map()
+    }
 
-/// A state metadata holding a list of values logged from comparisons. AFL++ RQ version.
-#[derive(Debug, Default, Serialize, Deserialize)]
-#[cfg_attr(
-    any(not(feature = "serdeany_autoreg"), miri),
-    allow(clippy::unsafe_derive_deserialize)
-)] // for SerdeAny
-pub struct AFLppCmpValuesMetadata {
-    /// The first map of `AFLppCmpLogVals` retrieved by running the un-mutated input
-    #[serde(skip)]
-    pub orig_cmpvals: HashMap<usize, Vec<CmpValues>>,
-    /// The second map of `AFLppCmpLogVals` retrieved by runnning the mutated input
-    #[serde(skip)]
-    pub new_cmpvals: HashMap<usize, Vec<CmpValues>>,
-    /// The list of logged idx and headers retrieved by runnning the mutated input
-    #[serde(skip)]
-    pub headers: Vec<(usize, AFLppCmpLogHeader)>,
+    fn cmp_map_mut(&mut self) -> &mut Self::Map {
+        self.inner.cmp_map_mut()
+    }
 }
 
-libafl_bolts::impl_serdeany!(AFLppCmpValuesMetadata);
+impl<I, O, F, S> Observer<I, S> for FilteredCmpObserver<O, F>
+where
+    O: Observer<I, S> + CmpObserver,
+    O::Map: CmpMap,
+    F: Fn(usize) -> bool,
+    S: HasMetadata,
+{
+    fn pre_exec(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.inner.pre_exec(state, input)
+    }
 
-impl AFLppCmpValuesMetadata {
-    /// Constructor for `AFLppCmpValuesMetadata`
-    #[must_use]
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        self.inner.post_exec(state, input, exit_kind)?;
+
+        let mut filtered = FilteredCmpMap {
+            map: self.inner.cmp_map(),
+            filter: &self.filter,
+        };
+        #[allow(clippy::option_if_let_else)] // we can't mutate state in a closure
+        let meta = state.metadata_or_insert_with(CmpValuesMetadata::new);
+        meta.add_from(self.inner.usable_count(), &mut filtered);
+        Ok(())
+    }
+}
+
+/// A view on a [`CmpMap`] that hides the cmps filtered out by a [`FilteredCmpObserver`]
+struct FilteredCmpMap<'a, CM, F> {
+    map: &'a CM,
+    filter: &'a F,
+}
+
+impl<CM, F> Debug for FilteredCmpMap<'_, CM, F>
+where
+    CM: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteredCmpMap")
+            .field("map", &self.map)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<CM, F> CmpMap for FilteredCmpMap<'_, CM, F>
+where
+    CM: CmpMap,
+    F: Fn(usize) -> bool,
+{
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn executions_for(&self, idx: usize) -> usize {
+        if (self.filter)(idx) {
+            self.map.executions_for(idx)
+        } else {
+            0
+        }
+    }
+
+    fn usable_executions_for(&self, idx: usize) -> usize {
+        if (self.filter)(idx) {
+            self.map.usable_executions_for(idx)
+        } else {
+            0
+        }
+    }
+
+    fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues> {
+        if (self.filter)(idx) {
+            self.map.values_of(idx, execution)
+        } else {
+            None
+        }
+    }
+
+    fn raw_operands_of(&self, idx: usize, execution: usize) -> Option<(u64, u64)> {
+        if (self.filter)(idx) {
+            self.map.raw_operands_of(idx, execution)
+        } else {
+            None
+        }
+    }
+
+    fn kind_of(&self, idx: usize) -> CmpKind {
+        self.map.kind_of(idx)
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Err(Error::illegal_state(
+            "a filtered view of a cmp map cannot be reset",
+        ))
+    }
+}
+
+/// A wrapper around a [`CmpObserver`] that keeps track of how much of its [`CmpMap`] is used
+/// over the whole run, e.g., to right-size the map for a target.
+///
+/// If [`CmpMapUtilizationObserver::peak_hits`] exceeds [`CmpMapUtilizationObserver::peak_executions`],
+/// a cmp was hit more often than the map can log, and values were lost.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CmpMapUtilizationObserver<O> {
+    inner: O,
+    peak_index: Option<usize>,
+    peak_executions: usize,
+    peak_hits: usize,
+}
+
+impl<O> CmpMapUtilizationObserver<O>
+where
+    O: CmpObserver,
+{
+    /// Creates a new [`CmpMapUtilizationObserver`], wrapping the given observer
+    pub fn new(inner: O) -> Self {
+        Self {
+            inner,
+            peak_index: None,
+            peak_executions: 0,
+            peak_hits: 0,
+        }
+    }
+
+    /// The wrapped observer
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+
+    /// The wrapped observer (mutable)
+    pub fn inner_mut(&mut self) -> &mut O {
+        &mut self.inner
+    }
+
+    /// The highest index of a cmp that was hit so far, if any
+    #[must_use]
+    pub fn peak_index(&self) -> Option<usize> {
+        self.peak_index
+    }
+
+    /// The highest number of logged executions of a single cmp so far,
+    /// see [`CmpMap::usable_executions_for`]
+    #[must_use]
+    pub fn peak_executions(&self) -> usize {
+        self.peak_executions
+    }
+
+    /// The highest number of executions of a single cmp so far, logged or not,
+    /// see [`CmpMap::executions_for`]
+    #[must_use]
+    pub fn peak_hits(&self) -> usize {
+        self.peak_hits
+    }
+}
+
+impl<O> Named for CmpMapUtilizationObserver<O>
+where
+    O: Named,
+{
+    fn name(&self) -> &Cow<'static, str> {
+        self.inner.name()
+    }
+}
+
+impl<O> CmpObserver for CmpMapUtilizationObserver<O>
+where
+    O: CmpObserver,
+{
+    type Map = O::Map;
+
+    fn usable_count(&self) -> usize {
+        self.inner.usable_count()
+    }
+
+    fn cmp_map(&self) -> &Self::Map {
+        self.inner.cmp_map()
+    }
+
+    fn cmp_map_mut(&mut self) -> &mut Self::Map {
+        self.inner.cmp_map_mut()
+    }
+}
+
+impl<I, O, S> Observer<I, S> for CmpMapUtilizationObserver<O>
+where
+    O: Observer<I, S> + CmpObserver,
+    O::Map: CmpMap,
+{
+    fn pre_exec(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.inner.pre_exec(state, input)
+    }
+
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        self.inner.post_exec(state, input, exit_kind)?;
+
+        let map = self.inner.cmp_map();
+        for idx in 0..map.len() {
+            let hits = map.executions_for(idx);
+            if hits > 0 {
+                self.peak_index = Some(self.peak_index.map_or(idx, |peak| peak.max(idx)));
+                self.peak_hits = self.peak_hits.max(hits);
+                self.peak_executions = self.peak_executions.max(map.usable_executions_for(idx));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A wrapper around a [`CmpObserver`] that counts the comparisons executed by the last run,
+/// i.e., the sum of [`CmpMap::executions_for`] over all usable cmps.
+///
+/// Unlike [`struct@CmpValuesMetadata`], no values are read, so it is cheap. The count is a coarse
+/// measure of how much branching logic an input reached, even if it did not find new edges, e.g.,
+/// for stats or a custom feedback.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CmpHitCountObserver<O> {
+    inner: O,
+    total_hits: usize,
+}
+
+impl<O> CmpHitCountObserver<O>
+where
+    O: CmpObserver,
+{
+    /// Creates a new [`CmpHitCountObserver`], wrapping the given observer
+    pub fn new(inner: O) -> Self {
+        Self {
+            inner,
+            total_hits: 0,
+        }
+    }
+
+    /// The wrapped observer
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+
+    /// The wrapped observer (mutable)
+    pub fn inner_mut(&mut self) -> &mut O {
+        &mut self.inner
+    }
+
+    /// The number of comparisons executed by the last run
+    #[must_use]
+    pub fn total_hits(&self) -> usize {
+        self.total_hits
+    }
+}
+
+impl<O> Named for CmpHitCountObserver<O>
+where
+    O: Named,
+{
+    fn name(&self) -> &Cow<'static, str> {
+        self.inner.name()
+    }
+}
+
+impl<O> CmpObserver for CmpHitCountObserver<O>
+where
+    O: CmpObserver,
+{
+    type Map = O::Map;
+
+    fn usable_count(&self) -> usize {
+        self.inner.usable_count()
+    }
+
+    fn cmp_map(&self) -> &Self::Map {
+        self.inner.cmp_map()
+    }
+
+    fn cmp_map_mut(&mut self) -> &mut Self::Map {
+        self.inner.cmp_map_mut()
+    }
+}
+
+impl<I, O, S> Observer<I, S> for CmpHitCountObserver<O>
+where
+    O: Observer<I, S> + CmpObserver,
+    O::Map: CmpMap,
+{
+    fn pre_exec(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.total_hits = 0;
+        self.inner.pre_exec(state, input)
+    }
+
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        self.inner.post_exec(state, input, exit_kind)?;
+
+        let map = self.inner.cmp_map();
+        self.total_hits = (0..self.inner.usable_count())
+            .map(|idx| map.executions_for(idx))
+            .fold(0, usize::saturating_add);
+        Ok(())
+    }
+}
+
+/// A [`CmpObserver`] that copies the logged comparisons into a ring buffer of fixed capacity,
+/// preallocated and owned by the observer, instead of building a [`struct@CmpValuesMetadata`]
+/// after each execution.
+///
+/// Once the buffer is full, the oldest comparisons are overwritten, so memory stays constant and
+/// no allocations happen per execution, at the cost of completeness. Loops are not filtered.
+/// Call [`RingCmpObserver::drain_into`] to move the buffered comparisons to a
+/// [`struct@CmpValuesMetadata`] when they are needed.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "CM: serde::de::DeserializeOwned + Serialize")]
+pub struct RingCmpObserver<'a, CM> {
+    cmp_map: OwnedRefMut<'a, CM>,
+    name: Cow<'static, str>,
+    capacity: usize,
+    // the buffer is not serialized, so neither is its write position
+    #[serde(skip)]
+    next: usize,
+    #[serde(skip)]
+    ring: Vec<CmpValues>,
+}
+
+impl<'a, CM> RingCmpObserver<'a, CM>
+where
+    CM: CmpMap,
+{
+    /// Creates a new [`RingCmpObserver`] with the given name and map, buffering at most
+    /// `capacity` comparisons.
+    #[must_use]
+    pub fn new(name: &'static str, cmp_map: OwnedRefMut<'a, CM>, capacity: usize) -> Self {
+        Self {
+            cmp_map,
+            name: Cow::from(name),
+            capacity,
+            next: 0,
+            ring: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The maximum number of buffered comparisons
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of currently buffered comparisons
+    #[must_use]
+    pub fn buffered(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Appends the buffered comparisons, from the oldest to the newest, to the `list` of the
+    /// given [`struct@CmpValuesMetadata`] and empties the buffer.
+    pub fn drain_into(&mut self, meta: &mut CmpValuesMetadata) {
+        meta.list.extend_from_slice(&self.ring[self.next..]);
+        meta.list.extend_from_slice(&self.ring[..self.next]);
+        self.ring.clear();
+        self.next = 0;
+    }
+
+    fn push(&mut self, values: CmpValues) {
+        if self.ring.len() < self.capacity {
+            self.ring.push(values);
+        } else if self.capacity > 0 {
+            self.ring[self.next] = values;
+            self.next = (self.next + 1) % self.capacity;
+        }
+    }
+}
+
+impl<CM> CmpObserver for RingCmpObserver<'_, CM>
+where
+    CM: CmpMap,
+{
+    type Map = CM;
+
+    fn usable_count(&self) -> usize {
+        self.cmp_map.as_ref().len()
+    }
+
+    fn cmp_map(&self) -> &Self::Map {
+        self.cmp_map.as_ref()
+    }
+
+    fn cmp_map_mut(&mut self) -> &mut Self::Map {
+        self.cmp_map.as_mut()
+    }
+}
+
+impl<CM, I, S> Observer<I, S> for RingCmpObserver<'_, CM>
+where
+    CM: Serialize + CmpMap + HasLen,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.cmp_map.as_mut().reset()?;
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        for idx in 0..CmpMap::len(self.cmp_map.as_ref()) {
+            for execution in 0..self.cmp_map.as_ref().usable_executions_for(idx) {
+                if let Some(values) = self.cmp_map.as_ref().values_of(idx, execution) {
+                    self.push(values);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<CM> Named for RingCmpObserver<'_, CM> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+/// A [`CmpMap`] presenting several [`CmpMap`]s as one, e.g., for instrumentation logging the
+/// comparisons of each thread or module to its own map.
+///
+/// The cmps of the maps are indexed one after the other: the flat index `idx` refers to the first map
+/// for `idx < maps[0].len()`, to the second one for the next `maps[1].len()` indices, and so on.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "CM: serde::de::DeserializeOwned + Serialize")]
+pub struct CombinedCmpMap<'a, CM> {
+    maps: Vec<OwnedRefMut<'a, CM>>,
+}
+
+impl<'a, CM> CombinedCmpMap<'a, CM>
+where
+    CM: CmpMap,
+{
+    /// Creates a new [`CombinedCmpMap`] over the given maps
+    #[must_use]
+    pub fn new(maps: Vec<OwnedRefMut<'a, CM>>) -> Self {
+        Self { maps }
+    }
+
+    /// The combined maps
+    #[must_use]
+    pub fn maps(&self) -> &[OwnedRefMut<'a, CM>] {
+        &self.maps
+    }
+
+    /// The combined maps (mutable)
+    pub fn maps_mut(&mut self) -> &mut [OwnedRefMut<'a, CM>] {
+        &mut self.maps
+    }
+
+    /// Returns the map the flat index `idx` refers to, and the index of the cmp in this map.
+    ///
+    /// # Panics
+    /// Panics if `idx` is out of bounds.
+    fn locate(&self, mut idx: usize) -> (&CM, usize) {
+        for map in &self.maps {
+            let len = map.as_ref().len();
+            if idx < len {
+                return (map.as_ref(), idx);
+            }
+            idx -= len;
+        }
+        panic!("Cmp index out of bounds of the combined maps");
+    }
+}
+
+impl<CM> CmpMap for CombinedCmpMap<'_, CM>
+where
+    CM: CmpMap,
+{
+    fn len(&self) -> usize {
+        self.maps.iter().map(|map| map.as_ref().len()).sum()
+    }
+
+    fn executions_for(&self, idx: usize) -> usize {
+        let (map, idx) = self.locate(idx);
+        map.executions_for(idx)
+    }
+
+    fn usable_executions_for(&self, idx: usize) -> usize {
+        let (map, idx) = self.locate(idx);
+        map.usable_executions_for(idx)
+    }
+
+    fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues> {
+        let (map, idx) = self.locate(idx);
+        map.values_of(idx, execution)
+    }
+
+    fn raw_operands_of(&self, idx: usize, execution: usize) -> Option<(u64, u64)> {
+        let (map, idx) = self.locate(idx);
+        map.raw_operands_of(idx, execution)
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        for map in &mut self.maps {
+            map.as_mut().reset()?;
+        }
+        Ok(())
+    }
+
+    fn kind_of(&self, idx: usize) -> CmpKind {
+        let (map, idx) = self.locate(idx);
+        map.kind_of(idx)
+    }
+}
+
+impl<CM> HasLen for CombinedCmpMap<'_, CM>
+where
+    CM: CmpMap,
+{
+    fn len(&self) -> usize {
+        CmpMap::len(self)
+    }
+}
+
+/// A [`CmpObserver`] over several [`CmpMap`]s at once, see [`CombinedCmpMap`].
+///
+/// All maps are reset before each execution, and their comparisons are added to the
+/// [`struct@CmpValuesMetadata`] together, if `add_meta` is set, so that a single input-to-state
+/// stage can use them.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "CM: serde::de::DeserializeOwned + Serialize")]
+pub struct CombinedCmpObserver<'a, CM> {
+    cmp_map: CombinedCmpMap<'a, CM>,
+    name: Cow<'static, str>,
+    add_meta: bool,
+}
+
+impl<'a, CM> CombinedCmpObserver<'a, CM>
+where
+    CM: CmpMap,
+{
+    /// Creates a new [`CombinedCmpObserver`] with the given name and maps.
+    #[must_use]
+    pub fn new(name: &'static str, maps: Vec<OwnedRefMut<'a, CM>>, add_meta: bool) -> Self {
+        Self {
+            cmp_map: CombinedCmpMap::new(maps),
+            name: Cow::from(name),
+            add_meta,
+        }
+    }
+}
+
+impl<'a, CM> CmpObserver for CombinedCmpObserver<'a, CM>
+where
+    CM: CmpMap,
+{
+    type Map = CombinedCmpMap<'a, CM>;
+
+    /// The number of cmps of all maps
+    fn usable_count(&self) -> usize {
+        CmpMap::len(&self.cmp_map)
+    }
+
+    fn cmp_map(&self) -> &Self::Map {
+        &self.cmp_map
+    }
+
+    fn cmp_map_mut(&mut self) -> &mut Self::Map {
+        &mut self.cmp_map
+    }
+}
+
+impl<CM, I, S> Observer<I, S> for CombinedCmpObserver<'_, CM>
+where
+    CM: Serialize + CmpMap,
+    S: HasMetadata,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.cmp_map.reset()
+    }
+
+    fn post_exec(&mut self, state: &mut S, _input: &I, _exit_kind: &ExitKind) -> Result<(), Error> {
+        if self.add_meta {
+            let usable_count = self.usable_count();
+            let meta = state.metadata_or_insert_with(CmpValuesMetadata::new);
+            meta.add_from(usable_count, &mut self.cmp_map);
+        }
+        Ok(())
+    }
+}
+
+impl<CM> Named for CombinedCmpObserver<'_, CM> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+/* From AFL++ cmplog.h
+
+#define CMP_MAP_W 65536
+#define CMP_MAP_H 32
+#define CMP_MAP_RTN_H (CMP_MAP_H / 4)
+
+struct cmp_header {
+
+  unsigned hits : 24;
+  unsigned id : 24;
+  unsigned shape : 5;
+  unsigned type : 2;
+  unsigned attribute : 4;
+  unsigned overflow : 1;
+  unsigned reserved : 4;
+
+} __attribute__((packed));
+
+struct cmp_operands {
+
+  u64 v0;
+  u64 v1;
+  u64 v0_128;
+  u64 v1_128;
+
+} __attribute__((packed));
+
+struct cmpfn_operands {
+
+  u8 v0[31];
+  u8 v0_len;
+  u8 v1[31];
+  u8 v1_len;
+
+} __attribute__((packed));
+
+typedef struct cmp_operands cmp_map_list[CMP_MAP_H];
+
+struct cmp_map {
+
+  struct cmp_header   headers[CMP_MAP_W];
+  struct cmp_operands log[CMP_MAP_W][CMP_MAP_H];
+
+};
+*/
+
+/// Buckets a raw hit count logarithmically, like AFL's `classify_counts`.
+///
+/// The buckets are `0`, `1`, `2`, `3`, `4-7`, `8-15`, `16-31`, `32-127` and `128+`,
+/// each represented by a single bit, so that small changes in large hit counts are ignored.
+#[must_use]
+pub const fn classify_hits(hits: u32) -> u8 {
+    match hits {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 4,
+        4..=7 => 8,
+        8..=15 => 16,
+        16..=31 => 32,
+        32..=127 => 64,
+        _ => 128,
+    }
+}
+
+/// A state metadata holding a list of values logged from comparisons. AFL++ RQ version.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct AFLppCmpValuesMetadata {
+    /// The first map of `AFLppCmpLogVals` retrieved by running the un-mutated input
+    #[serde(skip)]
+    pub orig_cmpvals: HashMap<usize, Vec<CmpValues>>,
+    /// The second map of `AFLppCmpLogVals` retrieved by runnning the mutated input
+    #[serde(skip)]
+    pub new_cmpvals: HashMap<usize, Vec<CmpValues>>,
+    /// The list of logged idx and headers retrieved by runnning the mutated input
+    #[serde(default)]
+    pub headers: Vec<(usize, AFLppCmpLogHeader)>,
+    /// The number of logged entries that were dropped because their operands did not match the
+    /// `shape` of their header
+    #[serde(default)]
+    pub rejected_entries: usize,
+    /// If set, the hit counts of the headers are also stored bucketed in `hit_buckets`
+    #[serde(default)]
+    pub classify_hits: bool,
+    /// The hit counts of the headers, bucketed by [`classify_hits`], if `classify_hits` is set
+    #[serde(skip)]
+    pub hit_buckets: HashMap<usize, u8>,
+}
+
+libafl_bolts::impl_serdeany!(AFLppCmpValuesMetadata);
+
+impl AFLppCmpValuesMetadata {
+    /// Constructor for `AFLppCmpValuesMetadata`
+    #[must_use]
     pub fn new() -> Self {
         Self {
             orig_cmpvals: HashMap::new(),
             new_cmpvals: HashMap::new(),
             headers: Vec::new(),
+            rejected_entries: 0,
+            classify_hits: false,
+            hit_buckets: HashMap::new(),
+        }
+    }
+
+    /// Constructor for `AFLppCmpValuesMetadata` that also stores the bucketed hit counts
+    /// (see [`classify_hits`]) of each header
+    #[must_use]
+    pub fn with_classified_hits() -> Self {
+        Self {
+            classify_hits: true,
+            ..Self::new()
+        }
+    }
+
+    /// Adds the header of the cmp at `idx`, and its bucketed hit count if `classify_hits` is set
+    pub fn push_header(&mut self, idx: usize, header: AFLppCmpLogHeader) {
+        if self.classify_hits {
+            self.hit_buckets.insert(idx, classify_hits(header.hits()));
         }
+        self.headers.push((idx, header));
     }
 
     /// Getter for `orig_cmpvals`
@@ -391,20 +2401,96 @@ impl AFLppCmpValuesMetadata {
         &self.orig_cmpvals
     }
 
-    /// Getter for `new_cmpvals`
+    /// Getter for `new_cmpvals`
+    #[must_use]
+    pub fn new_cmpvals(&self) -> &HashMap<usize, Vec<CmpValues>> {
+        &self.new_cmpvals
+    }
+
+    /// Getter for `headers`
+    #[must_use]
+    pub fn headers(&self) -> &Vec<(usize, AFLppCmpLogHeader)> {
+        &self.headers
+    }
+
+    /// The sorted indices of the cmps that the mutated input satisfied, but the original input did not,
+    /// i.e., that have equal operands in some execution logged in `new_cmpvals`, but in none logged in `orig_cmpvals`.
+    ///
+    /// Cmps without values logged for the original input are not considered solved.
+    #[must_use]
+    pub fn solved_cmps(&self) -> Vec<usize> {
+        let mut solved: Vec<usize> = self
+            .new_cmpvals
+            .iter()
+            .filter(|(idx, new_values)| {
+                self.orig_cmpvals.get(*idx).is_some_and(|orig_values| {
+                    !orig_values.is_empty()
+                        && !orig_values.iter().any(CmpValues::has_equal_operands)
+                        && new_values.iter().any(CmpValues::has_equal_operands)
+                })
+            })
+            .map(|(idx, _)| *idx)
+            .collect();
+        solved.sort_unstable();
+        solved
+    }
+
+    /// Exports the headers and both maps of logged comparison values as JSON, e.g., for offline analysis.
+    ///
+    /// The headers are exported with their decoded bitfields.
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> Result<alloc::string::String, Error> {
+        /// A decoded [`AFLppCmpLogHeader`] with the index of its cmp
+        #[derive(Serialize)]
+        struct HeaderExport {
+            idx: usize,
+            hits: u32,
+            shape: u32,
+            #[serde(rename = "type")]
+            kind: u8,
+            attribute: u32,
+        }
+
+        #[derive(Serialize)]
+        struct Export<'a> {
+            orig_cmpvals: &'a HashMap<usize, Vec<CmpValues>>,
+            new_cmpvals: &'a HashMap<usize, Vec<CmpValues>>,
+            headers: Vec<HeaderExport>,
+        }
+
+        let headers = self
+            .headers
+            .iter()
+            .map(|(idx, header)| HeaderExport {
+                idx: *idx,
+                hits: header.hits(),
+                shape: header.shape(),
+                kind: header._type(),
+                attribute: header.attribute(),
+            })
+            .collect();
+        serde_json::to_string(&Export {
+            orig_cmpvals: &self.orig_cmpvals,
+            new_cmpvals: &self.new_cmpvals,
+            headers,
+        })
+        .map_err(|e| Error::serialize(format!("Failed to export the cmp values as JSON: {e}")))
+    }
+
+    /// Getter for `rejected_entries`, the number of logged entries with mismatching `shape`s so far
     #[must_use]
-    pub fn new_cmpvals(&self) -> &HashMap<usize, Vec<CmpValues>> {
-        &self.new_cmpvals
+    pub fn rejected_entries(&self) -> usize {
+        self.rejected_entries
     }
 
-    /// Getter for `headers`
+    /// Getter for `hit_buckets`
     #[must_use]
-    pub fn headers(&self) -> &Vec<(usize, AFLppCmpLogHeader)> {
-        &self.headers
+    pub fn hit_buckets(&self) -> &HashMap<usize, u8> {
+        &self.hit_buckets
     }
 }
 
-#[derive(Debug, Copy, Clone, BitfieldStruct)]
+#[derive(Debug, Copy, Clone, BitfieldStruct, Serialize, Deserialize)]
 #[repr(C, packed)]
 /// Comparison header, used to describe a set of comparison values efficiently.
 ///
@@ -418,6 +2504,9 @@ impl AFLppCmpValuesMetadata {
 /// - attribute: OR-ed bitflags describing whether the comparison is <, >, =, <=, >=, or transform
 /// - overflow:  Whether the comparison overflows
 /// - reserved:  Reserved for future use
+///
+/// It is (de)serialized as its two raw bytes, see [`AFLppCmpLogHeader::raw`].
+#[serde(transparent)]
 pub struct AFLppCmpLogHeader {
     /// The header values
     #[bitfield(name = "hits", ty = "u32", bits = "0..=5")] // 6 bits up to 63 entries, we have CMP_MAP_H = 32 (so using half of it)
@@ -427,3 +2516,1207 @@ pub struct AFLppCmpLogHeader {
     // 16 types for arithmetic comparison types
     pub data: [u8; 2],
 }
+
+impl AFLppCmpLogHeader {
+    /// Creates a header from its raw bitfield data, in the layout AFL++ uses
+    #[must_use]
+    pub const fn from_raw(data: [u8; 2]) -> Self {
+        Self { data }
+    }
+
+    /// The raw bitfield data of this header, in the layout AFL++ uses
+    #[must_use]
+    pub const fn raw(&self) -> [u8; 2] {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, vec::Vec};
+    use core::num::NonZeroUsize;
+
+    use libafl_bolts::{
+        ownedref::OwnedRefMut,
+        rands::{Rand, StdRand},
+        serdeany::SerdeAnyMap,
+        Error, HasLen,
+    };
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        executors::ExitKind,
+        inputs::NopInput,
+        observers::{
+            cmp::{
+                classify_hits, collect_cmp_values, diff_cmp_maps, AFLppCmpLogHeader,
+                AFLppCmpValuesMetadata, CmpHitCountObserver, CmpKind, CmpMap,
+                CmpMapUtilizationObserver, CmpObserver, CmpOp, CmpValues, CmpValuesMetadata,
+                CmplogBytes, CombinedCmpObserver, ConstCmpMap, FilteredCmpObserver,
+                RingCmpObserver, StdCmpObserver,
+            },
+            Observer,
+        },
+        state::NopState,
+        HasMetadata,
+    };
+
+    /// The values logged for each execution of each cmp
+    #[derive(Debug, Serialize, Deserialize)]
+    struct TestCmpMap(Vec<Vec<CmpValues>>);
+
+    impl HasLen for TestCmpMap {
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    impl CmpMap for TestCmpMap {
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn executions_for(&self, idx: usize) -> usize {
+            self.0[idx].len()
+        }
+
+        fn usable_executions_for(&self, idx: usize) -> usize {
+            self.0[idx].len()
+        }
+
+        fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues> {
+            self.0.get(idx)?.get(execution).cloned()
+        }
+
+        fn reset(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    /// A [`TestCmpMap`] counting the cmps cleared by its resets
+    #[derive(Debug, Serialize)]
+    struct ResetCountingCmpMap {
+        map: TestCmpMap,
+        cleared: usize,
+        // tracked like a map written from Rust would, `None` until the first reset
+        used: Option<usize>,
+    }
+
+    impl ResetCountingCmpMap {
+        fn push(&mut self, idx: usize, values: CmpValues) {
+            self.map.0[idx].push(values);
+            self.used = self.used.map(|used| used.max(idx + 1));
+        }
+    }
+
+    impl HasLen for ResetCountingCmpMap {
+        fn len(&self) -> usize {
+            self.map.0.len()
+        }
+    }
+
+    impl CmpMap for ResetCountingCmpMap {
+        fn len(&self) -> usize {
+            self.map.0.len()
+        }
+
+        fn executions_for(&self, idx: usize) -> usize {
+            self.map.executions_for(idx)
+        }
+
+        fn usable_executions_for(&self, idx: usize) -> usize {
+            self.map.usable_executions_for(idx)
+        }
+
+        fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues> {
+            self.map.values_of(idx, execution)
+        }
+
+        fn reset(&mut self) -> Result<(), Error> {
+            let len = self.map.0.len();
+            self.reset_used(len)
+        }
+
+        fn reset_used(&mut self, count: usize) -> Result<(), Error> {
+            for values in &mut self.map.0[..count] {
+                values.clear();
+            }
+            self.cleared += count;
+            self.used = Some(0);
+            Ok(())
+        }
+
+        fn used_len(&self) -> Option<usize> {
+            self.used
+        }
+    }
+
+    #[test]
+    fn test_std_cmp_observer_usable_count() {
+        let mut map = TestCmpMap(vec![vec![CmpValues::U8((1, 2, false))]; 4]);
+        let mut observer = StdCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), false);
+        assert_eq!(observer.usable_count(), 4);
+        observer.set_usable_count(2);
+        assert_eq!(observer.usable_count(), 2);
+
+        let mut map = TestCmpMap(vec![vec![CmpValues::U8((1, 2, false))]; 4]);
+        let mut size = 3;
+        let mut observer =
+            StdCmpObserver::with_size_ref("cmplog", OwnedRefMut::Ref(&mut map), false, &mut size);
+        assert_eq!(observer.usable_count(), 3);
+        // sizes beyond the map are clamped
+        observer.set_usable_count(16);
+        assert_eq!(observer.usable_count(), 4);
+        drop(observer);
+        assert_eq!(size, 16);
+    }
+
+    #[test]
+    fn test_std_cmp_observer_skip_on_crash() -> Result<(), Error> {
+        let mut state = MetadataState::default();
+        let mut map = TestCmpMap(vec![vec![CmpValues::U8((1, 2, false))]]);
+        let mut observer =
+            StdCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), true).skip_on_crash(true);
+
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        assert_eq!(state.metadata::<CmpValuesMetadata>()?.list.len(), 1);
+
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Crash)?;
+        assert!(state.metadata::<CmpValuesMetadata>()?.list.is_empty());
+
+        Ok(())
+    }
+
+    /// A state with nothing but metadata, and the perf monitor required by `introspection`
+    #[derive(Default)]
+    struct MetadataState {
+        metadata: SerdeAnyMap,
+        #[cfg(feature = "introspection")]
+        perf: crate::monitors::ClientPerfMonitor,
+    }
+
+    impl HasMetadata for MetadataState {
+        fn metadata_map(&self) -> &SerdeAnyMap {
+            &self.metadata
+        }
+
+        fn metadata_map_mut(&mut self) -> &mut SerdeAnyMap {
+            &mut self.metadata
+        }
+    }
+
+    #[cfg(feature = "introspection")]
+    impl crate::state::HasClientPerfMonitor for MetadataState {
+        fn introspection_monitor(&self) -> &crate::monitors::ClientPerfMonitor {
+            &self.perf
+        }
+
+        fn introspection_monitor_mut(&mut self) -> &mut crate::monitors::ClientPerfMonitor {
+            &mut self.perf
+        }
+    }
+
+    #[test]
+    fn test_std_cmp_observer_metadata_state() -> Result<(), Error> {
+        let mut state = MetadataState::default();
+        let mut map = TestCmpMap(vec![vec![CmpValues::U8((1, 2, false))]; 2]);
+        let mut observer = StdCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), true);
+
+        observer.pre_exec(&mut state, &NopInput {})?;
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        assert_eq!(state.metadata::<CmpValuesMetadata>()?.list.len(), 2);
+
+        // the time is accounted to the current stage
+        #[cfg(feature = "introspection")]
+        assert_eq!(state.perf.used_stages().count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmp_values_metadata_max_entries() {
+        let mut map = TestCmpMap(vec![vec![CmpValues::U8((1, 2, false))]; 1000]);
+        // the most executed cmp should be kept
+        map.0[500] = vec![
+            CmpValues::U16((1, 2, false)),
+            CmpValues::U16((3, 4, false)),
+            CmpValues::U16((5, 6, false)),
+        ];
+
+        let mut meta = CmpValuesMetadata::new();
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list.len(), 1002);
+
+        let mut meta = CmpValuesMetadata::with_max_entries(10);
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list.len(), 10);
+        assert_eq!(meta.list[..3], map.0[500]);
+    }
+
+    #[test]
+    fn test_cmp_values_metadata_signed_loop() {
+        // a signed `i32` loop index counting down through zero
+        let countdown = [2, 1, 0, u32::MAX, u32::MAX - 1, u32::MAX - 2]
+            .map(|i| CmpValues::U32((i, 100, false)))
+            .to_vec();
+        let mut map = TestCmpMap(vec![
+            countdown,
+            vec![CmpValues::U32((1, 7, false)); 6],
+            vec![CmpValues::U8((1, 0, false)), CmpValues::U8((0, 0, false))],
+        ]);
+        // the same for an `i8` loop index
+        map.0[2].extend([255_u8, 254, 253, 252].map(|i| CmpValues::U8((i, 0, false))));
+
+        let mut meta = CmpValuesMetadata::new();
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list, map.0[1]);
+    }
+
+    /// Without loop detection, the values of loop counters survive
+    #[test]
+    fn test_detect_loops() {
+        let counter = (0..6).map(|i| CmpValues::U8((i, 10, false))).collect();
+        let mut map = TestCmpMap(vec![counter, vec![CmpValues::U16((1, 2, false))]]);
+
+        let mut meta = CmpValuesMetadata::new();
+        assert!(meta.detect_loops());
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list, map.0[1]);
+
+        meta.set_detect_loops(false);
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list, map.0.concat());
+
+        // the observer passes it on to the metadata
+        let mut state = MetadataState::default();
+        let mut observer =
+            StdCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), true).detect_loops(false);
+        observer
+            .post_exec(&mut state, &NopInput {}, &ExitKind::Ok)
+            .unwrap();
+        let meta = state.metadata::<CmpValuesMetadata>().unwrap();
+        assert!(!meta.detect_loops());
+        assert_eq!(meta.list.len(), 7);
+    }
+
+    #[test]
+    fn test_filtered_cmp_observer() -> Result<(), Error> {
+        let mut state = NopState::<NopInput>::new();
+        let mut map = TestCmpMap(vec![
+            vec![CmpValues::U8((0, 1, false))],
+            vec![CmpValues::U8((2, 3, false))],
+            vec![CmpValues::U8((4, 5, false))],
+            vec![CmpValues::U8((6, 7, false))],
+        ]);
+        let inner = StdCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), false);
+        let mut observer = FilteredCmpObserver::new(inner, |idx| (1..3).contains(&idx));
+
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        assert_eq!(
+            state.metadata::<CmpValuesMetadata>()?.list,
+            [CmpValues::U8((2, 3, false)), CmpValues::U8((4, 5, false))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_aflpp_cmp_values_metadata_to_json() -> Result<(), Error> {
+        let mut header = AFLppCmpLogHeader { data: [0; 2] };
+        header.set_hits(2);
+        header.set_shape(3);
+
+        let mut meta = AFLppCmpValuesMetadata::new();
+        meta.headers.push((7, header));
+        meta.orig_cmpvals
+            .insert(7, vec![CmpValues::U32((1, 2, false))]);
+
+        let json: serde_json::Value = serde_json::from_str(&meta.to_json()?).unwrap();
+        assert_eq!(json["headers"][0]["idx"], 7);
+        assert_eq!(json["headers"][0]["hits"], 2);
+        assert_eq!(json["headers"][0]["shape"], 3);
+        assert_eq!(json["orig_cmpvals"]["7"][0]["U32"][1], 2);
+        assert!(json["new_cmpvals"].as_object().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    /// Metadata serialized before the headers and counters were added still deserializes
+    #[test]
+    fn test_aflpp_cmp_values_metadata_serde_default() {
+        let meta: AFLppCmpValuesMetadata = serde_json::from_str("{}").unwrap();
+        assert!(meta.headers.is_empty());
+        assert_eq!(meta.rejected_entries, 0);
+        assert!(!meta.classify_hits);
+    }
+
+    #[test]
+    fn test_aflpp_cmp_log_header_raw() {
+        let mut header = AFLppCmpLogHeader::from_raw([0; 2]);
+        header.set_hits(5);
+        header.set_shape(3);
+        header.set_attribute(CmpOp::LESSER.bits().into());
+
+        let copy = AFLppCmpLogHeader::from_raw(header.raw());
+        assert_eq!(copy.hits(), 5);
+        assert_eq!(copy.shape(), 3);
+        assert_eq!(CmpOp::from_attribute(copy.attribute()), CmpOp::LESSER);
+
+        // the headers of the metadata survive a round trip, as their raw bytes
+        let mut meta = AFLppCmpValuesMetadata::new();
+        meta.headers.push((7, header));
+        let bytes = postcard::to_allocvec(&header).unwrap();
+        assert_eq!(bytes, header.raw());
+        let meta: AFLppCmpValuesMetadata =
+            postcard::from_bytes(&postcard::to_allocvec(&meta).unwrap()).unwrap();
+        assert_eq!(meta.headers().len(), 1);
+        assert_eq!(meta.headers()[0].0, 7);
+        assert_eq!(meta.headers()[0].1.raw(), header.raw());
+    }
+
+    #[test]
+    fn test_classify_hits() {
+        let table = [
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 4),
+            (4, 8),
+            (7, 8),
+            (8, 16),
+            (15, 16),
+            (16, 32),
+            (31, 32),
+            (32, 64),
+            (127, 64),
+            (128, 128),
+            (u32::MAX, 128),
+        ];
+        for (hits, bucket) in table {
+            assert_eq!(classify_hits(hits), bucket, "hits: {hits}");
+        }
+
+        let mut header = AFLppCmpLogHeader { data: [0; 2] };
+        header.set_hits(5);
+        let mut meta = AFLppCmpValuesMetadata::new();
+        meta.push_header(1, header);
+        assert!(meta.hit_buckets().is_empty());
+
+        let mut meta = AFLppCmpValuesMetadata::with_classified_hits();
+        meta.push_header(1, header);
+        assert_eq!(meta.headers()[0].1.hits(), 5);
+        assert_eq!(meta.hit_buckets()[&1], 8);
+    }
+
+    #[test]
+    fn test_std_cmp_observer_reset_used() -> Result<(), Error> {
+        let mut state = NopState::<NopInput>::new();
+        let mut map = ResetCountingCmpMap {
+            map: TestCmpMap(vec![vec![]; 65536]),
+            cleared: 0,
+            used: None,
+        };
+        let mut observer = StdCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), false);
+
+        // Without a previous execution, the whole map is cleared
+        observer.pre_exec(&mut state, &NopInput {})?;
+        assert_eq!(observer.cmp_map().cleared, 65536);
+
+        // The target only logs a few cmps at the start of the map
+        observer
+            .cmp_map_mut()
+            .push(99, CmpValues::U8((1, 2, false)));
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        observer.pre_exec(&mut state, &NopInput {})?;
+        assert_eq!(observer.cmp_map().cleared, 65536 + 100);
+        assert_eq!(observer.cmp_map().executions_for(99), 0);
+
+        // Nothing was logged, so nothing needs to be cleared
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        observer.pre_exec(&mut state, &NopInput {})?;
+        assert_eq!(observer.cmp_map().cleared, 65536 + 100);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ring_cmp_observer_wrap_around() -> Result<(), Error> {
+        let mut state = NopState::<NopInput>::new();
+        let mut map = TestCmpMap(vec![
+            vec![CmpValues::U8((0, 1, false)), CmpValues::U8((2, 3, false))],
+            vec![CmpValues::U16((4, 5, false))],
+        ]);
+        let mut observer = RingCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), 4);
+
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        assert_eq!(observer.buffered(), 3);
+        // The second execution overwrites the two oldest entries
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        assert_eq!(observer.buffered(), 4);
+
+        let mut meta = CmpValuesMetadata::new();
+        observer.drain_into(&mut meta);
+        assert_eq!(
+            meta.list,
+            [
+                CmpValues::U16((4, 5, false)),
+                CmpValues::U8((0, 1, false)),
+                CmpValues::U8((2, 3, false)),
+                CmpValues::U16((4, 5, false)),
+            ]
+        );
+        assert_eq!(observer.buffered(), 0);
+        assert!(state.metadata::<CmpValuesMetadata>().is_err());
+
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        let mut meta = CmpValuesMetadata::new();
+        observer.drain_into(&mut meta);
+        assert_eq!(meta.list, map.0.concat());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ring_cmp_observer_serde() -> Result<(), Error> {
+        let mut state = NopState::<NopInput>::new();
+        let mut map = TestCmpMap(vec![
+            vec![CmpValues::U8((0, 1, false)), CmpValues::U8((2, 3, false))],
+            vec![CmpValues::U16((4, 5, false))],
+        ]);
+        let mut observer = RingCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), 4);
+        // wrap around, so the write position is not at the start of the buffer
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+
+        let serialized = postcard::to_allocvec(&observer)?;
+        let mut observer: RingCmpObserver<TestCmpMap> = postcard::from_bytes(&serialized)?;
+        assert_eq!(observer.capacity(), 4);
+        assert_eq!(observer.buffered(), 0);
+
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        let mut meta = CmpValuesMetadata::new();
+        observer.drain_into(&mut meta);
+        assert_eq!(meta.list, map.0.concat());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmp_values_metadata_kinds() {
+        let bytes = CmplogBytes::from_buf_and_len([b'A'; 32], 4);
+        let mut map = TestCmpMap(vec![
+            vec![CmpValues::U32((1, 2, false))],
+            vec![CmpValues::Bytes((bytes, bytes))],
+        ]);
+
+        let mut meta = CmpValuesMetadata::new();
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.kinds, [CmpKind::Instruction, CmpKind::Routine]);
+
+        // values added by hand get a guessed kind
+        meta.list.push(CmpValues::U8((1, 2, true)));
+        let kinds: Vec<_> = meta.iter_with_kinds().map(|(kind, _)| kind).collect();
+        assert_eq!(
+            kinds,
+            [CmpKind::Instruction, CmpKind::Routine, CmpKind::Instruction]
+        );
+    }
+
+    #[test]
+    fn test_cmp_values_sort_key() {
+        let bytes = |bytes: &[u8]| {
+            let mut buf = [0; 32];
+            buf[..bytes.len()].copy_from_slice(bytes);
+            CmplogBytes::from_buf_and_len(buf, bytes.len() as u8)
+        };
+        // sorted, as the keys order them
+        let sorted = vec![
+            CmpValues::U8((1, 2, false)),
+            CmpValues::U8((2, 1, false)),
+            CmpValues::U8((1, 2, true)),
+            CmpValues::U16((0, 0, false)),
+            CmpValues::U32((0x1234, 7, false)),
+            CmpValues::U64((u64::MAX, 0, false)),
+            CmpValues::U64((0, 0, true)),
+            CmpValues::Bytes((bytes(b"abc"), bytes(b"abd"))),
+            CmpValues::Bytes((bytes(b"abd"), bytes(b""))),
+            CmpValues::Bytes((bytes(b"0123456789a"), bytes(b"x"))),
+            CmpValues::Vector {
+                width: 128,
+                v0: vec![1; 16],
+                v1: vec![2; 16],
+            },
+            CmpValues::Vector {
+                width: 256,
+                v0: vec![0; 32],
+                v1: vec![2; 32],
+            },
+        ];
+        for (lower, higher) in sorted.iter().zip(&sorted[1..]) {
+            assert!(lower.sort_key() < higher.sort_key(), "{lower} >= {higher}");
+        }
+
+        // operands sharing their first bytes and length still differ, by their hash
+        let long_a = CmpValues::Bytes((bytes(b"0123456789a"), bytes(b"x")));
+        let long_b = CmpValues::Bytes((bytes(b"0123456789b"), bytes(b"x")));
+        assert_ne!(long_a.sort_key(), long_b.sort_key());
+        // the hash is fixed across runs
+        assert_eq!(
+            long_a.sort_key(),
+            CmpValues::Bytes((bytes(b"0123456789a"), bytes(b"x"))).sort_key()
+        );
+
+        let mut with_long_b = sorted.clone();
+        with_long_b.push(long_b);
+        let mut expected = with_long_b.clone();
+        expected.sort_by_key(CmpValues::sort_key);
+        let mut rand = StdRand::with_seed(1337);
+        for _ in 0..16 {
+            let mut shuffled = with_long_b.clone();
+            for i in (1..shuffled.len()).rev() {
+                shuffled.swap(i, rand.below(NonZeroUsize::new(i + 1).unwrap()));
+            }
+            shuffled.sort_by_key(CmpValues::sort_key);
+            assert_eq!(shuffled, expected);
+        }
+    }
+
+    #[test]
+    fn test_cmp_values_display() {
+        use alloc::format;
+
+        assert_eq!(
+            format!("{}", CmpValues::U32((0x464c_457f, 0, true))),
+            "U32(0x464c457f, 0x00000000, v0 const)"
+        );
+        assert_eq!(
+            format!("{}", CmpValues::U8((0xa, 0xff, false))),
+            "U8(0x0a, 0xff)"
+        );
+
+        let mut buf = [0; 32];
+        buf[..5].copy_from_slice(b"\x7fELF\0");
+        let v0 = CmplogBytes::from_buf_and_len(buf, 5);
+        let v1 = CmplogBytes::from_buf_and_len(buf, 0);
+        assert_eq!(
+            format!("{}", CmpValues::Bytes((v0, v1))),
+            "Bytes(7f454c4600 |.ELF.|,  ||)"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                CmpValues::Vector {
+                    width: 16,
+                    v0: b"a b".to_vec(),
+                    v1: vec![0xfe, 0x41],
+                }
+            ),
+            "Vector16(612062 |a b|, fe41 |.A|)"
+        );
+    }
+
+    #[test]
+    fn test_cmplog_bytes_as_ref() {
+        let bytes = CmplogBytes::from_buf_and_len(*b"GET /index.html HTTP/1.1\r\n\r\n\0\0\0\0", 3);
+        assert_eq!(bytes.as_ref(), b"GET");
+        assert_eq!(bytes.iter().copied().collect::<Vec<_>>(), b"GET");
+
+        let mut sum = 0;
+        for byte in &bytes {
+            sum += u32::from(*byte);
+        }
+        assert_eq!(sum, u32::from(b'G') + u32::from(b'E') + u32::from(b'T'));
+    }
+
+    #[test]
+    fn test_combined_cmp_observer() -> Result<(), Error> {
+        let mut first = ResetCountingCmpMap {
+            map: TestCmpMap(vec![vec![CmpValues::U8((1, 2, false))], vec![]]),
+            cleared: 0,
+            used: None,
+        };
+        let mut second = ResetCountingCmpMap {
+            map: TestCmpMap(vec![vec![
+                CmpValues::U16((3, 4, false)),
+                CmpValues::U16((5, 6, false)),
+            ]]),
+            cleared: 0,
+            used: None,
+        };
+        let mut observer = CombinedCmpObserver::new(
+            "combined",
+            vec![OwnedRefMut::Ref(&mut first), OwnedRefMut::Ref(&mut second)],
+            true,
+        );
+        assert_eq!(observer.usable_count(), 3);
+        assert_eq!(observer.cmp_map().executions_for(1), 0);
+        assert_eq!(observer.cmp_map().executions_for(2), 2);
+        assert_eq!(
+            observer.cmp_map().values_of(2, 1),
+            Some(CmpValues::U16((5, 6, false)))
+        );
+
+        let mut state = NopState::<NopInput>::new();
+        let input = NopInput {};
+        Observer::<_, NopState<NopInput>>::post_exec(
+            &mut observer,
+            &mut state,
+            &input,
+            &ExitKind::Ok,
+        )?;
+        assert_eq!(
+            state.metadata::<CmpValuesMetadata>()?.list,
+            [
+                CmpValues::U8((1, 2, false)),
+                CmpValues::U16((3, 4, false)),
+                CmpValues::U16((5, 6, false))
+            ]
+        );
+
+        observer.pre_exec(&mut state, &input)?;
+        assert_eq!(observer.cmp_map().executions_for(0), 0);
+        assert_eq!(observer.cmp_map().executions_for(2), 0);
+        drop(observer);
+        assert_eq!((first.cleared, second.cleared), (2, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_equal_operands() {
+        let equal = CmplogBytes::from_buf_and_len([b'A'; 32], 4);
+        let mut buf = [b'A'; 32];
+        buf[3] = b'B';
+        let unequal = CmplogBytes::from_buf_and_len(buf, 4);
+        // the bytes after the logged length don't matter
+        let equal_prefix = CmplogBytes::from_buf_and_len(buf, 3);
+        let mut map = TestCmpMap(vec![
+            vec![CmpValues::U8((1, 1, false)), CmpValues::U8((1, 2, false))],
+            vec![CmpValues::U64((7, 7, true))],
+            vec![
+                CmpValues::Bytes((equal, unequal)),
+                CmpValues::Bytes((equal_prefix, CmplogBytes::from_buf_and_len([b'A'; 32], 3))),
+            ],
+        ]);
+
+        let mut meta = CmpValuesMetadata::new();
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list.len(), 5);
+
+        meta.set_skip_equal_operands(true);
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(
+            meta.list,
+            [
+                CmpValues::U8((1, 2, false)),
+                CmpValues::Bytes((equal, unequal))
+            ]
+        );
+        assert_eq!(meta.kinds.len(), 2);
+    }
+
+    /// Logs `(shape, v0, v1)` per cmp, with the AFL++ shapes `0`, `1`, `3` and `7`
+    #[derive(Debug)]
+    struct ShapedCmpMap(Vec<(u8, u64, u64)>);
+
+    impl CmpMap for ShapedCmpMap {
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+
+        fn executions_for(&self, _idx: usize) -> usize {
+            1
+        }
+
+        fn usable_executions_for(&self, _idx: usize) -> usize {
+            1
+        }
+
+        fn values_of(&self, idx: usize, _execution: usize) -> Option<CmpValues> {
+            let (shape, v0, v1) = self.0[idx];
+            match shape {
+                0 => Some(CmpValues::U8((v0 as u8, v1 as u8, false))),
+                1 => Some(CmpValues::U16((v0 as u16, v1 as u16, false))),
+                3 => Some(CmpValues::U32((v0 as u32, v1 as u32, false))),
+                7 => Some(CmpValues::U64((v0, v1, false))),
+                _ => None,
+            }
+        }
+
+        fn raw_operands_of(&self, idx: usize, _execution: usize) -> Option<(u64, u64)> {
+            let (_, v0, v1) = self.0[idx];
+            Some((v0, v1))
+        }
+
+        fn reset(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_keep_ambiguous() {
+        // shape 5 is out of range
+        let mut map = ShapedCmpMap(vec![(1, 0x1234, 0x4321), (5, 0x0102_0304_0506, 7)]);
+
+        let mut meta = CmpValuesMetadata::new();
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list, [CmpValues::U16((0x1234, 0x4321, false))]);
+
+        meta.set_keep_ambiguous(true);
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list.len(), 2);
+        let CmpValues::Bytes((v0, v1)) = &meta.list[1] else {
+            panic!("ambiguous cmps are kept as bytes");
+        };
+        assert_eq!(v0.as_ref(), [6, 5, 4, 3, 2, 1, 0, 0]);
+        assert_eq!(v1.as_ref(), [7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(meta.kinds.len(), 2);
+    }
+
+    #[test]
+    fn test_min_numeric_bytes() {
+        let bytes = CmpValues::Bytes((
+            CmplogBytes::from_buf_and_len([b'A'; 32], 1),
+            CmplogBytes::from_buf_and_len([b'B'; 32], 1),
+        ));
+        let mut map = TestCmpMap(vec![
+            vec![CmpValues::U8((0, 1, false))],
+            vec![CmpValues::U16((2, 3, false))],
+            vec![CmpValues::U32((4, 5, false))],
+            vec![CmpValues::U64((6, 7, false))],
+            vec![bytes.clone()],
+        ]);
+
+        let mut meta = CmpValuesMetadata::new();
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list.len(), 5);
+
+        meta.set_min_numeric_bytes(2);
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(
+            meta.list,
+            [
+                CmpValues::U16((2, 3, false)),
+                CmpValues::U32((4, 5, false)),
+                CmpValues::U64((6, 7, false)),
+                bytes.clone()
+            ]
+        );
+
+        meta.set_min_numeric_bytes(8);
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list, [CmpValues::U64((6, 7, false)), bytes]);
+        assert_eq!(meta.kinds.len(), 2);
+    }
+
+    #[test]
+    fn test_record_positions() {
+        let mut map = TestCmpMap(vec![
+            vec![CmpValues::U8((1, 2, false)), CmpValues::U8((3, 4, false))],
+            vec![],
+            vec![CmpValues::U16((5, 6, false))],
+        ]);
+
+        let mut meta = CmpValuesMetadata::new();
+        meta.add_from(map.0.len(), &mut map);
+        assert!(meta.positions.is_empty());
+
+        meta.set_record_positions(true);
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.positions, [(0, 0), (0, 1), (2, 0)]);
+        assert_eq!(
+            meta.iter_with_positions().nth(1),
+            Some(((0, 1), &CmpValues::U8((3, 4, false))))
+        );
+    }
+
+    #[test]
+    fn test_split_lanes() {
+        let values = CmpValues::Vector {
+            width: 64,
+            v0: vec![1, 0, 2, 0, 3, 0, 4, 0],
+            v1: vec![1, 0, 2, 0, 5, 0, 4, 0],
+        };
+        assert!(!values.is_numeric());
+        assert!(!values.has_equal_operands());
+        assert_eq!(
+            values.split_lanes(2).unwrap(),
+            [
+                CmpValues::U16((1, 1, false)),
+                CmpValues::U16((2, 2, false)),
+                CmpValues::U16((3, 5, false)),
+                CmpValues::U16((4, 4, false)),
+            ]
+        );
+        assert_eq!(
+            values.split_lanes(8).unwrap(),
+            [CmpValues::U64((
+                0x4_0003_0002_0001,
+                0x4_0005_0002_0001,
+                false
+            ))]
+        );
+        assert!(values.split_lanes(3).is_none());
+        assert!(values.split_lanes(16).is_none());
+        assert!(CmpValues::U8((1, 2, false)).split_lanes(1).is_none());
+    }
+
+    #[test]
+    fn test_to_tokens() {
+        let mut buf = [0; 32];
+        buf[..4].copy_from_slice(b"HTTP");
+        let http = CmplogBytes::from_buf_and_len(buf, 4);
+        let empty = CmplogBytes::from_buf_and_len([0; 32], 0);
+
+        let mut meta = CmpValuesMetadata::new();
+        meta.list = vec![
+            CmpValues::Bytes((http, empty)),
+            CmpValues::U8((0x41, 0x42, false)),
+            CmpValues::U16((0x1234, 0x10, true)),
+            CmpValues::U32((0xdead_beef, 0, false)),
+            CmpValues::U64((0x100, 0xdead_beef, false)),
+            // duplicates are dropped
+            CmpValues::Bytes((http, http)),
+        ];
+
+        assert_eq!(
+            meta.to_tokens(),
+            [
+                b"HTTP".to_vec(),
+                vec![0x34, 0x12],
+                vec![0xef, 0xbe, 0xad, 0xde],
+                vec![0, 1, 0, 0, 0, 0, 0, 0],
+                vec![0xef, 0xbe, 0xad, 0xde, 0, 0, 0, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_difficulty_hint() {
+        let mut buf = [0; 32];
+        buf[..4].copy_from_slice(b"GET ");
+        let get = CmplogBytes::from_buf_and_len(buf, 4);
+        buf[..4].copy_from_slice(b"GEX ");
+        let gex = CmplogBytes::from_buf_and_len(buf, 4);
+
+        assert_eq!(CmpValues::U8((1, 1, false)).difficulty_hint(), 0);
+        assert_eq!(CmpValues::U8((1, 2, false)).difficulty_hint(), 1);
+        assert_eq!(
+            CmpValues::U64((0, 0x1122_3344_5566_7788, true)).difficulty_hint(),
+            8
+        );
+        assert_eq!(CmpValues::U32((0x1234, 0x1299, false)).difficulty_hint(), 1);
+        assert_eq!(CmpValues::Bytes((get, gex)).difficulty_hint(), 1);
+        assert_eq!(
+            CmpValues::Bytes((get, CmplogBytes::from_buf_and_len(buf, 1))).difficulty_hint(),
+            3
+        );
+
+        let values = CmpValues::U8((1, 2, false));
+        assert_eq!(values.difficulty_hint_with_op(CmpOp::EQUAL), 1);
+        assert_eq!(values.difficulty_hint_with_op(CmpOp::LESSER), 9);
+        assert_eq!(
+            CmpValues::U8((2, 2, false)).difficulty_hint_with_op(CmpOp::GREATER | CmpOp::FP),
+            13
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let values = CmpValues::U32((0xffff_ff80, 0x1234, false));
+        assert_eq!(
+            values.normalize(CmpOp::EQUAL),
+            [CmpValues::U32((0xffff_ff80, 0x1234, false))]
+        );
+        assert_eq!(
+            values.normalize(CmpOp::EQUAL | CmpOp::TRANSFORM),
+            [
+                values.clone(),
+                CmpValues::U32((0x80ff_ffff, 0x3412_0000, false)),
+                CmpValues::U16((0xff80, 0x1234, false)),
+            ]
+        );
+        assert_eq!(
+            CmpValues::U16((0xfffe, 2, true)).normalize(CmpOp::TRANSFORM),
+            [
+                CmpValues::U16((0xfffe, 2, true)),
+                CmpValues::U16((0xfeff, 0x200, true)),
+                CmpValues::U8((0xfe, 2, true)),
+            ]
+        );
+        assert_eq!(
+            CmpValues::U8((1, 2, false)).normalize(CmpOp::TRANSFORM),
+            [CmpValues::U8((1, 2, false))]
+        );
+    }
+
+    #[test]
+    fn test_collect_cmp_values() {
+        let counter = (0..8).map(|i| CmpValues::U32((i, 100, false))).collect();
+        let map = TestCmpMap(vec![
+            vec![CmpValues::U8((1, 2, false)), CmpValues::U8((3, 4, false))],
+            counter,
+            vec![],
+            vec![CmpValues::U16((5, 6, true))],
+        ]);
+
+        let mut collected = vec![];
+        collect_cmp_values(map.0.len(), &map, |values| collected.push(values));
+        // the loop counter is left out
+        assert_eq!(
+            collected,
+            [
+                CmpValues::U8((1, 2, false)),
+                CmpValues::U8((3, 4, false)),
+                CmpValues::U16((5, 6, true))
+            ]
+        );
+
+        // the metadata collects the same values
+        let mut map = map;
+        let mut meta = CmpValuesMetadata::new();
+        meta.add_from(map.0.len(), &mut map);
+        assert_eq!(meta.list, collected);
+
+        collected.clear();
+        collect_cmp_values(1, &map, |values| collected.push(values));
+        assert_eq!(collected.len(), 2);
+    }
+
+    #[test]
+    fn test_const_cmp_map() {
+        let mut map = ConstCmpMap::<4, 2>::new();
+        assert_eq!(CmpMap::len(&map), 4);
+        map.push(1, CmpValues::U8((1, 2, false)));
+        map.push(1, CmpValues::U8((3, 4, false)));
+        map.push(1, CmpValues::U8((5, 6, false)));
+        map.push(3, CmpValues::U16((7, 8, true)));
+
+        assert_eq!(map.executions_for(0), 0);
+        assert_eq!(map.executions_for(1), 3);
+        assert_eq!(map.usable_executions_for(1), 2);
+        // the third execution overwrites the first one
+        assert_eq!(map.values_of(1, 0), Some(CmpValues::U8((5, 6, false))));
+        assert_eq!(map.values_of(1, 1), Some(CmpValues::U8((3, 4, false))));
+        assert_eq!(map.values_of(3, 0), Some(CmpValues::U16((7, 8, true))));
+        assert_eq!(map.values_of(3, 1), None);
+
+        let json = serde_json::to_string(&map).unwrap();
+        let restored: ConstCmpMap<4, 2> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.hits(), map.hits());
+        assert_eq!(restored.values_of(1, 1), map.values_of(1, 1));
+        assert_eq!(restored.used_len(), Some(4));
+        assert!(serde_json::from_str::<ConstCmpMap<4, 3>>(&json).is_err());
+
+        assert_eq!(map.used_len(), Some(4));
+        map.reset_used(2).unwrap();
+        assert_eq!(map.hits(), &[0, 0, 0, 1]);
+        assert_eq!(map.used_len(), Some(4));
+        map.reset().unwrap();
+        assert_eq!(map.used_len(), Some(0));
+        assert_eq!(map.values_of(3, 0), None);
+
+        let mut observer = StdCmpObserver::new("const", OwnedRefMut::Owned(Box::new(map)), false);
+        observer
+            .cmp_map_mut()
+            .push(0, CmpValues::U32((1, 2, false)));
+        assert_eq!(observer.usable_count(), 4);
+        assert_eq!(observer.cmp_map().executions_for(0), 1);
+    }
+
+    #[test]
+    fn test_const_cmp_map_boxed() {
+        // megabytes, more than the stack of a test thread
+        let mut map = ConstCmpMap::<4096, 32>::boxed();
+        assert_eq!(CmpMap::len(&*map), 4096);
+        assert_eq!(map.used_len(), Some(0));
+        assert!(
+            (0..4096).all(|idx| map.executions_for(idx) == 0 && map.values_of(idx, 0).is_none())
+        );
+
+        map.push(4095, CmpValues::U8((1, 2, false)));
+        assert_eq!(map.values_of(4095, 0), Some(CmpValues::U8((1, 2, false))));
+        assert_eq!(map.used_len(), Some(4096));
+    }
+
+    #[test]
+    fn test_cmp_map_snapshot_into() {
+        let mut map = ConstCmpMap::<2, 2>::new();
+        map.push(0, CmpValues::U8((1, 2, false)));
+        let mut orig = ConstCmpMap::<2, 2>::new();
+        map.snapshot_into(&mut orig).unwrap();
+
+        // the run of the mutated input overwrites the map, but not the snapshot
+        map.reset().unwrap();
+        map.push(0, CmpValues::U8((3, 2, false)));
+        assert_eq!(
+            diff_cmp_maps(&orig, &map),
+            [(
+                0,
+                CmpValues::U8((1, 2, false)),
+                CmpValues::U8((3, 2, false))
+            )]
+        );
+
+        let mut other = TestCmpMap(vec![]);
+        assert!(TestCmpMap(vec![]).snapshot_into(&mut other).is_err());
+    }
+
+    #[test]
+    fn test_normalize_overflow() {
+        let values = CmpValues::U32((5, 0xffff_fff0, false));
+        let candidates = values.normalize(CmpOp::EQUAL | CmpOp::OVERFLOW);
+        // the direct operands come first
+        assert_eq!(candidates[0], values);
+        for candidate in [
+            CmpValues::U32((5, 0xffff_fff1, false)),
+            CmpValues::U32((5, 0xffff_ffef, false)),
+            CmpValues::U32((5, 0xffff_ffff, false)),
+            CmpValues::U32((5, 0, false)),
+            CmpValues::U32((5, 0x7fff_ffff, false)),
+            CmpValues::U32((5, 0x8000_0000, false)),
+            CmpValues::U32((6, 0xffff_fff0, false)),
+            CmpValues::U32((4, 0xffff_fff0, false)),
+        ] {
+            assert!(candidates.contains(&candidate));
+        }
+        assert_eq!(candidates.len(), 13);
+
+        // the boundaries wrap at the width of the comparison
+        assert!(CmpValues::U8((0xff, 0, false))
+            .normalize(CmpOp::OVERFLOW)
+            .contains(&CmpValues::U8((0xff, 0xff, false))));
+        assert_eq!(
+            CmpValues::Bytes((
+                CmplogBytes::from_buf_and_len([0; 32], 1),
+                CmplogBytes::from_buf_and_len([1; 32], 1)
+            ))
+            .normalize(CmpOp::OVERFLOW)
+            .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_nul_trimmed() {
+        let mut buf = [b'X'; 32];
+        buf[..8].copy_from_slice(b"magic\0ab");
+        let v0 = CmplogBytes::from_buf_and_len(buf, 32);
+        let mut buf = [0; 32];
+        buf[..4].copy_from_slice(b"abc\0");
+        buf[5] = b'z';
+        let v1 = CmplogBytes::from_buf_and_len(buf, 8);
+        let values = CmpValues::Bytes((v0, v1));
+
+        assert_eq!(values.nul_trimmed(false), values);
+        let CmpValues::Bytes((v0, v1)) = values.nul_trimmed(true) else {
+            panic!("bytes stay bytes");
+        };
+        assert_eq!(v0.as_ref(), b"magic");
+        assert_eq!(v1.as_ref(), b"abc");
+
+        // without a nul, all bytes are kept, numeric values are not touched
+        let no_nul = CmplogBytes::from_buf_and_len([b'A'; 32], 4);
+        assert_eq!(no_nul.trim_at_nul(), no_nul);
+        let numeric = CmpValues::U16((0, 1, false));
+        assert_eq!(numeric.nul_trimmed(true), numeric);
+    }
+
+    #[test]
+    fn test_byte_prefixes() {
+        let mut buf = [0; 32];
+        buf[..6].copy_from_slice(b"GET /x");
+        let v0 = CmplogBytes::from_buf_and_len(buf, 6);
+        buf[..4].copy_from_slice(b"POST");
+        let v1 = CmplogBytes::from_buf_and_len(buf, 4);
+
+        let values = CmpValues::Bytes((v0, v1));
+        let prefixes: Vec<_> = values.byte_prefixes().collect();
+        assert_eq!(prefixes.len(), 4);
+        assert_eq!(prefixes[0], (&b"G"[..], &b"P"[..]));
+        assert_eq!(prefixes[3], (&b"GET "[..], &b"POST"[..]));
+
+        assert_eq!(CmpValues::U8((1, 2, false)).byte_prefixes().count(), 0);
+    }
+
+    #[test]
+    fn test_diff_cmp_maps() {
+        let orig = TestCmpMap(vec![
+            vec![CmpValues::U8((1, 2, false))],
+            vec![CmpValues::U16((3, 4, false)), CmpValues::U16((5, 6, false))],
+            vec![CmpValues::U32((7, 8, false))],
+            vec![],
+        ]);
+        let new = TestCmpMap(vec![
+            vec![CmpValues::U8((1, 2, false))],
+            vec![CmpValues::U16((3, 4, false)), CmpValues::U16((5, 9, false))],
+            vec![
+                CmpValues::U32((7, 10, false)),
+                CmpValues::U32((7, 11, false)),
+            ],
+            vec![CmpValues::U8((1, 2, false))],
+        ]);
+
+        assert_eq!(
+            diff_cmp_maps(&orig, &new),
+            [
+                (
+                    1,
+                    CmpValues::U16((5, 6, false)),
+                    CmpValues::U16((5, 9, false))
+                ),
+                (
+                    2,
+                    CmpValues::U32((7, 8, false)),
+                    CmpValues::U32((7, 10, false))
+                ),
+            ]
+        );
+        assert!(diff_cmp_maps(&orig, &orig).is_empty());
+    }
+
+    #[test]
+    fn test_cmp_map_utilization_observer() -> Result<(), Error> {
+        let mut state = NopState::<NopInput>::new();
+        let mut map = TestCmpMap(vec![vec![]; 8]);
+        let inner = StdCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), false);
+        let mut observer = CmpMapUtilizationObserver::new(inner);
+
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        assert_eq!(observer.peak_index(), None);
+
+        observer.cmp_map_mut().0[5] = vec![CmpValues::U8((1, 2, false)); 3];
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        observer.cmp_map_mut().0[5].clear();
+        observer.cmp_map_mut().0[2] = vec![CmpValues::U8((1, 2, false)); 1];
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+
+        // the peaks are kept over the whole run
+        assert_eq!(observer.peak_index(), Some(5));
+        assert_eq!(observer.peak_executions(), 3);
+        assert_eq!(observer.peak_hits(), 3);
+
+        Ok(())
+    }
+    #[test]
+    fn test_cmp_hit_count_observer() -> Result<(), Error> {
+        let mut state = NopState::<NopInput>::new();
+        let mut map = TestCmpMap(vec![vec![]; 4]);
+        let inner = StdCmpObserver::new("cmplog", OwnedRefMut::Ref(&mut map), false);
+        let mut observer = CmpHitCountObserver::new(inner);
+
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        assert_eq!(observer.total_hits(), 0);
+
+        observer.cmp_map_mut().0[1] = vec![CmpValues::U8((1, 2, false)); 3];
+        observer.cmp_map_mut().0[3] = vec![CmpValues::U16((1, 2, false)); 2];
+        observer.post_exec(&mut state, &NopInput {}, &ExitKind::Ok)?;
+        assert_eq!(observer.total_hits(), 5);
+
+        // the count is for the last run only
+        observer.pre_exec(&mut state, &NopInput {})?;
+        assert_eq!(observer.total_hits(), 0);
+
+        Ok(())
+    }
+}
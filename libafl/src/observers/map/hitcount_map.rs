@@ -19,8 +19,9 @@ use crate::{
     Error,
 };
 
-/// Hitcounts class lookup
-static COUNT_CLASS_LOOKUP: [u8; 256] = [
+/// The AFL hitcounts class lookup, bucketing the hitcounts `0`, `1`, `2`, `3`, `4-7`, `8-15`, `16-31`,
+/// `32-127` and `128-255` into one bit (or `0`) each, see [`classify_counts`]
+pub static COUNT_CLASS_LOOKUP: [u8; 256] = [
     0, 1, 2, 4, 8, 8, 8, 8, 16, 16, 16, 16, 16, 16, 16, 16, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32,
     32, 32, 32, 32, 32, 32, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64,
     64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64,
@@ -35,6 +36,16 @@ static COUNT_CLASS_LOOKUP: [u8; 256] = [
     128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128,
 ];
 
+/// Buckets the hitcounts in `map` into the AFL classes in place, see [`COUNT_CLASS_LOOKUP`].
+///
+/// Many slightly different hitcounts otherwise look like new coverage to the feedbacks.
+/// The [`HitcountsMapObserver`] does the same (faster) in its `post_exec`.
+pub fn classify_counts(map: &mut [u8]) {
+    for count in map {
+        *count = COUNT_CLASS_LOOKUP[*count as usize];
+    }
+}
+
 /// Hitcounts class lookup for 16-byte values
 static mut COUNT_CLASS_LOOKUP_16: Vec<u16> = vec![];
 
@@ -513,3 +524,39 @@ where
         self.base.as_iter_mut()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::AsSlice;
+
+    use crate::{
+        executors::ExitKind,
+        observers::{classify_counts, HitcountsMapObserver, Observer, StdMapObserver},
+        state::NopState,
+    };
+
+    const COUNTS: [u8; 20] = [
+        0, 1, 2, 3, 4, 7, 8, 15, 16, 31, 32, 127, 128, 255, 5, 12, 20, 64, 200, 3,
+    ];
+    const CLASSES: [u8; 20] = [
+        0, 1, 2, 4, 8, 8, 16, 16, 32, 32, 64, 64, 128, 128, 8, 16, 32, 64, 128, 4,
+    ];
+
+    #[test]
+    fn test_classify_counts() {
+        let mut map = COUNTS;
+        classify_counts(&mut map);
+        assert_eq!(map, CLASSES);
+    }
+
+    #[test]
+    fn test_hitcounts_map_observer_classes() {
+        let mut map = COUNTS;
+        let mut observer = HitcountsMapObserver::new(unsafe {
+            StdMapObserver::from_mut_ptr("hitcounts", map.as_mut_ptr(), map.len())
+        });
+        let mut state = NopState::<()>::new();
+        observer.post_exec(&mut state, &(), &ExitKind::Ok).unwrap();
+        assert_eq!(observer.as_slice(), CLASSES);
+    }
+}
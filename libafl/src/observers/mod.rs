@@ -32,14 +32,12 @@ use core::{fmt::Debug, time::Duration};
 #[cfg(feature = "std")]
 use std::time::Instant;
 
-#[cfg(not(feature = "std"))]
-use libafl_bolts::current_time;
-use libafl_bolts::{tuples::MatchName, Named};
+use libafl_bolts::{current_time, tuples::MatchName, Named};
 pub use list::*;
 use serde::{Deserialize, Serialize};
 pub use value::*;
 
-use crate::{executors::ExitKind, Error};
+use crate::{executors::ExitKind, Error, HasNamedMetadata};
 
 /// Observers observe different information about the target.
 /// They can then be used by various sorts of feedback.
@@ -398,9 +396,131 @@ impl Named for TimeObserver {
 
 impl<OTA, OTB, I, S> DifferentialObserver<OTA, OTB, I, S> for TimeObserver {}
 
+/// The runtimes of the harness as recorded by a [`HarnessTimingObserver`], in the named metadata of the state
+/// under the name of the observer, see [`HarnessTimingMetadata::of`].
+///
+/// Timeouts are not slow executions but aborted ones, so they are counted separately, and not in `max` or `slowest`.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HarnessTimingMetadata {
+    /// The runtime of the last execution, if any
+    pub last: Option<Duration>,
+    /// The runtime of the slowest execution so far, if any
+    pub max: Option<Duration>,
+    /// The number of recorded executions
+    pub count: u64,
+    /// The summed up runtime of all recorded executions
+    pub total: Duration,
+    /// The runtime of the slowest execution since the interval started, if any,
+    /// see [`HarnessTimingMetadata::take_interval_from`]
+    pub slowest: Option<Duration>,
+    /// The number of executions that timed out since the interval started
+    pub timeouts: usize,
+}
+
+libafl_bolts::impl_serdeany!(HarnessTimingMetadata);
+
+impl HarnessTimingMetadata {
+    /// Record an execution that took `runtime` and ended with `exit_kind`
+    pub fn record(&mut self, runtime: Duration, exit_kind: ExitKind) {
+        self.last = Some(runtime);
+        self.count += 1;
+        self.total = self.total.saturating_add(runtime);
+        if exit_kind == ExitKind::Timeout {
+            self.timeouts += 1;
+        } else {
+            self.max = self.max.max(Some(runtime));
+            self.slowest = self.slowest.max(Some(runtime));
+        }
+    }
+
+    /// The executions per second spent in the harness, excluding the time the fuzzer spends elsewhere,
+    /// `None` if nothing was recorded yet
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn execs_per_sec(&self) -> Option<f64> {
+        (!self.total.is_zero()).then(|| self.count as f64 / self.total.as_secs_f64())
+    }
+
+    /// The runtimes recorded by the [`HarnessTimingObserver`] called `name`, if it ran yet
+    pub fn of<S>(state: &S, name: &str) -> Option<Self>
+    where
+        S: HasNamedMetadata,
+    {
+        state.named_metadata::<Self>(name).ok().copied()
+    }
+
+    /// Like [`HarnessTimingMetadata::of`], but starts the interval of `slowest` and `timeouts` over in the `state`,
+    /// e.g., for each report interval
+    pub fn take_interval_from<S>(state: &mut S, name: &str) -> Option<Self>
+    where
+        S: HasNamedMetadata,
+    {
+        let timing = state.named_metadata_mut::<Self>(name).ok()?;
+        let taken = *timing;
+        timing.slowest = None;
+        timing.timeouts = 0;
+        Some(taken)
+    }
+}
+
+/// An observer timing each execution of the harness, from its `pre_exec` to its `post_exec`,
+/// and recording the runtimes in the [`HarnessTimingMetadata`] of the state under its name,
+/// e.g., for the [`crate::stages::StatsStage`].
+///
+/// Unlike the [`TimeObserver`], the runtimes are kept in the state, so any stage can read them, independent of
+/// the executor and the observers tuple. The time is taken with [`current_time`], so it is cheap, but only as precise as the clock.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HarnessTimingObserver {
+    name: Cow<'static, str>,
+    // the time of the last `pre_exec`
+    start_time: Duration,
+}
+
+impl HarnessTimingObserver {
+    /// Creates a new [`HarnessTimingObserver`] recording into the named metadata `name`
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::from(name),
+            start_time: Duration::ZERO,
+        }
+    }
+}
+
+impl<I, S> Observer<I, S> for HarnessTimingObserver
+where
+    S: HasNamedMetadata,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.start_time = current_time();
+        Ok(())
+    }
+
+    fn post_exec(&mut self, state: &mut S, _input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        let runtime = current_time()
+            .checked_sub(self.start_time)
+            .unwrap_or_default();
+        state
+            .named_metadata_or_insert_with(&self.name, HarnessTimingMetadata::default)
+            .record(runtime, *exit_kind);
+        Ok(())
+    }
+}
+
+impl Named for HarnessTimingObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
 #[cfg(feature = "std")]
 #[cfg(test)]
 mod tests {
+    use core::time::Duration;
 
     use libafl_bolts::{
         ownedref::OwnedMutSlice,
@@ -408,7 +528,15 @@ mod tests {
         Named,
     };
 
-    use crate::observers::{StdMapObserver, TimeObserver};
+    use crate::{
+        executors::ExitKind,
+        inputs::NopInput,
+        observers::{
+            HarnessTimingMetadata, HarnessTimingObserver, Observer, StdMapObserver, TimeObserver,
+        },
+        state::StdState,
+        HasNamedMetadata,
+    };
 
     static mut MAP: [u32; 4] = [0; 4];
 
@@ -428,4 +556,59 @@ mod tests {
             postcard::from_bytes(&vec).unwrap();
         assert_eq!(obv.0.name(), obv2.0.name());
     }
+
+    #[test]
+    fn test_harness_timing_observer() {
+        let mut state = StdState::nop::<NopInput>().unwrap();
+        let mut observer = HarnessTimingObserver::new("harness");
+        assert_eq!(HarnessTimingMetadata::of(&state, "harness"), None);
+
+        for exit_kind in [ExitKind::Ok, ExitKind::Timeout, ExitKind::Ok] {
+            observer.pre_exec(&mut state, &NopInput {}).unwrap();
+            observer
+                .post_exec(&mut state, &NopInput {}, &exit_kind)
+                .unwrap();
+        }
+        let timing = HarnessTimingMetadata::of(&state, "harness").unwrap();
+        assert_eq!(timing.count, 3);
+        assert_eq!(timing.timeouts, 1);
+        assert!(timing.last.is_some());
+        assert!(timing.max >= timing.last);
+        assert_eq!(timing.slowest, timing.max);
+        assert!(timing.total >= timing.max.unwrap());
+
+        let mut timing = HarnessTimingMetadata::default();
+        assert_eq!(timing.execs_per_sec(), None);
+        timing.record(Duration::from_millis(300), ExitKind::Ok);
+        timing.record(Duration::from_millis(100), ExitKind::Ok);
+        timing.record(Duration::from_millis(100), ExitKind::Timeout);
+        assert_eq!(timing.last, Some(Duration::from_millis(100)));
+        assert_eq!(timing.max, Some(Duration::from_millis(300)));
+        assert_eq!(timing.execs_per_sec(), Some(6.0));
+    }
+
+    /// Only the interval of the slowest execution and the timeouts starts over
+    #[test]
+    fn test_harness_timing_take_interval() {
+        let mut state = StdState::nop::<NopInput>().unwrap();
+        assert_eq!(
+            HarnessTimingMetadata::take_interval_from(&mut state, "harness"),
+            None
+        );
+
+        let timing = state.named_metadata_or_insert_with("harness", HarnessTimingMetadata::default);
+        timing.record(Duration::from_millis(70), ExitKind::Crash);
+        timing.record(Duration::from_secs(10), ExitKind::Timeout);
+        let timing = *timing;
+
+        assert_eq!(
+            HarnessTimingMetadata::take_interval_from(&mut state, "harness"),
+            Some(timing)
+        );
+        let rest = HarnessTimingMetadata::of(&state, "harness").unwrap();
+        assert_eq!(rest.slowest, None);
+        assert_eq!(rest.timeouts, 0);
+        assert_eq!(rest.max, Some(Duration::from_millis(70)));
+        assert_eq!(rest.count, 2);
+    }
 }
@@ -58,6 +58,11 @@ impl Ord for Earlier {
     }
 }
 
+/// The number of randomization attempts for an input of `input_len` bytes: twice its length, at most `max_attempts`
+fn colorization_attempts(input_len: usize, max_attempts: Option<usize>) -> usize {
+    max_attempts.map_or(input_len * 2, |max| max.min(input_len * 2))
+}
+
 /// Default name for `ColorizationStage`; derived from ALF++
 pub const COLORIZATION_STAGE_NAME: &str = "colorization";
 /// The mutational stage using power schedules
@@ -65,6 +70,8 @@ pub const COLORIZATION_STAGE_NAME: &str = "colorization";
 pub struct ColorizationStage<C, E, EM, O, Z> {
     map_observer_handle: Handle<C>,
     name: Cow<'static, str>,
+    /// The maximum amount of randomization attempts per input, if bounded
+    max_attempts: Option<usize>,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, O, E, Z)>,
 }
@@ -107,7 +114,14 @@ where
         manager: &mut EM,
     ) -> Result<(), Error> {
         // Run with the mutated input
-        Self::colorize(fuzzer, executor, state, manager, &self.map_observer_handle)?;
+        Self::colorize(
+            fuzzer,
+            executor,
+            state,
+            manager,
+            &self.map_observer_handle,
+            self.max_attempts,
+        )?;
 
         Ok(())
     }
@@ -183,6 +197,7 @@ where
         state: &mut <Self as UsesState>::State,
         manager: &mut EM,
         observer_handle: &Handle<C>,
+        max_attempts: Option<usize>,
     ) -> Result<E::Input, Error> {
         let mut input = state.current_input_cloned()?;
         // The backup of the input
@@ -215,7 +230,7 @@ where
         // println!("Replaced bytes: {:#?}", changed_bytes);
         // What we do is now to separate the input into smaller regions
         // And in each small regions make sure changing those bytes in the regions does not affect the coverage
-        for _ in 0..input_len * 2 {
+        for _ in 0..colorization_attempts(input_len, max_attempts) {
             if let Some(b) = ranges.pop() {
                 // Let's try the largest one (ranges is sorted)
                 let r = b.0;
@@ -314,10 +329,21 @@ where
         Self {
             map_observer_handle: map_observer.handle(),
             name: Cow::Owned(COLORIZATION_STAGE_NAME.to_owned() + ":" + obs_name.as_str()),
+            max_attempts: None,
             phantom: PhantomData,
         }
     }
 
+    #[must_use]
+    /// Creates a new [`ColorizationStage`] that runs at most `max_attempts` randomization attempts
+    /// (i.e., executions) per input, instead of twice the input length.
+    /// This bounds the time spent on large inputs.
+    pub fn with_max_attempts(map_observer: &C, max_attempts: usize) -> Self {
+        let mut stage = Self::new(map_observer);
+        stage.max_attempts = Some(max_attempts);
+        stage
+    }
+
     // Run the target and get map hash but before hitcounts's post_exec is used
     fn get_raw_map_hash_run(
         fuzzer: &mut Z,
@@ -439,3 +465,17 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::colorization_attempts;
+
+    /// `max_attempts` bounds the attempts on large inputs, but never raises them
+    #[test]
+    fn test_colorization_attempts() {
+        assert_eq!(colorization_attempts(100, None), 200);
+        assert_eq!(colorization_attempts(100, Some(50)), 50);
+        assert_eq!(colorization_attempts(10, Some(50)), 20);
+        assert_eq!(colorization_attempts(0, Some(50)), 0);
+    }
+}
@@ -0,0 +1,330 @@
+//! The [`DiffMutationalStage`] fuzzes a [`crate::executors::DiffExecutor`], for differential fuzzing.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::ToString,
+};
+use core::marker::PhantomData;
+
+use libafl_bolts::Named;
+
+#[cfg(feature = "introspection")]
+use crate::state::HasClientPerfMonitor;
+use crate::{
+    corpus::{Corpus, HasCurrentCorpusId},
+    fuzzer::{Evaluator, ExecuteInputResult},
+    mutators::Mutator,
+    stages::{
+        mutational::MutatedTransform, IterationsPolicy, MutationalStage, RandomIterations,
+        RetryCountRestartHelper, Stage,
+    },
+    state::{HasCorpus, HasRand, UsesState},
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// The unique id for the differential mutational stage
+static mut DIFF_MUTATIONAL_STAGE_ID: usize = 0;
+/// The name for the differential mutational stage
+pub static DIFF_MUTATIONAL_STAGE_NAME: &str = "diff_mutational";
+
+/// A mutational stage for differential fuzzing: it runs each mutated input on a
+/// [`crate::executors::DiffExecutor`], i.e., on a primary and a secondary executor, e.g., two
+/// implementations of the same target.
+///
+/// The mutated inputs are evaluated like in the [`crate::stages::StdMutationalStage`], using
+/// [`MutationalStage::perform_mutational`]. A divergence is detected by the objective of the fuzzer,
+/// e.g., a [`crate::feedbacks::DiffFeedback`] comparing the observers of both executors, or a
+/// [`crate::feedbacks::DiffExitKindFeedback`] for the executions that ended with different
+/// [`crate::executors::ExitKind`]s. Hence, diverging inputs are added to the solutions and reported
+/// to the event manager like any other objective.
+///
+/// The [`crate::executors::DiffExecutor`] reports a crash of only one of the executors as
+/// [`crate::executors::ExitKind::Diff`]. Note that the secondary executor should still handle crashes
+/// on its own, e.g., by running the target in a separate process.
+#[derive(Clone, Debug)]
+pub struct DiffMutationalStage<E, EM, I, M, Z, P = RandomIterations, F = fn(&I) -> bool> {
+    name: Cow<'static, str>,
+    mutator: M,
+    iterations_policy: P,
+    pre_eval: Option<F>,
+    divergences: usize,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, I, Z)>,
+}
+
+impl<E, EM, I, M, Z, P, F> UsesState for DiffMutationalStage<E, EM, I, M, Z, P, F>
+where
+    Z: UsesState,
+{
+    type State = Z::State;
+}
+
+impl<E, EM, I, M, Z, P, F> Named for DiffMutationalStage<E, EM, I, M, Z, P, F> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, I, M, Z, P, F> MutationalStage<E, EM, I, M, Z>
+    for DiffMutationalStage<E, EM, I, M, Z, P, F>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState<State = Self::State>,
+    M: Mutator<I, Self::State>,
+    Z: Evaluator<E, EM>,
+    Z::State: HasCorpus + HasCurrentCorpusId + HasRand + HasMetadata + HasNamedMetadata,
+    I: MutatedTransform<Self::Input, Self::State> + Clone,
+    P: IterationsPolicy<Self::State>,
+    F: FnMut(&I) -> bool,
+    <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
+{
+    #[inline]
+    fn mutator(&self) -> &M {
+        &self.mutator
+    }
+
+    #[inline]
+    fn mutator_mut(&mut self) -> &mut M {
+        &mut self.mutator
+    }
+
+    fn iterations(&mut self, state: &mut Self::State) -> Result<usize, Error> {
+        self.iterations_policy.iterations(state)
+    }
+
+    #[inline]
+    fn pre_eval(&mut self, _state: &mut Self::State, input: &I) -> bool {
+        match &mut self.pre_eval {
+            Some(pre_eval) => pre_eval(input),
+            None => true,
+        }
+    }
+
+    /// Counts the objectives, i.e., the divergences
+    #[inline]
+    fn post_eval(
+        &mut self,
+        _state: &mut Self::State,
+        result: &ExecuteInputResult,
+    ) -> Result<(), Error> {
+        if *result == ExecuteInputResult::Solution {
+            self.divergences += 1;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn completed_iterations(&mut self, state: &mut Self::State) -> Result<usize, Error> {
+        Ok(RetryCountRestartHelper::completed_iterations(
+            state, &self.name,
+        ))
+    }
+
+    #[inline]
+    fn set_completed_iterations(
+        &mut self,
+        state: &mut Self::State,
+        completed_iterations: usize,
+    ) -> Result<(), Error> {
+        RetryCountRestartHelper::set_completed_iterations(state, &self.name, completed_iterations);
+        Ok(())
+    }
+}
+
+impl<E, EM, I, M, Z, P, F> Stage<E, EM, Z> for DiffMutationalStage<E, EM, I, M, Z, P, F>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState<State = Self::State>,
+    M: Mutator<I, Self::State>,
+    Z: Evaluator<E, EM>,
+    Z::State: HasCorpus + HasCurrentCorpusId + HasRand + HasMetadata + HasNamedMetadata,
+    I: MutatedTransform<Self::Input, Self::State> + Clone,
+    P: IterationsPolicy<Self::State>,
+    F: FnMut(&I) -> bool,
+    <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
+{
+    #[inline]
+    #[allow(clippy::let_and_return)]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let ret = self.perform_mutational(fuzzer, executor, state, manager);
+
+        #[cfg(feature = "introspection")]
+        state.introspection_monitor_mut().finish_stage();
+
+        ret
+    }
+
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        // Make sure we don't get stuck crashing on a single testcase
+        RetryCountRestartHelper::should_restart(state, &self.name, 3)
+    }
+
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        RetryCountRestartHelper::clear_progress(state, &self.name)
+    }
+}
+
+impl<E, EM, I, M, Z> DiffMutationalStage<E, EM, I, M, Z>
+where
+    Z: UsesState,
+    Z::State: HasRand,
+{
+    /// Creates a new [`DiffMutationalStage`] with a random number of iterations
+    pub fn new(mutator: M) -> Self {
+        Self::with_iterations_policy(mutator, RandomIterations::default())
+    }
+}
+
+impl<E, EM, I, M, Z, P> DiffMutationalStage<E, EM, I, M, Z, P> {
+    /// Creates a new [`DiffMutationalStage`] with the number of iterations decided by `iterations_policy`
+    pub fn with_iterations_policy(mutator: M, iterations_policy: P) -> Self {
+        // unsafe but impossible that you create two threads both instantiating this instance
+        let stage_id = unsafe {
+            let ret = DIFF_MUTATIONAL_STAGE_ID;
+            DIFF_MUTATIONAL_STAGE_ID += 1;
+            ret
+        };
+        Self {
+            name: Cow::Owned(
+                DIFF_MUTATIONAL_STAGE_NAME.to_owned() + ":" + stage_id.to_string().as_str(),
+            ),
+            mutator,
+            iterations_policy,
+            pre_eval: None,
+            divergences: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E, EM, I, M, Z, P, F> DiffMutationalStage<E, EM, I, M, Z, P, F> {
+    /// Only execute the mutated inputs for which `pre_eval` returns `true`,
+    /// see [`crate::stages::StdMutationalStage::with_pre_eval`].
+    pub fn with_pre_eval<F2>(self, pre_eval: F2) -> DiffMutationalStage<E, EM, I, M, Z, P, F2>
+    where
+        F2: FnMut(&I) -> bool,
+    {
+        DiffMutationalStage {
+            name: self.name,
+            mutator: self.mutator,
+            iterations_policy: self.iterations_policy,
+            pre_eval: Some(pre_eval),
+            divergences: self.divergences,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The number of mutated inputs this stage found to be objectives so far, i.e., the divergences
+    #[must_use]
+    pub fn divergences(&self) -> usize {
+        self.divergences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list, Error, Named};
+
+    use crate::{
+        corpus::{Corpus, HasCurrentCorpusId, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{DiffExecutor, ExitKind, InProcessExecutor},
+        feedbacks::DiffExitKindFeedback,
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::{MutationResult, Mutator},
+        schedulers::RandScheduler,
+        stages::{DiffMutationalStage, ExecBudget, FixedIterations, Stage},
+        state::{HasSolutions, StdState},
+        HasMetadata, StdFuzzer,
+    };
+
+    /// Sets the first byte to `a`, `b`, `c`, ... in subsequent calls
+    struct NextFirstByte(u8);
+
+    impl Named for NextFirstByte {
+        fn name(&self) -> &alloc::borrow::Cow<'static, str> {
+            static NAME: alloc::borrow::Cow<'static, str> =
+                alloc::borrow::Cow::Borrowed("NextFirstByte");
+            &NAME
+        }
+    }
+
+    impl<S> Mutator<BytesInput, S> for NextFirstByte {
+        fn mutate(
+            &mut self,
+            _state: &mut S,
+            input: &mut BytesInput,
+        ) -> Result<MutationResult, Error> {
+            input.bytes_mut()[0] = b'a' + self.0;
+            self.0 += 1;
+            Ok(MutationResult::Mutated)
+        }
+    }
+
+    #[test]
+    fn test_diff_mutational_stage() -> Result<(), Error> {
+        let mut primary_harness = |_input: &BytesInput| ExitKind::Ok;
+        // crashes on `b` and times out on `c`
+        let mut secondary_harness = |input: &BytesInput| match input.bytes()[0] {
+            b'b' => ExitKind::Crash,
+            b'c' => ExitKind::Timeout,
+            _ => ExitKind::Ok,
+        };
+
+        let mut corpus = InMemoryCorpus::new();
+        let corpus_id = corpus.add(Testcase::new(BytesInput::new(b"abcd".to_vec())))?;
+
+        let mut feedback = tuple_list!();
+        let mut objective = DiffExitKindFeedback::new();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )?;
+        let mut fuzzer = StdFuzzer::new(RandScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let primary = InProcessExecutor::new(
+            &mut primary_harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )?;
+        let secondary = InProcessExecutor::new(
+            &mut secondary_harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )?;
+        let mut executor = DiffExecutor::new(primary, secondary, ());
+        state.set_corpus_id(corpus_id)?;
+
+        let mut stage =
+            DiffMutationalStage::with_iterations_policy(NextFirstByte(0), FixedIterations::new(4));
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+
+        // the diverging inputs are solutions
+        assert_eq!(stage.divergences(), 2);
+        assert_eq!(state.solutions().count(), 2);
+        let solution = state.solutions().first().unwrap();
+        let solution = state.solutions().cloned_input_for_id(solution)?;
+        assert_eq!(solution.bytes(), b"bbcd");
+
+        // the stage takes its executions from the budget
+        state.add_metadata(ExecBudget::new(1));
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert!(state.metadata::<ExecBudget>()?.is_exhausted());
+        assert_eq!(state.solutions().count(), 2);
+
+        Ok(())
+    }
+}
@@ -1,10 +1,11 @@
-//! The [`DumpToDiskStage`] is a stage that dumps the corpus and the solutions to disk to e.g. allow AFL to sync
+//! The [`DumpToDiskStage`] is a stage that dumps the corpus and the solutions to disk to e.g. allow AFL to sync,
+//! the [`CorpusSnapshotStage`] periodically snapshots the whole corpus to disk.
 
 use alloc::{string::String, vec::Vec};
-use core::{clone::Clone, marker::PhantomData};
+use core::{clone::Clone, marker::PhantomData, time::Duration};
 use std::{fs, fs::File, io::Write, path::PathBuf};
 
-use libafl_bolts::impl_serdeany;
+use libafl_bolts::{current_time, fs::write_file_atomic, impl_serdeany};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -179,3 +180,196 @@ where
         Ok(())
     }
 }
+
+/// The [`CorpusSnapshotStage`] writes the inputs of the whole corpus to a directory every `interval`,
+/// e.g., to recover from a crash of the fuzzer or to analyze the corpus offline.
+///
+/// Unlike the [`DumpToDiskStage`], it writes all testcases on each snapshot, not only the new ones, and is
+/// independent of the corpus backend and the scheduler.
+/// Each file is written atomically, so an interrupted snapshot does not leave partial inputs behind.
+#[derive(Debug)]
+pub struct CorpusSnapshotStage<CB, EM, Z> {
+    snapshot_dir: PathBuf,
+    to_bytes: CB,
+    last_snapshot_time: Duration,
+    snapshot_interval: Duration,
+    phantom: PhantomData<(EM, Z)>,
+}
+
+impl<CB, EM, Z> UsesState for CorpusSnapshotStage<CB, EM, Z>
+where
+    EM: UsesState,
+{
+    type State = EM::State;
+}
+
+impl<CB, E, EM, Z> Stage<E, EM, Z> for CorpusSnapshotStage<CB, EM, Z>
+where
+    CB: FnMut(&Self::Input, &Self::State) -> Vec<u8>,
+    EM: UsesState,
+    E: UsesState<State = Self::State>,
+    Z: UsesState<State = Self::State>,
+    EM::State: HasCorpus,
+    <<EM as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut Self::State,
+        _manager: &mut EM,
+    ) -> Result<(), Error> {
+        let cur = current_time();
+        if cur.checked_sub(self.last_snapshot_time).unwrap_or_default() >= self.snapshot_interval {
+            self.snapshot(state)?;
+            self.last_snapshot_time = cur;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn should_restart(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        // Not executing the target, so restart safety is not needed
+        Ok(())
+    }
+}
+
+impl<CB, EM, Z> CorpusSnapshotStage<CB, EM, Z>
+where
+    EM: UsesState,
+    <EM as UsesState>::State: HasCorpus,
+    <<EM as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = EM::Input>,
+{
+    /// Create a new [`CorpusSnapshotStage`], snapshotting the corpus to `snapshot_dir` every `interval`
+    pub fn new<A>(to_bytes: CB, snapshot_dir: A, interval: Duration) -> Result<Self, Error>
+    where
+        A: Into<PathBuf>,
+    {
+        let snapshot_dir = snapshot_dir.into();
+        if let Err(e) = fs::create_dir_all(&snapshot_dir) {
+            if !snapshot_dir.is_dir() {
+                return Err(Error::os_error(
+                    e,
+                    format!("Error creating directory {}", snapshot_dir.display()),
+                ));
+            }
+        }
+        Ok(Self {
+            snapshot_dir,
+            to_bytes,
+            last_snapshot_time: current_time(),
+            snapshot_interval: interval,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The directory the snapshots are written to
+    #[must_use]
+    pub fn snapshot_dir(&self) -> &PathBuf {
+        &self.snapshot_dir
+    }
+
+    /// Write the inputs of all testcases in the corpus to the snapshot directory now, as `id_<id>`.
+    ///
+    /// Testcases whose input fails to load are skipped, instead of aborting the snapshot.
+    /// Returns the number of inputs written.
+    pub fn snapshot(&mut self, state: &<Self as UsesState>::State) -> Result<usize, Error>
+    where
+        CB: FnMut(
+            &<<<EM as UsesState>::State as HasCorpus>::Corpus as Corpus>::Input,
+            &<EM as UsesState>::State,
+        ) -> Vec<u8>,
+    {
+        let mut written = 0;
+        for id in state.corpus().ids() {
+            let mut testcase = state.corpus().get(id)?.borrow_mut();
+            if let Err(e) = state.corpus().load_input_into(&mut testcase) {
+                log::warn!("Skipping testcase {id} in the corpus snapshot: {e}");
+                continue;
+            }
+            let Some(input) = testcase.input().as_ref() else {
+                log::warn!("Skipping testcase {id} in the corpus snapshot: no input");
+                continue;
+            };
+            let bytes = (self.to_bytes)(input, state);
+            write_file_atomic(self.snapshot_dir.join(format!("id_{id}")), &bytes)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+    use std::{env, fs};
+
+    use libafl_bolts::Error;
+
+    use super::CorpusSnapshotStage;
+    use crate::{
+        corpus::{Corpus, Testcase},
+        events::NopEventManager,
+        fuzzer::NopFuzzer,
+        inputs::{BytesInput, HasTargetBytes},
+        stages::Stage,
+        state::{HasCorpus, State, StdState, UsesState},
+    };
+
+    /// The snapshot stage never runs the executor, it only needs its state type
+    struct NopExecutor<S>(PhantomData<S>);
+
+    impl<S> NopExecutor<S> {
+        fn new() -> Self {
+            Self(PhantomData)
+        }
+    }
+
+    impl<S> UsesState for NopExecutor<S>
+    where
+        S: State,
+    {
+        type State = S;
+    }
+
+    /// The snapshot contains all loadable inputs of the corpus
+    #[test]
+    fn test_corpus_snapshot_stage() -> Result<(), Error> {
+        let mut state = StdState::nop::<BytesInput>()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = NopEventManager::new();
+
+        let a = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"aaaa".to_vec())))?;
+        // A testcase without an input is skipped
+        state.corpus_mut().add(Testcase::default())?;
+        let b = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"bb".to_vec())))?;
+
+        let dir = env::temp_dir().join(format!("libafl_corpus_snapshot_{}", std::process::id()));
+        let mut stage = CorpusSnapshotStage::new(
+            |input: &BytesInput, _state: &_| input.target_bytes().to_vec(),
+            &dir,
+            core::time::Duration::ZERO,
+        )?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+
+        assert_eq!(fs::read_dir(&dir)?.count(), 2);
+        assert_eq!(fs::read(dir.join(format!("id_{a}")))?, b"aaaa");
+        assert_eq!(fs::read(dir.join(format!("id_{b}")))?, b"bb");
+        assert_eq!(stage.snapshot(&state)?, 2);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,575 @@
+//! The input-to-state (I2S) mutational stage, solving comparisons by replacing logged operands.
+//!
+//! While the [`crate::mutators::I2SRandReplace`] mutator picks a random comparison and a random
+//! offset, this stage walks through all the comparisons in the [`CmpValuesMetadata`]
+//! and tries every place in the input where an operand appears.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::ToString,
+    vec::Vec,
+};
+use core::{marker::PhantomData, num::NonZeroUsize};
+
+use hashbrown::HashMap;
+use libafl_bolts::{AsSlice, Named};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    fuzzer::Evaluator,
+    inputs::HasMutatorBytes,
+    observers::cmp::{CmpOp, CmpValues, CmpValuesMetadata},
+    stages::{RetryCountRestartHelper, Stage},
+    state::{HasCorpus, HasCurrentTestcase, UsesState},
+    Error, HasMetadata, HasNamedMetadata,
+};
+
+/// Counts how often replacing the operands of a logged comparison led to a new corpus entry,
+/// so that, e.g., a scheduler can prioritize similar comparisons.
+///
+/// The comparisons are identified by their index in the [`crate::observers::CmpMap`], as recorded in the
+/// [`CmpValuesMetadata::positions`], which stays the same across executions, unlike the index in the
+/// [`CmpValuesMetadata::list`]. Comparisons without a recorded position are not counted.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct SolvedCmpMetadata {
+    solved: HashMap<usize, usize>,
+}
+
+libafl_bolts::impl_serdeany!(SolvedCmpMetadata);
+
+impl SolvedCmpMetadata {
+    /// Creates a new, empty [`struct@SolvedCmpMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that solving the comparison at `map_idx` in the cmp map led to a new corpus entry
+    pub fn record(&mut self, map_idx: usize) {
+        *self.solved.entry(map_idx).or_default() += 1;
+    }
+
+    /// The number of new corpus entries found by solving the comparison at `map_idx` in the cmp map
+    #[must_use]
+    pub fn successes(&self, map_idx: usize) -> usize {
+        self.solved.get(&map_idx).copied().unwrap_or(0)
+    }
+
+    /// The success counts of all comparisons that led to a new corpus entry, by their index in the cmp map
+    #[must_use]
+    pub fn solved(&self) -> &HashMap<usize, usize> {
+        &self.solved
+    }
+}
+
+/// The round-robin cursor of an [`I2SMutationalStage`] with a [`I2SMutationalStage::max_comparisons_per_visit`] cap,
+/// in the metadata of the testcase: the index of the comparison in the [`CmpValuesMetadata`] to start the next visit at.
+///
+/// The cursor belongs to the testcase, so a testcase that is (re-)added to the corpus, e.g., after minimization,
+/// starts at the first comparison again.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct I2SCursorMetadata {
+    next: usize,
+}
+
+libafl_bolts::impl_serdeany!(I2SCursorMetadata);
+
+impl I2SCursorMetadata {
+    /// The index of the comparison the next visit starts at
+    #[must_use]
+    pub fn next(&self) -> usize {
+        self.next
+    }
+
+    /// Returns the indices of the comparisons to attempt in this visit, at most `max` of `count`,
+    /// and advances the cursor past them, wrapping around at `count`
+    pub fn advance(&mut self, count: usize, max: usize) -> impl Iterator<Item = usize> {
+        let start = if count == 0 { 0 } else { self.next % count };
+        let taken = max.min(count);
+        if count > 0 {
+            self.next = (start + taken) % count;
+        }
+        (start..start + taken).map(move |idx| idx % count)
+    }
+}
+
+/// The unique id for the I2S mutational stage
+static mut I2S_MUTATIONAL_STAGE_ID: usize = 0;
+/// The name for the I2S mutational stage
+pub static I2S_MUTATIONAL_STAGE_NAME: &str = "i2s";
+
+/// A stage that uses the comparisons logged in the [`CmpValuesMetadata`] (e.g., by a
+/// `CmpObserver` in a tracing stage) to replace each occurrence of one comparison operand
+/// in the current input with the other operand.
+///
+/// Numeric operands are searched for in both little and big endian encoding,
+/// [`CmpValues::Bytes`] and [`CmpValues::Vector`]s are searched for as-is (e.g., for `memcmp`-style comparisons).
+/// Each replacement is evaluated as a new input. If it is added to the corpus, this is recorded
+/// for its comparison in the [`struct@SolvedCmpMetadata`]. For this, the stage turns on
+/// [`CmpValuesMetadata::set_record_positions`], so the comparisons are counted from the next trace on.
+///
+/// If created [`I2SMutationalStage::with_transforms`], all comparisons are treated as transform comparisons,
+/// and each candidate of [`CmpValues::normalize`] is tried.
+///
+/// For testcases with many logged comparisons, the comparisons attempted per visit can be capped with
+/// [`I2SMutationalStage::max_comparisons_per_visit`].
+#[derive(Clone, Debug)]
+pub struct I2SMutationalStage<E, EM, Z> {
+    name: Cow<'static, str>,
+    transforms: bool,
+    max_comparisons_per_visit: Option<NonZeroUsize>,
+    phantom: PhantomData<(E, EM, Z)>,
+}
+
+impl<E, EM, Z> UsesState for I2SMutationalStage<E, EM, Z>
+where
+    Z: UsesState,
+{
+    type State = Z::State;
+}
+
+impl<E, EM, Z> Named for I2SMutationalStage<E, EM, Z> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, Z> Stage<E, EM, Z> for I2SMutationalStage<E, EM, Z>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState<State = Self::State>,
+    Z: Evaluator<E, EM>,
+    Z::State: HasCorpus + HasCurrentTestcase + HasMetadata + HasNamedMetadata,
+    Z::Input: HasMutatorBytes + Clone,
+    <Self::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        // The metadata gets overwritten by each execution, so we work on a copy.
+        let (cmp_values, positions) = match state.metadata_map_mut().get_mut::<CmpValuesMetadata>()
+        {
+            Some(meta) if !meta.list.is_empty() => {
+                // the positions identify the comparisons in the `SolvedCmpMetadata` across executions
+                meta.set_record_positions(true);
+                (meta.list.clone(), meta.positions.clone())
+            }
+            _ => return Ok(()),
+        };
+        let input = state.current_input_cloned()?;
+
+        // With a cap, continue where the last visit of this testcase stopped.
+        // The cursor is advanced before executing, so a crashing comparison is not retried forever.
+        let cmp_indices: Vec<usize> = match self.max_comparisons_per_visit {
+            Some(max) => state
+                .current_testcase_mut()?
+                .metadata_or_insert_with(I2SCursorMetadata::default)
+                .advance(cmp_values.len(), max.get())
+                .collect(),
+            None => (0..cmp_values.len()).collect(),
+        };
+
+        // Each replacement is tagged with the index of the comparison it solves
+        let mut replacements = Vec::new();
+        let mut found = Vec::new();
+        for cmp_idx in cmp_indices {
+            let cmp = &cmp_values[cmp_idx];
+            if self.transforms {
+                for candidate in cmp.normalize(CmpOp::TRANSFORM) {
+                    i2s_replacements(input.bytes(), &candidate, &mut found);
+                }
+            } else {
+                i2s_replacements(input.bytes(), cmp, &mut found);
+            }
+            replacements.extend(
+                found
+                    .drain(..)
+                    .map(|(offset, replacement)| (offset, replacement, cmp_idx)),
+            );
+        }
+        replacements.sort_unstable();
+        replacements.dedup_by(|a, b| a.0 == b.0 && a.1 == b.1);
+
+        for (offset, replacement, cmp_idx) in replacements {
+            let mut new_input = input.clone();
+            new_input.bytes_mut()[offset..offset + replacement.len()].copy_from_slice(&replacement);
+            let (_, corpus_id) = fuzzer.evaluate_input(state, executor, manager, new_input)?;
+            if corpus_id.is_some() {
+                if let Some(&(map_idx, _)) = positions.get(cmp_idx) {
+                    state
+                        .metadata_or_insert_with(SolvedCmpMetadata::new)
+                        .record(map_idx);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        // Make sure we don't get stuck crashing on a single testcase
+        RetryCountRestartHelper::should_restart(state, &self.name, 3)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        RetryCountRestartHelper::clear_progress(state, &self.name)
+    }
+}
+
+impl<E, EM, Z> I2SMutationalStage<E, EM, Z> {
+    /// Creates a new [`I2SMutationalStage`]
+    #[must_use]
+    pub fn new() -> Self {
+        // unsafe but impossible that you create two threads both instantiating this instance
+        let stage_id = unsafe {
+            let ret = I2S_MUTATIONAL_STAGE_ID;
+            I2S_MUTATIONAL_STAGE_ID += 1;
+            ret
+        };
+        Self {
+            name: Cow::Owned(
+                I2S_MUTATIONAL_STAGE_NAME.to_owned() + ":" + stage_id.to_string().as_str(),
+            ),
+            transforms: false,
+            max_comparisons_per_visit: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new [`I2SMutationalStage`] that also tries the byteswapped and sign-extended
+    /// candidates of each comparison (see [`CmpValues::normalize`]), e.g., for targets parsing big endian integers.
+    /// This costs more executions per testcase.
+    #[must_use]
+    pub fn with_transforms() -> Self {
+        Self {
+            transforms: true,
+            ..Self::new()
+        }
+    }
+
+    /// Attempts at most `max` comparisons per visit of a testcase, instead of all of them.
+    ///
+    /// Successive visits of the same testcase continue with the following comparisons, round-robin,
+    /// so the solving effort is spread over the whole comparison set across scheduling rounds.
+    /// The cursor is kept in the [`I2SCursorMetadata`] of the testcase, and wraps around at the number of comparisons
+    /// logged for the current visit.
+    #[must_use]
+    pub fn max_comparisons_per_visit(mut self, max: NonZeroUsize) -> Self {
+        self.max_comparisons_per_visit = Some(max);
+        self
+    }
+}
+
+impl<E, EM, Z> Default for I2SMutationalStage<E, EM, Z> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Collects `(offset, replacement)` pairs for every place in `bytes` where an operand of `cmp`
+/// appears, replacing it with the other operand.
+fn i2s_replacements(bytes: &[u8], cmp: &CmpValues, replacements: &mut Vec<(usize, Vec<u8>)>) {
+    if let CmpValues::Bytes((v0, v1)) = cmp {
+        let (v0, v1) = (v0.as_slice(), v1.as_slice());
+        push_replacements(bytes, v0, v1, replacements);
+        push_replacements(bytes, v1, v0, replacements);
+        return;
+    }
+    if let CmpValues::Vector { v0, v1, .. } = cmp {
+        push_replacements(bytes, v0, v1, replacements);
+        push_replacements(bytes, v1, v0, replacements);
+        return;
+    }
+
+    let width = match cmp {
+        CmpValues::U8(_) => 1,
+        CmpValues::U16(_) => 2,
+        CmpValues::U32(_) => 4,
+        _ => 8,
+    };
+    let Some((v0, v1, v0_is_const)) = cmp.to_u64_tuple() else {
+        return;
+    };
+
+    let (v0_le, v1_le) = (v0.to_le_bytes(), v1.to_le_bytes());
+    let (v0_be, v1_be) = (v0.to_be_bytes(), v1.to_be_bytes());
+    let (v0_le, v1_le) = (&v0_le[..width], &v1_le[..width]);
+    let (v0_be, v1_be) = (&v0_be[8 - width..], &v1_be[8 - width..]);
+
+    // A constant operand will never appear in the input
+    if !v0_is_const {
+        push_replacements(bytes, v0_le, v1_le, replacements);
+        if width > 1 {
+            push_replacements(bytes, v0_be, v1_be, replacements);
+        }
+    }
+    push_replacements(bytes, v1_le, v0_le, replacements);
+    if width > 1 {
+        push_replacements(bytes, v1_be, v0_be, replacements);
+    }
+}
+
+fn push_replacements(
+    bytes: &[u8],
+    pattern: &[u8],
+    replacement: &[u8],
+    replacements: &mut Vec<(usize, Vec<u8>)>,
+) {
+    if pattern.is_empty() || pattern == replacement || pattern.len() > bytes.len() {
+        return;
+    }
+    for (offset, window) in bytes.windows(pattern.len()).enumerate() {
+        if window == pattern {
+            let len = replacement.len().min(bytes.len() - offset);
+            replacements.push((offset, replacement[..len].to_vec()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list};
+
+    use crate::{
+        corpus::{Corpus, CorpusId, HasCurrentCorpusId, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        feedbacks::{CrashFeedback, Feedback, StateInitializer},
+        inputs::{BytesInput, HasMutatorBytes},
+        nonzero,
+        observers::cmp::{CmpValues, CmpValuesMetadata},
+        schedulers::RandScheduler,
+        stages::{I2SCursorMetadata, I2SMutationalStage, SolvedCmpMetadata, Stage},
+        state::{HasCorpus, HasSolutions, StdState},
+        HasMetadata, StdFuzzer,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// Sets up a fuzzer with the `feedback` and the `objective`, and an executor running the `harness`,
+    /// for a state whose only testcase, `ABCDabcdEFGH`, is the current one
+    #[allow(clippy::type_complexity)]
+    fn fuzz_setup<H, F, OF>(
+        harness: &mut H,
+        mut feedback: F,
+        mut objective: OF,
+    ) -> (
+        StdFuzzer<RandScheduler<TestState>, F, OF, TestState>,
+        InProcessExecutor<'_, H, (), TestState>,
+        TestState,
+        NopEventManager<TestState>,
+    )
+    where
+        H: FnMut(&BytesInput) -> ExitKind,
+        F: StateInitializer<TestState>,
+        OF: StateInitializer<TestState>
+            + Feedback<NopEventManager<TestState>, BytesInput, (), TestState>,
+    {
+        let mut corpus = InMemoryCorpus::new();
+        let corpus_id = corpus
+            .add(Testcase::new(BytesInput::new(b"ABCDabcdEFGH".to_vec())))
+            .unwrap();
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut fuzzer = StdFuzzer::new(RandScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let executor =
+            InProcessExecutor::new(harness, tuple_list!(), &mut fuzzer, &mut state, &mut mgr)
+                .unwrap();
+        state.set_corpus_id(corpus_id).unwrap();
+
+        (fuzzer, executor, state, mgr)
+    }
+
+    /// Crashes if the second four bytes of the input are `0xdeadbeef`, in little endian
+    fn magic_value_harness(input: &BytesInput) -> ExitKind {
+        let bytes = input.bytes();
+        let x = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        if x == 0xdeadbeef {
+            ExitKind::Crash
+        } else {
+            ExitKind::Ok
+        }
+    }
+
+    #[test]
+    fn test_i2s_magic_value() {
+        let mut harness = magic_value_harness;
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) =
+            fuzz_setup(&mut harness, tuple_list!(), CrashFeedback::new());
+
+        // What a cmplog observer would have recorded for `if (x == 0xdeadbeef)`
+        let mut meta = CmpValuesMetadata::new();
+        meta.list.push(CmpValues::U32((
+            u32::from_le_bytes(*b"abcd"),
+            0xdeadbeef,
+            false,
+        )));
+        state.add_metadata(meta);
+
+        let mut stage = I2SMutationalStage::new();
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+
+        assert_eq!(state.solutions().count(), 1);
+        let solution = state.solutions().first().unwrap();
+        let solution = state.solutions().get(solution).unwrap().borrow();
+        assert_eq!(
+            solution.input().as_ref().unwrap().bytes(),
+            b"ABCD\xef\xbe\xad\xdeEFGH"
+        );
+    }
+
+    #[test]
+    fn test_i2s_transforms() {
+        // parses a big endian `i16`, sign-extended before the comparison
+        let mut harness = |input: &BytesInput| {
+            let bytes = input.bytes();
+            let x = i32::from(i16::from_be_bytes(bytes[4..6].try_into().unwrap()));
+            if x == -2 {
+                ExitKind::Crash
+            } else {
+                ExitKind::Ok
+            }
+        };
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) =
+            fuzz_setup(&mut harness, tuple_list!(), CrashFeedback::new());
+
+        let mut meta = CmpValuesMetadata::new();
+        meta.list.push(CmpValues::U32((0x6162, 0xffff_fffe, false)));
+        state.add_metadata(meta);
+
+        // the logged operands don't appear in the input
+        let mut stage = I2SMutationalStage::new();
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        assert_eq!(state.solutions().count(), 0);
+
+        let mut stage = I2SMutationalStage::with_transforms();
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        assert_eq!(state.solutions().count(), 1);
+    }
+
+    #[test]
+    fn test_i2s_solved_cmps() {
+        let mut harness = magic_value_harness;
+
+        // the crash is a new corpus entry, not a solution
+        let (mut fuzzer, mut executor, mut state, mut mgr) =
+            fuzz_setup(&mut harness, CrashFeedback::new(), tuple_list!());
+
+        let other = CmpValues::U32((u32::from_le_bytes(*b"ABCD"), 1, false));
+        let magic = CmpValues::U32((u32::from_le_bytes(*b"abcd"), 0xdeadbeef, false));
+        let mut stage = I2SMutationalStage::new();
+
+        // two traces log the same comparisons of the cmp map in a different order
+        for (list, positions) in [
+            (vec![other.clone(), magic.clone()], vec![(3, 0), (7, 0)]),
+            (vec![magic, other], vec![(7, 0), (3, 0)]),
+        ] {
+            let mut meta = CmpValuesMetadata::new();
+            meta.list = list;
+            meta.positions = positions;
+            state.add_metadata(meta);
+            stage
+                .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+                .unwrap();
+            assert!(state
+                .metadata::<CmpValuesMetadata>()
+                .unwrap()
+                .record_positions());
+        }
+
+        let solved = state.metadata::<SolvedCmpMetadata>().unwrap();
+        assert_eq!(solved.successes(3), 0);
+        assert_eq!(solved.successes(7), 2);
+        assert_eq!(solved.solved().len(), 1);
+    }
+
+    /// The round-robin cursor of the testcase
+    fn cursor_of<S>(state: &S, corpus_id: CorpusId) -> usize
+    where
+        S: HasCorpus,
+    {
+        state
+            .corpus()
+            .get(corpus_id)
+            .unwrap()
+            .borrow()
+            .metadata::<I2SCursorMetadata>()
+            .unwrap()
+            .next()
+    }
+
+    #[test]
+    fn test_i2s_max_comparisons_per_visit() {
+        let mut harness = magic_value_harness;
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) =
+            fuzz_setup(&mut harness, tuple_list!(), CrashFeedback::new());
+
+        // only the last comparison leads to the crash
+        let mut meta = CmpValuesMetadata::new();
+        meta.list
+            .push(CmpValues::U32((u32::from_le_bytes(*b"ABCD"), 1, false)));
+        meta.list
+            .push(CmpValues::U32((u32::from_le_bytes(*b"EFGH"), 2, false)));
+        meta.list.push(CmpValues::U32((
+            u32::from_le_bytes(*b"abcd"),
+            0xdeadbeef,
+            false,
+        )));
+        state.add_metadata(meta);
+
+        let corpus_id = state.current_corpus_id().unwrap().unwrap();
+        let mut stage = I2SMutationalStage::new().max_comparisons_per_visit(nonzero!(2));
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        assert_eq!(state.solutions().count(), 0);
+        assert_eq!(cursor_of(&state, corpus_id), 2);
+
+        // the second visit continues with the last comparison, and wraps around
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)
+            .unwrap();
+        assert_eq!(state.solutions().count(), 1);
+        assert_eq!(cursor_of(&state, corpus_id), 1);
+
+        let mut cursor = I2SCursorMetadata::default();
+        assert_eq!(cursor.advance(0, 2).count(), 0);
+        assert_eq!(cursor.advance(3, 5).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(cursor.next(), 0);
+    }
+}
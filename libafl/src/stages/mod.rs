@@ -20,26 +20,37 @@ pub use colorization::*;
 pub use concolic::ConcolicTracingStage;
 #[cfg(all(feature = "std", feature = "concolic_mutation", unix))]
 pub use concolic::SimpleConcolicMutationalStage;
+pub use differential::DiffMutationalStage;
 #[cfg(feature = "std")]
 pub use dump::*;
 pub use generalization::GeneralizationStage;
 use hashbrown::HashSet;
+pub use i2s::{I2SCursorMetadata, I2SMutationalStage, SolvedCmpMetadata};
 use libafl_bolts::{
     impl_serdeany,
     tuples::{HasConstLen, IntoVec},
     Named,
 };
 pub use logics::*;
-pub use mutational::{MutationalStage, StdMutationalStage};
+pub use mutational::{
+    AdaptiveIterations, AdaptiveIterationsMetadata, ExecBudget, FixedIterations, IterationsPolicy,
+    MutationalStage, RandomIterations, RoundRobinMutationalStage, StdMutationalStage,
+};
 pub use power::{PowerMutationalStage, StdPowerMutationalStage};
+pub use repro_verify::{ReproVerifyMetadata, ReproVerifyStage};
 use serde::{Deserialize, Serialize};
-pub use stats::StatsStage;
+#[cfg(feature = "introspection")]
+pub use stats::IntrospectionStatsStage;
+#[cfg(feature = "std")]
+pub use stats::StatsAggregation;
+pub use stats::{AflStatsSnapshot, HarnessTimingStatsStage, InputSizeStatsStage, StatsStage};
 #[cfg(feature = "std")]
 pub use sync::*;
 #[cfg(feature = "std")]
 pub use time_tracker::TimeTrackingStageWrapper;
 pub use tmin::{
-    MapEqualityFactory, MapEqualityFeedback, StdTMinMutationalStage, TMinMutationalStage,
+    MapEqualityFactory, MapEqualityFeedback, MinimizationStage, StdTMinMutationalStage,
+    TMinMutationalStage,
 };
 pub use tracing::{ShadowTracingStage, TracingStage};
 pub use tuneable::*;
@@ -73,12 +84,15 @@ pub mod calibrate;
 pub mod colorization;
 #[cfg(all(feature = "std", unix))]
 pub mod concolic;
+pub mod differential;
 #[cfg(feature = "std")]
 pub mod dump;
 pub mod generalization;
 pub mod generation;
+pub mod i2s;
 pub mod logics;
 pub mod power;
+pub mod repro_verify;
 pub mod stats;
 #[cfg(feature = "std")]
 pub mod sync;
@@ -528,6 +542,8 @@ where
 pub struct RetryCountRestartHelper {
     tries_remaining: Option<usize>,
     skipped: HashSet<CorpusId>,
+    #[serde(default)]
+    completed_iterations: usize,
 }
 
 impl_serdeany!(RetryCountRestartHelper);
@@ -558,7 +574,12 @@ impl RetryCountRestartHelper {
         let metadata = state.named_metadata_or_insert_with(name, || Self {
             tries_remaining: Some(initial_tries_remaining),
             skipped: HashSet::new(),
+            completed_iterations: 0,
         });
+        if metadata.tries_remaining.is_none() {
+            // a new round, not a restart
+            metadata.completed_iterations = 0;
+        }
         let tries_remaining = metadata
             .tries_remaining
             .unwrap_or(initial_tries_remaining)
@@ -582,14 +603,111 @@ impl RetryCountRestartHelper {
         })
     }
 
+    /// Like [`Self::should_restart`], but when giving up on the current testcase, i.e., when it
+    /// restarted the stage `max_retries` times, counts it in the campaign-wide [`AbandonedTestcaseCount`]
+    /// and logs its corpus id. Later rounds skipping the same testcase don't count it again.
+    pub fn should_restart_or_abandon<S>(
+        state: &mut S,
+        name: &str,
+        max_retries: usize,
+    ) -> Result<bool, Error>
+    where
+        S: HasNamedMetadata + HasCurrentCorpusId + HasMetadata,
+    {
+        let should_restart = Self::should_restart(state, name, max_retries)?;
+        let abandoned = !should_restart
+            && state
+                .named_metadata::<Self>(name)
+                .is_ok_and(|metadata| metadata.tries_remaining == Some(0));
+        if abandoned {
+            if let Some(corpus_id) = state.current_corpus_id()? {
+                log::warn!(
+                    "Abandoning testcase {corpus_id} in stage {name}, it restarted the stage {max_retries} times"
+                );
+                let count = state.metadata_or_insert_with(AbandonedTestcaseCount::default);
+                count.count += 1;
+                count.last = Some(corpus_id);
+            }
+        }
+        Ok(should_restart)
+    }
+
     /// Clears the progress
     pub fn clear_progress<S>(state: &mut S, name: &str) -> Result<(), Error>
     where
         S: HasNamedMetadata,
     {
-        state.named_metadata_mut::<Self>(name)?.tries_remaining = None;
+        let metadata = state.named_metadata_mut::<Self>(name)?;
+        metadata.tries_remaining = None;
+        metadata.completed_iterations = 0;
         Ok(())
     }
+
+    /// The amount of iterations a stage completed for the current testcase before it got restarted
+    pub fn completed_iterations<S>(state: &S, name: &str) -> usize
+    where
+        S: HasNamedMetadata,
+    {
+        state
+            .named_metadata::<Self>(name)
+            .map_or(0, |metadata| metadata.completed_iterations)
+    }
+
+    /// Records the amount of iterations a stage completed for the current testcase so far,
+    /// so that it can resume from there if it gets restarted
+    pub fn set_completed_iterations<S>(state: &mut S, name: &str, completed_iterations: usize)
+    where
+        S: HasNamedMetadata,
+    {
+        state
+            .named_metadata_or_insert_with(name, || Self {
+                tries_remaining: None,
+                skipped: HashSet::new(),
+                completed_iterations: 0,
+            })
+            .completed_iterations = completed_iterations;
+    }
+}
+
+/// The number of testcases the stages gave up on, because they restarted the stage too often, e.g.,
+/// as the harness crashed while fuzzing them, see [`RetryCountRestartHelper::should_restart_or_abandon`].
+///
+/// Many abandoned testcases hint at a harness that crashes on its own corpus entries.
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AbandonedTestcaseCount {
+    count: usize,
+    last: Option<CorpusId>,
+}
+
+impl_serdeany!(AbandonedTestcaseCount);
+
+impl AbandonedTestcaseCount {
+    /// The number of abandoned testcases
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The corpus id of the testcase abandoned last, if any
+    #[must_use]
+    pub fn last(&self) -> Option<CorpusId> {
+        self.last
+    }
+
+    /// The number of abandoned testcases recorded in the `state`, `0` if none
+    #[must_use]
+    pub fn count_in<S>(state: &S) -> usize
+    where
+        S: HasMetadata,
+    {
+        state
+            .metadata::<Self>()
+            .map_or(0, AbandonedTestcaseCount::count)
+    }
 }
 
 /// The index of a stage
@@ -721,7 +839,7 @@ mod test {
     use crate::{
         corpus::{Corpus, HasCurrentCorpusId, Testcase},
         inputs::NopInput,
-        stages::{RetryCountRestartHelper, Stage},
+        stages::{AbandonedTestcaseCount, RetryCountRestartHelper, Stage},
         state::{HasCorpus, State, StdState, UsesState},
         HasMetadata,
     };
@@ -888,4 +1006,40 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_abandoned_testcase_count() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let corpus_id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(corpus_id)?;
+        assert_eq!(AbandonedTestcaseCount::count_in(&state), 0);
+
+        // a finished stage is not abandoned
+        assert!(RetryCountRestartHelper::should_restart_or_abandon(
+            &mut state, "a", 1
+        )?);
+        RetryCountRestartHelper::clear_progress(&mut state, "a")?;
+        assert_eq!(AbandonedTestcaseCount::count_in(&state), 0);
+
+        // restarted once, then given up
+        assert!(RetryCountRestartHelper::should_restart_or_abandon(
+            &mut state, "a", 1
+        )?);
+        assert!(!RetryCountRestartHelper::should_restart_or_abandon(
+            &mut state, "a", 1
+        )?);
+        RetryCountRestartHelper::clear_progress(&mut state, "a")?;
+        assert_eq!(AbandonedTestcaseCount::count_in(&state), 1);
+
+        // skipping it in the next round does not count it again
+        assert!(!RetryCountRestartHelper::should_restart_or_abandon(
+            &mut state, "a", 1
+        )?);
+        RetryCountRestartHelper::clear_progress(&mut state, "a")?;
+        let count = state.metadata::<AbandonedTestcaseCount>()?;
+        assert_eq!(count.count(), 1);
+        assert_eq!(count.last(), Some(corpus_id));
+
+        Ok(())
+    }
 }
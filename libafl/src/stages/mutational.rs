@@ -3,22 +3,35 @@
 
 use alloc::{
     borrow::{Cow, ToOwned},
+    format,
     string::ToString,
+    vec::Vec,
 };
-use core::{marker::PhantomData, num::NonZeroUsize};
+use core::{marker::PhantomData, num::NonZeroUsize, time::Duration};
 
-use libafl_bolts::{rands::Rand, Named};
+use libafl_bolts::{current_time, rands::Rand, HasLen, Named};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    corpus::{Corpus, CorpusId, Testcase},
-    fuzzer::Evaluator,
+    corpus::{Corpus, CorpusId, HasCurrentCorpusId, Testcase},
+    events::{Event, EventFirer},
+    executors::BatchExecutor,
+    fuzzer::{Evaluator, ExecuteInputResult, ExecutionProcessor},
     inputs::Input,
     mark_feature_time,
-    mutators::{MultiMutator, MutationResult, Mutator},
+    monitors::{AggregatorOps, UserStats, UserStatsValue},
+    mutators::{
+        LogMutationMetadata, MultiMutator, MutationResult, Mutator, MutatorsTuple,
+        RoundRobinMutator,
+    },
     nonzero,
+    observers::ObserversTuple,
     stages::{RetryCountRestartHelper, Stage},
     start_timer,
-    state::{HasCorpus, HasCurrentTestcase, HasExecutions, HasRand, UsesState},
+    state::{
+        HasCorpus, HasCurrentTestcase, HasExecutions, HasImported, HasRand, HasSolutions,
+        Stoppable, UsesState,
+    },
     Error, HasMetadata, HasNamedMetadata,
 };
 #[cfg(feature = "introspection")]
@@ -69,7 +82,9 @@ where
     #[inline]
     fn try_transform_from(base: &mut Testcase<I>, state: &S) -> Result<Self, Error> {
         state.corpus().load_input_into(base)?;
-        Ok(base.input().as_ref().unwrap().clone())
+        base.input().clone().ok_or_else(|| {
+            Error::empty_optional("The testcase has no input, it may have failed to load")
+        })
     }
 
     #[inline]
@@ -87,7 +102,7 @@ where
     M: Mutator<I, Self::State>,
     EM: UsesState<State = Self::State>,
     Z: Evaluator<E, EM, State = Self::State>,
-    Self::State: HasCorpus + HasCurrentTestcase,
+    Self::State: HasCorpus + HasCurrentTestcase + HasMetadata,
     I: MutatedTransform<Self::Input, Self::State> + Clone,
     <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>,
 {
@@ -98,9 +113,50 @@ where
     fn mutator_mut(&mut self) -> &mut M;
 
     /// Gets the number of iterations this mutator should run for.
-    fn iterations(&self, state: &mut Self::State) -> Result<usize, Error>;
+    fn iterations(&mut self, state: &mut Self::State) -> Result<usize, Error>;
+
+    /// Gets the number of iterations already completed for the current testcase,
+    /// e.g., before the stage got restarted after a crash.
+    ///
+    /// By default, the progress is not tracked and the stage always starts over.
+    #[inline]
+    fn completed_iterations(&mut self, _state: &mut Self::State) -> Result<usize, Error> {
+        Ok(0)
+    }
+
+    /// Records the number of iterations completed for the current testcase so far,
+    /// see [`MutationalStage::completed_iterations`].
+    #[inline]
+    fn set_completed_iterations(
+        &mut self,
+        _state: &mut Self::State,
+        _completed_iterations: usize,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Inspects a mutated input before it is executed.
+    /// If this returns `false`, the input is not executed, as if the mutation was skipped.
+    #[inline]
+    fn pre_eval(&mut self, _state: &mut Self::State, _input: &I) -> bool {
+        true
+    }
+
+    /// Inspects the result of the evaluation of a mutated input, e.g., to annotate a new solution.
+    /// Called by [`MutationalStage::perform_mutational`] after each evaluation, before the mutator's `post_exec`.
+    #[inline]
+    fn post_eval(
+        &mut self,
+        _state: &mut Self::State,
+        _result: &ExecuteInputResult,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
 
     /// Runs this (mutational) stage for the given testcase
+    ///
+    /// Each executed input is taken from the [`ExecBudget`] of the state, if there is one.
+    /// Once it is exhausted, the stage returns early and requests the fuzzer to stop.
     #[allow(clippy::cast_possible_wrap)] // more than i32 stages on 32 bit system - highly unlikely...
     fn perform_mutational(
         &mut self,
@@ -111,13 +167,10 @@ where
     ) -> Result<(), Error> {
         start_timer!(state);
 
-        // Here saturating_sub is needed as self.iterations() might be actually smaller than the previous value before reset.
-        /*
-        let num = self
-            .iterations(state)?
-            .saturating_sub(self.execs_since_progress_start(state)?);
-        */
         let num = self.iterations(state)?;
+        // After a restart, only run the remaining `num.saturating_sub(completed)` iterations.
+        // `num` might actually be smaller than the previous value before the restart.
+        let start = self.completed_iterations(state)?.min(num);
         let mut testcase = state.current_testcase_mut()?;
 
         let Ok(input) = I::try_transform_from(&mut testcase, state) else {
@@ -126,26 +179,147 @@ where
         drop(testcase);
         mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
 
-        for _ in 0..num {
+        for iteration in start..num {
+            if ExecBudget::is_exhausted_in(state) {
+                break;
+            }
+            // everything before this iteration is done, in case we crash and get restarted
+            self.set_completed_iterations(state, iteration)?;
             let mut input = input.clone();
 
             start_timer!(state);
             let mutated = self.mutator_mut().mutate(state, &mut input)?;
             mark_feature_time!(state, PerfFeature::Mutate);
 
-            if mutated == MutationResult::Skipped {
+            if mutated == MutationResult::Skipped || !self.pre_eval(state, &input) {
                 continue;
             }
+            ExecBudget::spend_in(state, 1);
 
             // Time is measured directly the `evaluate_input` function
             let (untransformed, post) = input.try_transform_into(state)?;
-            let (_, corpus_id) = fuzzer.evaluate_input(state, executor, manager, untransformed)?;
+            let (result, corpus_id) =
+                fuzzer.evaluate_input(state, executor, manager, untransformed)?;
+            self.post_eval(state, &result)?;
 
             start_timer!(state);
             self.mutator_mut().post_exec(state, corpus_id)?;
             post.post_exec(state, corpus_id)?;
             mark_feature_time!(state, PerfFeature::MutatePostExec);
         }
+        // the round is done, the next one starts over
+        self.set_completed_iterations(state, 0)?;
+
+        Ok(())
+    }
+
+    /// Runs this (mutational) stage for the given testcase, like [`MutationalStage::perform_mutational`],
+    /// but mutates up to `batch_size` inputs first and runs them with a single [`BatchExecutor::run_batch`].
+    /// The results of the inputs are evaluated afterwards, one by one.
+    ///
+    /// Note that the mutator's `post_exec` is only called after the whole batch ran.
+    fn perform_mutational_batched(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+        batch_size: NonZeroUsize,
+    ) -> Result<(), Error>
+    where
+        E: BatchExecutor<EM, Z>,
+        E::Observers: ObserversTuple<Self::Input, Self::State> + Serialize,
+        EM: EventFirer<State = Self::State>,
+        Z: ExecutionProcessor<EM, E::Observers>,
+    {
+        start_timer!(state);
+
+        let num = self.iterations(state)?;
+        let mut iteration = self.completed_iterations(state)?.min(num);
+        let mut testcase = state.current_testcase_mut()?;
+
+        let Ok(input) = I::try_transform_from(&mut testcase, state) else {
+            return Ok(());
+        };
+        drop(testcase);
+        mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+
+        while iteration < num {
+            if ExecBudget::is_exhausted_in(state) {
+                break;
+            }
+            // everything before this batch is done, in case we crash and get restarted
+            self.set_completed_iterations(state, iteration)?;
+            let batch_size =
+                ExecBudget::remaining_in(state).map_or(batch_size.get(), |remaining| {
+                    batch_size
+                        .get()
+                        .min(usize::try_from(remaining).unwrap_or(usize::MAX))
+                });
+            let batch_end = num.min(iteration.saturating_add(batch_size));
+
+            let mut inputs = Vec::with_capacity(batch_end - iteration);
+            let mut posts = Vec::with_capacity(batch_end - iteration);
+            for _ in iteration..batch_end {
+                let mut input = input.clone();
+
+                start_timer!(state);
+                let mutated = self.mutator_mut().mutate(state, &mut input)?;
+                mark_feature_time!(state, PerfFeature::Mutate);
+
+                if mutated == MutationResult::Skipped || !self.pre_eval(state, &input) {
+                    continue;
+                }
+
+                let (untransformed, post) = input.try_transform_into(state)?;
+                inputs.push(untransformed);
+                posts.push(post);
+            }
+            iteration = batch_end;
+            if inputs.is_empty() {
+                continue;
+            }
+            ExecBudget::spend_in(state, inputs.len() as u64);
+
+            start_timer!(state);
+            let exit_kinds = executor.run_batch(fuzzer, state, manager, &inputs)?;
+            mark_feature_time!(state, PerfFeature::TargetExecution);
+            if exit_kinds.len() != inputs.len() {
+                return Err(Error::illegal_state(format!(
+                    "The executor returned {} exit kinds for a batch of {} inputs",
+                    exit_kinds.len(),
+                    inputs.len()
+                )));
+            }
+
+            for (idx, ((input, post), exit_kind)) in
+                inputs.into_iter().zip(posts).zip(exit_kinds).enumerate()
+            {
+                start_timer!(state);
+                executor.observers_mut().pre_exec_all(state, &input)?;
+                executor.load_batch_observations(state, idx)?;
+                executor
+                    .observers_mut()
+                    .post_exec_all(state, &input, &exit_kind)?;
+                mark_feature_time!(state, PerfFeature::PostExecObservers);
+
+                let (_, corpus_id) = fuzzer.evaluate_execution(
+                    state,
+                    manager,
+                    input,
+                    &*executor.observers(),
+                    &exit_kind,
+                    true,
+                )?;
+
+                start_timer!(state);
+                self.mutator_mut().post_exec(state, corpus_id)?;
+                post.post_exec(state, corpus_id)?;
+                mark_feature_time!(state, PerfFeature::MutatePostExec);
+            }
+        }
+        // the round is done, the next one starts over
+        self.set_completed_iterations(state, 0)?;
 
         Ok(())
     }
@@ -155,27 +329,343 @@ where
 /// It may randomly continue earlier.
 pub const DEFAULT_MUTATIONAL_MAX_ITERATIONS: usize = 128;
 
+/// Decides how many mutations a mutational stage performs on the current testcase.
+pub trait IterationsPolicy<S> {
+    /// Gets the number of iterations to run for the current testcase
+    fn iterations(&mut self, state: &mut S) -> Result<usize, Error>;
+}
+
+/// Runs a random number of iterations between `1` and `max_iterations`.
+/// This is the default [`IterationsPolicy`] of the [`StdMutationalStage`].
+#[derive(Clone, Copy, Debug)]
+pub struct RandomIterations {
+    max_iterations: NonZeroUsize,
+}
+
+impl RandomIterations {
+    /// Creates a new [`RandomIterations`] policy with the given upper bound
+    #[must_use]
+    pub fn new(max_iterations: NonZeroUsize) -> Self {
+        Self { max_iterations }
+    }
+
+    /// The maximum amount of iterations
+    #[must_use]
+    pub fn max_iterations(&self) -> NonZeroUsize {
+        self.max_iterations
+    }
+
+    /// Sets the maximum amount of iterations
+    pub fn set_max_iterations(&mut self, max_iterations: NonZeroUsize) {
+        self.max_iterations = max_iterations;
+    }
+}
+
+impl Default for RandomIterations {
+    fn default() -> Self {
+        Self::new(nonzero!(DEFAULT_MUTATIONAL_MAX_ITERATIONS))
+    }
+}
+
+impl<S> IterationsPolicy<S> for RandomIterations
+where
+    S: HasRand,
+{
+    fn iterations(&mut self, state: &mut S) -> Result<usize, Error> {
+        Ok(1 + state.rand_mut().below(self.max_iterations))
+    }
+}
+
+/// Always runs the same number of iterations.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedIterations {
+    iterations: usize,
+}
+
+impl FixedIterations {
+    /// Creates a new [`FixedIterations`] policy
+    #[must_use]
+    pub fn new(iterations: usize) -> Self {
+        Self { iterations }
+    }
+}
+
+impl<S> IterationsPolicy<S> for FixedIterations {
+    fn iterations(&mut self, _state: &mut S) -> Result<usize, Error> {
+        Ok(self.iterations)
+    }
+}
+
+/// The default for [`AdaptiveIterations::set_smoothing`]
+pub const DEFAULT_ADAPTIVE_ITERATIONS_SMOOTHING: f64 = 0.3;
+
+/// The productivity of a testcase for the [`AdaptiveIterations`] policy, in the metadata of the testcase:
+/// an exponential moving average of the corpus additions per round of fuzzing it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct AdaptiveIterationsMetadata {
+    ema: f64,
+}
+
+libafl_bolts::impl_serdeany!(AdaptiveIterationsMetadata);
+
+impl Default for AdaptiveIterationsMetadata {
+    /// Testcases that were not fuzzed yet are assumed to add one testcase per round
+    fn default() -> Self {
+        Self { ema: 1.0 }
+    }
+}
+
+impl AdaptiveIterationsMetadata {
+    /// The average corpus additions per round
+    #[must_use]
+    pub fn ema(&self) -> f64 {
+        self.ema
+    }
+
+    /// Accounts for a round with `additions` corpus additions, weighting it with `smoothing`
+    pub fn update(&mut self, additions: usize, smoothing: f64) {
+        #[allow(clippy::cast_precision_loss)]
+        let additions = additions as f64;
+        self.ema = smoothing * additions + (1.0 - smoothing) * self.ema;
+    }
+}
+
+/// Runs a random number of iterations between `1` and an upper bound that adapts to the recent productivity
+/// of the current testcase, AFL-MOpt style: testcases that recently added to the corpus get more iterations,
+/// barren ones fewer.
+///
+/// The bound grows from `min_iterations` towards `max_iterations` with the [`AdaptiveIterationsMetadata`]
+/// of the testcase, reaching the middle for an average of one addition per round.
+/// A round is accounted for when the next testcase starts, as the corpus growth since the previous one,
+/// without the testcases imported from other clients. Hence, finds of the other stages fuzzing the same
+/// testcase count, too.
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveIterations {
+    min_iterations: NonZeroUsize,
+    max_iterations: NonZeroUsize,
+    smoothing: f64,
+    // the testcase of the round in progress, and the own corpus size when it started
+    last_round: Option<(CorpusId, usize)>,
+    last_bound: Option<NonZeroUsize>,
+}
+
+impl AdaptiveIterations {
+    /// Creates a new [`AdaptiveIterations`] policy, with an upper bound between `min_iterations` and `max_iterations`
+    pub fn new(min_iterations: NonZeroUsize, max_iterations: NonZeroUsize) -> Result<Self, Error> {
+        if min_iterations > max_iterations {
+            return Err(Error::illegal_argument(format!(
+                "min_iterations ({min_iterations}) must not exceed max_iterations ({max_iterations})"
+            )));
+        }
+        Ok(Self {
+            min_iterations,
+            max_iterations,
+            smoothing: DEFAULT_ADAPTIVE_ITERATIONS_SMOOTHING,
+            last_round: None,
+            last_bound: None,
+        })
+    }
+
+    /// Sets the weight of the latest round in the moving average, in `(0, 1]`.
+    /// Defaults to [`DEFAULT_ADAPTIVE_ITERATIONS_SMOOTHING`].
+    pub fn set_smoothing(&mut self, smoothing: f64) -> Result<(), Error> {
+        if smoothing <= 0.0 || smoothing > 1.0 {
+            return Err(Error::illegal_argument(format!(
+                "The smoothing must be in (0, 1], got {smoothing}"
+            )));
+        }
+        self.smoothing = smoothing;
+        Ok(())
+    }
+
+    /// The upper bound of the iterations for a testcase with an average of `ema` corpus additions per round
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn bound(&self, ema: f64) -> NonZeroUsize {
+        let min = self.min_iterations.get();
+        let range = (self.max_iterations.get() - min) as f64;
+        let ema = ema.max(0.0);
+        let extra = (range * ema / (ema + 1.0)) as usize;
+        self.min_iterations.saturating_add(extra)
+    }
+
+    /// The upper bound used for the last testcase, if any
+    #[must_use]
+    pub fn last_bound(&self) -> Option<NonZeroUsize> {
+        self.last_bound
+    }
+}
+
+impl<S> IterationsPolicy<S> for AdaptiveIterations
+where
+    S: HasCorpus + HasCurrentCorpusId + HasImported + HasRand,
+{
+    fn iterations(&mut self, state: &mut S) -> Result<usize, Error> {
+        let own_corpus_size = state.corpus().count().saturating_sub(*state.imported());
+        if let Some((id, size_before)) = self.last_round.take() {
+            // the testcase may have been removed in the meantime
+            if let Ok(testcase) = state.corpus().get(id) {
+                testcase
+                    .borrow_mut()
+                    .metadata_or_insert_with(AdaptiveIterationsMetadata::default)
+                    .update(own_corpus_size.saturating_sub(size_before), self.smoothing);
+            }
+        }
+
+        let Some(id) = state.current_corpus_id()? else {
+            return Err(Error::illegal_state(
+                "state is not currently processing a corpus index",
+            ));
+        };
+        let ema = state
+            .corpus()
+            .get(id)?
+            .borrow()
+            .metadata::<AdaptiveIterationsMetadata>()
+            .map_or_else(
+                |_| AdaptiveIterationsMetadata::default().ema(),
+                AdaptiveIterationsMetadata::ema,
+            );
+        let bound = self.bound(ema);
+        self.last_round = Some((id, own_corpus_size));
+        self.last_bound = Some(bound);
+
+        Ok(1 + state.rand_mut().below(bound))
+    }
+}
+
+/// A campaign-wide budget of executions for the mutational stages, e.g., to stop a fuzzing campaign in CI
+/// after a fixed total number of executions.
+///
+/// Add it to the metadata of the state to enable it. Each input executed by
+/// [`MutationalStage::perform_mutational`] is taken from the budget. Once it is exhausted, the stage
+/// returns early and requests the fuzzer to stop, see [`Stoppable`].
+/// As the budget is part of the state, it survives restarts.
+///
+/// All mutational stages share the same budget: it limits the executions of all of them together, and
+/// the first stage to find it exhausted stops the fuzzer. Other stages, and the executions outside of
+/// mutational stages, e.g., calibration or the initial corpus, are not counted.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+pub struct ExecBudget {
+    remaining: u64,
+}
+
+libafl_bolts::impl_serdeany!(ExecBudget);
+
+impl ExecBudget {
+    /// Creates a new [`ExecBudget`] of `executions` executions
+    #[must_use]
+    pub fn new(executions: u64) -> Self {
+        Self {
+            remaining: executions,
+        }
+    }
+
+    /// The number of executions left
+    #[must_use]
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Returns if the budget is exhausted
+    #[must_use]
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Takes `executions` executions from the budget
+    pub fn spend(&mut self, executions: u64) {
+        self.remaining = self.remaining.saturating_sub(executions);
+    }
+
+    /// The number of executions left in the budget of the state, if it has one
+    pub fn remaining_in<S>(state: &S) -> Option<u64>
+    where
+        S: HasMetadata,
+    {
+        state.metadata::<Self>().ok().map(ExecBudget::remaining)
+    }
+
+    /// Returns if the budget of the state is exhausted, and requests the fuzzer to stop if so.
+    ///
+    /// A state without a budget is never exhausted.
+    pub fn is_exhausted_in<S>(state: &mut S) -> bool
+    where
+        S: HasMetadata + Stoppable,
+    {
+        let exhausted = state.metadata::<Self>().is_ok_and(ExecBudget::is_exhausted);
+        if exhausted {
+            state.request_stop();
+        }
+        exhausted
+    }
+
+    /// Takes `executions` executions from the budget of the state, if it has one
+    pub fn spend_in<S>(state: &mut S, executions: u64)
+    where
+        S: HasMetadata,
+    {
+        if let Ok(budget) = state.metadata_mut::<Self>() {
+            budget.spend(executions);
+        }
+    }
+}
+
 /// The default mutational stage
+///
+/// The mutator works on inputs of type `I`. With [`StdMutationalStage::new`], this is the input
+/// type of the corpus. With [`StdMutationalStage::transforming`], `I` may be any [`MutatedTransform`]
+/// of the corpus input: each testcase is transformed into an `I` for mutation, and each mutated `I`
+/// is transformed back for execution.
+/// The transform can be pinned explicitly, i.e., `StdMutationalStage::<_, _, MyTransform, _, _>::transforming(mutator)`.
+///
+/// Mutated inputs can be filtered before their execution with [`StdMutationalStage::with_pre_eval`].
+/// The mutations leading to a solution can be recorded with [`StdMutationalStage::record_mutation_chain`].
 #[derive(Clone, Debug)]
-pub struct StdMutationalStage<E, EM, I, M, Z> {
+pub struct StdMutationalStage<E, EM, I, M, Z, P = RandomIterations, F = fn(&I) -> bool> {
     /// The name
     name: Cow<'static, str>,
     /// The mutator(s) to use
     mutator: M,
-    /// The maximum amount of iterations we should do each round
-    max_iterations: NonZeroUsize,
+    /// Decides how many iterations we should do each round
+    iterations_policy: P,
+    /// Decides if a mutated input should be executed, if set
+    pre_eval: Option<F>,
+    /// Whether to add the mutation log of the mutator to new solutions
+    record_mutation_chain: bool,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, I, Z)>,
 }
 
-impl<E, EM, I, M, Z> MutationalStage<E, EM, I, M, Z> for StdMutationalStage<E, EM, I, M, Z>
+impl<E, EM, I, M, Z, P, F> MutationalStage<E, EM, I, M, Z>
+    for StdMutationalStage<E, EM, I, M, Z, P, F>
 where
     E: UsesState<State = Self::State>,
     EM: UsesState<State = Self::State>,
     M: Mutator<I, Self::State>,
     Z: Evaluator<E, EM>,
-    Z::State: HasCorpus + HasRand + HasExecutions + HasMetadata + HasNamedMetadata,
+    Z::State: HasCorpus
+        + HasCurrentCorpusId
+        + HasRand
+        + HasExecutions
+        + HasMetadata
+        + HasNamedMetadata
+        + HasSolutions,
     I: MutatedTransform<Self::Input, Self::State> + Clone,
+    P: IterationsPolicy<Self::State>,
+    F: FnMut(&I) -> bool,
     <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
 {
     /// The mutator, added to this stage
@@ -190,9 +680,55 @@ where
         &mut self.mutator
     }
 
-    /// Gets the number of iterations as a random number
-    fn iterations(&self, state: &mut Self::State) -> Result<usize, Error> {
-        Ok(1 + state.rand_mut().below(self.max_iterations))
+    /// Gets the number of iterations from the [`IterationsPolicy`]
+    fn iterations(&mut self, state: &mut Self::State) -> Result<usize, Error> {
+        self.iterations_policy.iterations(state)
+    }
+
+    #[inline]
+    fn pre_eval(&mut self, _state: &mut Self::State, input: &I) -> bool {
+        match &mut self.pre_eval {
+            Some(pre_eval) => pre_eval(input),
+            None => true,
+        }
+    }
+
+    /// Adds the mutation log of the mutator to a new solution, if [`StdMutationalStage::record_mutation_chain`] is set
+    fn post_eval(
+        &mut self,
+        state: &mut Self::State,
+        result: &ExecuteInputResult,
+    ) -> Result<(), Error> {
+        if !self.record_mutation_chain || *result != ExecuteInputResult::Solution {
+            return Ok(());
+        }
+        let (Some(log), Some(id)) = (self.mutator.mutation_log(), state.solutions().last()) else {
+            return Ok(());
+        };
+        let parent_id = state.current_corpus_id()?;
+        let mut solution = state.solutions().get(id)?.borrow_mut();
+        solution.add_metadata(LogMutationMetadata::new(log));
+        if solution.parent_id().is_none() {
+            solution.set_parent_id_optional(parent_id);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn completed_iterations(&mut self, state: &mut Self::State) -> Result<usize, Error> {
+        Ok(RetryCountRestartHelper::completed_iterations(
+            state, &self.name,
+        ))
+    }
+
+    #[inline]
+    fn set_completed_iterations(
+        &mut self,
+        state: &mut Self::State,
+        completed_iterations: usize,
+    ) -> Result<(), Error> {
+        RetryCountRestartHelper::set_completed_iterations(state, &self.name, completed_iterations);
+        Ok(())
     }
 }
 
@@ -201,27 +737,35 @@ static mut MUTATIONAL_STAGE_ID: usize = 0;
 /// The name for mutational stage
 pub static MUTATIONAL_STAGE_NAME: &str = "mutational";
 
-impl<E, EM, I, M, Z> UsesState for StdMutationalStage<E, EM, I, M, Z>
+impl<E, EM, I, M, Z, P, F> UsesState for StdMutationalStage<E, EM, I, M, Z, P, F>
 where
     Z: UsesState,
 {
     type State = Z::State;
 }
 
-impl<E, EM, I, M, Z> Named for StdMutationalStage<E, EM, I, M, Z> {
+impl<E, EM, I, M, Z, P, F> Named for StdMutationalStage<E, EM, I, M, Z, P, F> {
     fn name(&self) -> &Cow<'static, str> {
         &self.name
     }
 }
 
-impl<E, EM, I, M, Z> Stage<E, EM, Z> for StdMutationalStage<E, EM, I, M, Z>
+impl<E, EM, I, M, Z, P, F> Stage<E, EM, Z> for StdMutationalStage<E, EM, I, M, Z, P, F>
 where
     E: UsesState<State = Self::State>,
     EM: UsesState<State = Self::State>,
     M: Mutator<I, Self::State>,
     Z: Evaluator<E, EM>,
-    Z::State: HasCorpus + HasRand + HasMetadata + HasExecutions + HasNamedMetadata,
+    Z::State: HasCorpus
+        + HasCurrentCorpusId
+        + HasRand
+        + HasMetadata
+        + HasExecutions
+        + HasNamedMetadata
+        + HasSolutions,
     I: MutatedTransform<Self::Input, Self::State> + Clone,
+    P: IterationsPolicy<Self::State>,
+    F: FnMut(&I) -> bool,
     <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
 {
     #[inline]
@@ -242,7 +786,7 @@ where
     }
 
     fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
-        RetryCountRestartHelper::should_restart(state, &self.name, 3)
+        RetryCountRestartHelper::should_restart_or_abandon(state, &self.name, 3)
     }
 
     fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
@@ -291,6 +835,35 @@ where
     /// Will return [`Error::IllegalArgument`] for `max_iterations` of 0.
     #[inline]
     pub fn transforming_with_max_iterations(mutator: M, max_iterations: NonZeroUsize) -> Self {
+        Self::transforming_with_iterations_policy(mutator, RandomIterations::new(max_iterations))
+    }
+}
+
+impl<E, EM, M, Z, P> StdMutationalStage<E, EM, Z::Input, M, Z, P>
+where
+    E: UsesState<State = <Self as UsesState>::State>,
+    EM: UsesState<State = <Self as UsesState>::State>,
+    M: Mutator<Z::Input, <Self as UsesState>::State>,
+    Z: Evaluator<E, EM>,
+    P: IterationsPolicy<<Self as UsesState>::State>,
+{
+    /// Creates a new mutational stage that uses the given [`IterationsPolicy`]
+    #[inline]
+    pub fn with_iterations_policy(mutator: M, iterations_policy: P) -> Self {
+        Self::transforming_with_iterations_policy(mutator, iterations_policy)
+    }
+}
+
+impl<E, EM, I, M, Z, P> StdMutationalStage<E, EM, I, M, Z, P>
+where
+    E: UsesState<State = <Self as UsesState>::State>,
+    EM: UsesState<State = <Self as UsesState>::State>,
+    M: Mutator<I, <Self as UsesState>::State>,
+    Z: Evaluator<E, EM>,
+    P: IterationsPolicy<<Self as UsesState>::State>,
+{
+    /// Creates a new transforming mutational stage that uses the given [`IterationsPolicy`]
+    pub fn transforming_with_iterations_policy(mutator: M, iterations_policy: P) -> Self {
         let stage_id = unsafe {
             let ret = MUTATIONAL_STAGE_ID;
             MUTATIONAL_STAGE_ID += 1;
@@ -301,10 +874,95 @@ where
         Self {
             name,
             mutator,
-            max_iterations,
+            iterations_policy,
+            pre_eval: None,
+            record_mutation_chain: false,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E, EM, I, M, Z, P, F> StdMutationalStage<E, EM, I, M, Z, P, F> {
+    /// The [`IterationsPolicy`] of this stage
+    pub fn iterations_policy(&self) -> &P {
+        &self.iterations_policy
+    }
+
+    /// The [`IterationsPolicy`] of this stage (mutable)
+    pub fn iterations_policy_mut(&mut self) -> &mut P {
+        &mut self.iterations_policy
+    }
+
+    /// Only execute the mutated inputs for which `pre_eval` returns `true`,
+    /// e.g., to drop inputs that break a structural invariant without running them.
+    /// Inputs that are filtered out are treated like skipped mutations.
+    pub fn with_pre_eval<F2>(self, pre_eval: F2) -> StdMutationalStage<E, EM, I, M, Z, P, F2>
+    where
+        F2: FnMut(&I) -> bool,
+    {
+        StdMutationalStage {
+            name: self.name,
+            mutator: self.mutator,
+            iterations_policy: self.iterations_policy,
+            pre_eval: Some(pre_eval),
+            record_mutation_chain: self.record_mutation_chain,
             phantom: PhantomData,
         }
     }
+
+    /// Add the names of the mutations that led to a solution to the solution's testcase, as a
+    /// [`LogMutationMetadata`], to help reproducing and minimizing it. Off by default.
+    ///
+    /// This depends on the mutator to keep a log of its last mutations, see [`Mutator::mutation_log`],
+    /// e.g., by wrapping it in a [`crate::mutators::LoggerScheduledMutator`]. For other mutators, nothing is recorded.
+    /// The testcase the mutations started from is set as the parent of the solution, see [`Testcase::parent_id`].
+    ///
+    /// The log is added after the solution was evaluated, so it is missing if the solution was
+    /// stored from a crash handler, e.g., of an in-process executor, and it is not written to the disk of
+    /// an on-disk solutions corpus.
+    #[must_use]
+    pub fn record_mutation_chain(mut self, record_mutation_chain: bool) -> Self {
+        self.record_mutation_chain = record_mutation_chain;
+        self
+    }
+}
+
+impl<E, EM, I, M, Z, F> StdMutationalStage<E, EM, I, M, Z, RandomIterations, F> {
+    /// The maximum amount of iterations per testcase
+    #[must_use]
+    pub fn max_iterations(&self) -> NonZeroUsize {
+        self.iterations_policy.max_iterations()
+    }
+
+    /// Sets the maximum amount of iterations per testcase, e.g., to ramp up the mutations
+    /// as the corpus matures. The stage keeps its name, and so its restart progress.
+    pub fn set_max_iterations(&mut self, max_iterations: NonZeroUsize) {
+        self.iterations_policy.set_max_iterations(max_iterations);
+    }
+}
+
+/// The outcome of the inputs generated by a [`MultiMutationalStage`], see [`MultiMutationalStage::report_stats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MultiMutationalStats {
+    /// The number of generated inputs that were evaluated
+    pub generated: u64,
+    /// The number of generated inputs that were added to the corpus
+    pub corpus_additions: u64,
+    /// The number of generated inputs that were objectives
+    pub objectives: u64,
+}
+
+impl MultiMutationalStats {
+    /// Counts a generated input, with the result of its evaluation
+    fn record(&mut self, result: &ExecuteInputResult, corpus_id: Option<CorpusId>) {
+        self.generated += 1;
+        if corpus_id.is_some() {
+            self.corpus_additions += 1;
+        }
+        if *result == ExecuteInputResult::Solution {
+            self.objectives += 1;
+        }
+    }
 }
 
 /// A mutational stage that operates on multiple inputs, as returned by [`MultiMutator::multi_mutate`].
@@ -312,6 +970,9 @@ where
 pub struct MultiMutationalStage<E, EM, I, M, Z> {
     name: Cow<'static, str>,
     mutator: M,
+    // the stats and their report interval, if enabled
+    stats: Option<(MultiMutationalStats, Duration)>,
+    last_stats_report_time: Duration,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, I, Z)>,
 }
@@ -337,17 +998,17 @@ impl<E, EM, I, M, Z> Named for MultiMutationalStage<E, EM, I, M, Z> {
 impl<E, EM, I, M, Z> Stage<E, EM, Z> for MultiMutationalStage<E, EM, I, M, Z>
 where
     E: UsesState<State = Self::State>,
-    EM: UsesState<State = Self::State>,
+    EM: EventFirer<State = Self::State>,
     M: MultiMutator<I, Self::State>,
     Z: Evaluator<E, EM>,
-    Z::State: HasCorpus + HasRand + HasNamedMetadata + HasCurrentTestcase,
+    Z::State: HasCorpus + HasRand + HasMetadata + HasNamedMetadata + HasCurrentTestcase,
     I: MutatedTransform<Self::Input, Self::State> + Clone,
     <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
 {
     #[inline]
     fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
         // Make sure we don't get stuck crashing on a single testcase
-        RetryCountRestartHelper::should_restart(state, &self.name, 3)
+        RetryCountRestartHelper::should_restart_or_abandon(state, &self.name, 3)
     }
 
     #[inline]
@@ -376,13 +1037,17 @@ where
         for new_input in generated {
             // Time is measured directly the `evaluate_input` function
             let (untransformed, post) = new_input.try_transform_into(state)?;
-            let (_, corpus_id) = fuzzer.evaluate_input(state, executor, manager, untransformed)?;
+            let (result, corpus_id) =
+                fuzzer.evaluate_input(state, executor, manager, untransformed)?;
             self.mutator.multi_post_exec(state, corpus_id)?;
+            if let Some((stats, _)) = &mut self.stats {
+                stats.record(&result, corpus_id);
+            }
             post.post_exec(state, corpus_id)?;
         }
         // println!("Found {}", found);
 
-        Ok(())
+        self.maybe_report_stats(state, manager)
     }
 }
 
@@ -410,7 +1075,1017 @@ impl<E, EM, I, M, Z> MultiMutationalStage<E, EM, I, M, Z> {
                 MULTI_MUTATIONAL_STAGE_NAME.to_owned() + ":" + stage_id.to_string().as_str(),
             ),
             mutator,
+            stats: None,
+            last_stats_report_time: Duration::ZERO,
             phantom: PhantomData,
         }
     }
+
+    /// Count the generated inputs, and how many of them were added to the corpus or were objectives,
+    /// and report the counts as [`UserStats`] named after the stage, at most every `interval`.
+    /// Off by default.
+    #[must_use]
+    pub fn report_stats(mut self, interval: Duration) -> Self {
+        self.stats = Some((MultiMutationalStats::default(), interval));
+        self
+    }
+
+    /// The counts of the generated inputs so far, if enabled with [`Self::report_stats`]
+    #[must_use]
+    pub fn stats(&self) -> Option<&MultiMutationalStats> {
+        self.stats.as_ref().map(|(stats, _)| stats)
+    }
+
+    fn maybe_report_stats(&mut self, state: &mut EM::State, manager: &mut EM) -> Result<(), Error>
+    where
+        EM: EventFirer,
+    {
+        let Some((stats, interval)) = self.stats else {
+            return Ok(());
+        };
+        let cur = current_time();
+        if cur
+            .checked_sub(self.last_stats_report_time)
+            .unwrap_or_default()
+            < interval
+        {
+            return Ok(());
+        }
+        self.last_stats_report_time = cur;
+
+        for (suffix, count) in [
+            ("generated", stats.generated),
+            ("corpus_additions", stats.corpus_additions),
+            ("objectives", stats.objectives),
+        ] {
+            manager.fire(
+                state,
+                Event::UpdateUserStats {
+                    name: Cow::Owned(format!("{}_{suffix}", self.name)),
+                    value: UserStats::new(UserStatsValue::Number(count), AggregatorOps::Sum),
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A mutational stage that cycles through several mutators, one per iteration, e.g., to alternate
+/// between a havoc and an input-to-state mutator without adding a stage for each.
+///
+/// The mutators are given as a [`MutatorsTuple`], i.e., a tuple list or a `Vec` of boxed mutators,
+/// and are applied by a [`RoundRobinMutator`] in [`MutationalStage::perform_mutational`].
+/// Iteration `i` of each round uses the mutator at `i % len`, and only this mutator gets the `post_exec`
+/// call for the input it mutated. Like the [`StdMutationalStage`], the stage resumes an interrupted
+/// round after a restart, with the same mutator.
+#[derive(Clone, Debug)]
+pub struct RoundRobinMutationalStage<E, EM, I, MT, Z, P = RandomIterations, F = fn(&I) -> bool> {
+    name: Cow<'static, str>,
+    mutator: RoundRobinMutator<MT>,
+    iterations_policy: P,
+    pre_eval: Option<F>,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, I, Z)>,
+}
+
+/// The unique id for round-robin mutational stage
+static mut ROUND_ROBIN_MUTATIONAL_STAGE_ID: usize = 0;
+/// The name for round-robin mutational stage
+pub static ROUND_ROBIN_MUTATIONAL_STAGE_NAME: &str = "roundrobinmutational";
+
+impl<E, EM, I, MT, Z, P, F> UsesState for RoundRobinMutationalStage<E, EM, I, MT, Z, P, F>
+where
+    Z: UsesState,
+{
+    type State = Z::State;
+}
+
+impl<E, EM, I, MT, Z, P, F> Named for RoundRobinMutationalStage<E, EM, I, MT, Z, P, F> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, I, MT, Z, P, F> MutationalStage<E, EM, I, RoundRobinMutator<MT>, Z>
+    for RoundRobinMutationalStage<E, EM, I, MT, Z, P, F>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState<State = Self::State>,
+    MT: MutatorsTuple<I, Self::State>,
+    Z: Evaluator<E, EM>,
+    Z::State: HasCorpus + HasRand + HasMetadata + HasNamedMetadata + HasCurrentTestcase,
+    I: MutatedTransform<Self::Input, Self::State> + Clone,
+    P: IterationsPolicy<Self::State>,
+    F: FnMut(&I) -> bool,
+    <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
+{
+    #[inline]
+    fn mutator(&self) -> &RoundRobinMutator<MT> {
+        &self.mutator
+    }
+
+    #[inline]
+    fn mutator_mut(&mut self) -> &mut RoundRobinMutator<MT> {
+        &mut self.mutator
+    }
+
+    fn iterations(&mut self, state: &mut Self::State) -> Result<usize, Error> {
+        self.iterations_policy.iterations(state)
+    }
+
+    #[inline]
+    fn pre_eval(&mut self, _state: &mut Self::State, input: &I) -> bool {
+        match &mut self.pre_eval {
+            Some(pre_eval) => pre_eval(input),
+            None => true,
+        }
+    }
+
+    #[inline]
+    fn completed_iterations(&mut self, state: &mut Self::State) -> Result<usize, Error> {
+        Ok(RetryCountRestartHelper::completed_iterations(
+            state, &self.name,
+        ))
+    }
+
+    /// Also selects the mutator of the next iteration, so a resumed round continues with the same mutator
+    #[inline]
+    fn set_completed_iterations(
+        &mut self,
+        state: &mut Self::State,
+        completed_iterations: usize,
+    ) -> Result<(), Error> {
+        RetryCountRestartHelper::set_completed_iterations(state, &self.name, completed_iterations);
+        self.mutator.set_next_index(completed_iterations);
+        Ok(())
+    }
+}
+
+impl<E, EM, I, MT, Z, P, F> Stage<E, EM, Z> for RoundRobinMutationalStage<E, EM, I, MT, Z, P, F>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState<State = Self::State>,
+    MT: MutatorsTuple<I, Self::State>,
+    Z: Evaluator<E, EM>,
+    Z::State: HasCorpus + HasRand + HasMetadata + HasNamedMetadata + HasCurrentTestcase,
+    I: MutatedTransform<Self::Input, Self::State> + Clone,
+    P: IterationsPolicy<Self::State>,
+    F: FnMut(&I) -> bool,
+    <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
+{
+    #[inline]
+    #[allow(clippy::let_and_return)]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let ret = self.perform_mutational(fuzzer, executor, state, manager);
+
+        #[cfg(feature = "introspection")]
+        state.introspection_monitor_mut().finish_stage();
+
+        ret
+    }
+
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        RetryCountRestartHelper::should_restart_or_abandon(state, &self.name, 3)
+    }
+
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        RetryCountRestartHelper::clear_progress(state, &self.name)
+    }
+}
+
+impl<E, EM, MT, Z> RoundRobinMutationalStage<E, EM, Z::Input, MT, Z>
+where
+    MT: HasLen,
+    Z: UsesState,
+{
+    /// Creates a new [`RoundRobinMutationalStage`] with the default max iterations
+    ///
+    /// # Errors
+    /// Will return [`Error::IllegalArgument`] if there are no mutators.
+    pub fn new(mutators: MT) -> Result<Self, Error> {
+        Self::transforming_with_iterations_policy(mutators, RandomIterations::default())
+    }
+}
+
+impl<E, EM, MT, Z, P> RoundRobinMutationalStage<E, EM, Z::Input, MT, Z, P>
+where
+    MT: HasLen,
+    Z: UsesState,
+{
+    /// Creates a new [`RoundRobinMutationalStage`] that uses the given [`IterationsPolicy`]
+    ///
+    /// # Errors
+    /// Will return [`Error::IllegalArgument`] if there are no mutators.
+    pub fn with_iterations_policy(mutators: MT, iterations_policy: P) -> Result<Self, Error> {
+        Self::transforming_with_iterations_policy(mutators, iterations_policy)
+    }
+}
+
+impl<E, EM, I, MT, Z, P> RoundRobinMutationalStage<E, EM, I, MT, Z, P>
+where
+    MT: HasLen,
+{
+    /// Creates a new transforming [`RoundRobinMutationalStage`] that uses the given [`IterationsPolicy`]
+    ///
+    /// # Errors
+    /// Will return [`Error::IllegalArgument`] if there are no mutators.
+    pub fn transforming_with_iterations_policy(
+        mutators: MT,
+        iterations_policy: P,
+    ) -> Result<Self, Error> {
+        let mutator = RoundRobinMutator::new(mutators)?;
+        // unsafe but impossible that you create two threads both instantiating this instance
+        let stage_id = unsafe {
+            let ret = ROUND_ROBIN_MUTATIONAL_STAGE_ID;
+            ROUND_ROBIN_MUTATIONAL_STAGE_ID += 1;
+            ret
+        };
+        Ok(Self {
+            name: Cow::Owned(
+                ROUND_ROBIN_MUTATIONAL_STAGE_NAME.to_owned() + ":" + stage_id.to_string().as_str(),
+            ),
+            mutator,
+            iterations_policy,
+            pre_eval: None,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<E, EM, I, MT, Z, P, F> RoundRobinMutationalStage<E, EM, I, MT, Z, P, F> {
+    /// Only execute the mutated inputs for which `pre_eval` returns `true`,
+    /// see [`StdMutationalStage::with_pre_eval`].
+    pub fn with_pre_eval<F2>(
+        self,
+        pre_eval: F2,
+    ) -> RoundRobinMutationalStage<E, EM, I, MT, Z, P, F2>
+    where
+        F2: FnMut(&I) -> bool,
+    {
+        RoundRobinMutationalStage {
+            name: self.name,
+            mutator: self.mutator,
+            iterations_policy: self.iterations_policy,
+            pre_eval: Some(pre_eval),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The mutators of this stage
+    pub fn mutators(&self) -> &MT {
+        self.mutator.mutators()
+    }
+
+    /// The mutators of this stage (mutable)
+    pub fn mutators_mut(&mut self) -> &mut MT {
+        self.mutator.mutators_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, rc::Rc, vec::Vec};
+    use core::{cell::RefCell, marker::PhantomData, time::Duration};
+
+    use libafl_bolts::{
+        rands::StdRand,
+        tuples::{tuple_list, RefIndexable},
+        Error, Named,
+    };
+
+    use crate::{
+        corpus::{Corpus, CorpusId, HasCurrentCorpusId, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{BatchExecutor, Executor, ExitKind, HasObservers, InProcessExecutor},
+        feedbacks::{ConstFeedback, CrashFeedback, Feedback, StateInitializer},
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::{
+            BitFlipMutator, LogMutationMetadata, LoggerScheduledMutator, MultiMutator,
+            MutationResult, Mutator, NopMutator, StdScheduledMutator,
+        },
+        nonzero,
+        schedulers::RandScheduler,
+        stages::{
+            mutational::{
+                MultiMutationalStage, MultiMutationalStats, MutatedTransform,
+                DEFAULT_MUTATIONAL_MAX_ITERATIONS,
+            },
+            AdaptiveIterations, AdaptiveIterationsMetadata, ExecBudget, FixedIterations,
+            IterationsPolicy, MutationalStage, RetryCountRestartHelper, RoundRobinMutationalStage,
+            Stage, StdMutationalStage,
+        },
+        state::{
+            HasCorpus, HasCurrentTestcase, HasExecutions, HasImported, HasSolutions, State,
+            StdState, Stoppable, UsesState,
+        },
+        HasMetadata, StdFuzzer,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// Sets up a fuzzer with the `feedback` and the `objective`, and an executor running the `harness`,
+    /// for a state whose only testcase, `testcase`, is the current one
+    #[allow(clippy::type_complexity)]
+    fn fuzz_setup<H, F, OF>(
+        harness: &mut H,
+        testcase: Testcase<BytesInput>,
+        mut feedback: F,
+        mut objective: OF,
+    ) -> Result<
+        (
+            StdFuzzer<RandScheduler<TestState>, F, OF, TestState>,
+            InProcessExecutor<'_, H, (), TestState>,
+            TestState,
+            NopEventManager<TestState>,
+        ),
+        Error,
+    >
+    where
+        H: FnMut(&BytesInput) -> ExitKind,
+        F: StateInitializer<TestState>,
+        OF: StateInitializer<TestState>
+            + Feedback<NopEventManager<TestState>, BytesInput, (), TestState>,
+    {
+        let mut corpus = InMemoryCorpus::new();
+        let corpus_id = corpus.add(testcase)?;
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )?;
+        let mut fuzzer = StdFuzzer::new(RandScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let executor =
+            InProcessExecutor::new(harness, tuple_list!(), &mut fuzzer, &mut state, &mut mgr)?;
+        state.set_corpus_id(corpus_id)?;
+
+        Ok((fuzzer, executor, state, mgr))
+    }
+
+    /// Doubles the iterations for each testcase
+    struct ExponentialIterations {
+        next: usize,
+    }
+
+    impl<S> IterationsPolicy<S> for ExponentialIterations {
+        fn iterations(&mut self, _state: &mut S) -> Result<usize, Error> {
+            let iterations = self.next;
+            self.next *= 2;
+            Ok(iterations)
+        }
+    }
+
+    #[test]
+    fn test_custom_iterations_policy() -> Result<(), Error> {
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::new(BytesInput::new(b"abcd".to_vec())),
+            tuple_list!(),
+            tuple_list!(),
+        )?;
+
+        let mut stage = StdMutationalStage::with_iterations_policy(
+            NopMutator::new(MutationResult::Mutated),
+            ExponentialIterations { next: 1 },
+        );
+        for expected in [1, 3, 7] {
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+            assert_eq!(*state.executions(), expected);
+        }
+        assert_eq!(stage.iterations_policy().next, 8);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_adaptive_iterations() -> Result<(), Error> {
+        let mut corpus = InMemoryCorpus::new();
+        let corpus_id = corpus.add(Testcase::new(BytesInput::new(b"abcd".to_vec())))?;
+
+        let mut feedback = tuple_list!();
+        let mut objective = tuple_list!();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )?;
+        state.set_corpus_id(corpus_id)?;
+
+        assert!(AdaptiveIterations::new(nonzero!(8), nonzero!(4)).is_err());
+        let mut policy = AdaptiveIterations::new(nonzero!(4), nonzero!(1024))?;
+        assert!(policy.set_smoothing(0.0).is_err());
+        assert_eq!(policy.last_bound(), None);
+
+        // an unknown testcase starts in the middle
+        let iterations = policy.iterations(&mut state)?;
+        let mut bound = policy.last_bound().unwrap();
+        assert_eq!(bound, policy.bound(1.0));
+        assert!((1..=bound.get()).contains(&iterations));
+
+        // each round adds more than the average so far
+        for round in 0..4 {
+            for _ in 0..4 {
+                state
+                    .corpus_mut()
+                    .add(Testcase::new(BytesInput::new(vec![round])))?;
+            }
+            policy.iterations(&mut state)?;
+            let next = policy.last_bound().unwrap();
+            assert!(next > bound, "{next} <= {bound}");
+            bound = next;
+        }
+        assert!(bound <= nonzero!(1024));
+        let ema = state
+            .corpus()
+            .get(corpus_id)?
+            .borrow()
+            .metadata::<AdaptiveIterationsMetadata>()?
+            .ema();
+        assert!(ema > 3.0);
+
+        // barren rounds decrease the bound again, imported testcases do not count
+        for _ in 0..4 {
+            state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(b"imported".to_vec())))?;
+            *state.imported_mut() += 1;
+            policy.iterations(&mut state)?;
+            let next = policy.last_bound().unwrap();
+            assert!(next < bound, "{next} >= {bound}");
+            bound = next;
+        }
+
+        Ok(())
+    }
+
+    /// A non-reflexive transform, exposing only the first byte of a [`BytesInput`] to the mutator
+    #[derive(Clone, Debug)]
+    struct FirstByte {
+        first: u8,
+        rest: Vec<u8>,
+    }
+
+    impl<S> MutatedTransform<BytesInput, S> for FirstByte {
+        type Post = ();
+
+        fn try_transform_from(base: &mut Testcase<BytesInput>, _state: &S) -> Result<Self, Error> {
+            let bytes = base.input().as_ref().unwrap().bytes();
+            Ok(Self {
+                first: bytes[0],
+                rest: bytes[1..].to_vec(),
+            })
+        }
+
+        fn try_transform_into(self, _state: &S) -> Result<(BytesInput, Self::Post), Error> {
+            let mut bytes = vec![self.first];
+            bytes.extend(self.rest);
+            Ok((BytesInput::new(bytes), ()))
+        }
+    }
+
+    struct IncrementFirstByte;
+
+    impl Named for IncrementFirstByte {
+        fn name(&self) -> &Cow<'static, str> {
+            &Cow::Borrowed("IncrementFirstByte")
+        }
+    }
+
+    impl<S> Mutator<FirstByte, S> for IncrementFirstByte {
+        fn mutate(
+            &mut self,
+            _state: &mut S,
+            input: &mut FirstByte,
+        ) -> Result<MutationResult, Error> {
+            input.first += 1;
+            Ok(MutationResult::Mutated)
+        }
+    }
+
+    #[test]
+    fn test_transforming_mutational_stage() -> Result<(), Error> {
+        let mut executed = vec![];
+        let mut harness = |input: &BytesInput| {
+            executed.push(input.bytes().to_vec());
+            ExitKind::Ok
+        };
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::new(BytesInput::new(b"abcd".to_vec())),
+            tuple_list!(),
+            tuple_list!(),
+        )?;
+
+        let mut stage =
+            StdMutationalStage::<_, _, FirstByte, _, _, _>::transforming_with_iterations_policy(
+                IncrementFirstByte,
+                FixedIterations::new(2),
+            );
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+
+        assert_eq!(executed, [b"bbcd", b"bbcd"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pre_eval() -> Result<(), Error> {
+        let mut executed = vec![];
+        let mut harness = |input: &BytesInput| {
+            executed.push(input.bytes().to_vec());
+            ExitKind::Ok
+        };
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::new(BytesInput::new(b"abcd".to_vec())),
+            tuple_list!(),
+            tuple_list!(),
+        )?;
+
+        let mut calls = 0;
+        let mut stage = StdMutationalStage::with_iterations_policy(
+            NopMutator::new(MutationResult::Mutated),
+            FixedIterations::new(4),
+        )
+        .with_pre_eval(|_input: &BytesInput| {
+            calls += 1;
+            calls % 2 == 0
+        });
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+
+        assert_eq!(*state.executions(), 2);
+        assert_eq!(executed.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_mutation_chain() -> Result<(), Error> {
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+
+        // every input is a solution
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::new(BytesInput::new(b"abcd".to_vec())),
+            tuple_list!(),
+            ConstFeedback::new(true),
+        )?;
+        let corpus_id = state.current_corpus_id()?;
+
+        let mutator = LoggerScheduledMutator::new(StdScheduledMutator::new(tuple_list!(
+            BitFlipMutator::new()
+        )));
+        let mut stage =
+            StdMutationalStage::with_iterations_policy(mutator, FixedIterations::new(1))
+                .record_mutation_chain(true);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+
+        let id = state.solutions().last().unwrap();
+        let solution = state.solutions().get(id)?.borrow();
+        let log = solution.metadata::<LogMutationMetadata>()?;
+        assert!(!log.is_empty());
+        assert!(log.iter().all(|name| name == "BitFlipMutator"));
+        assert_eq!(solution.parent_id(), corpus_id);
+        drop(solution);
+
+        // nothing is recorded by default
+        let mutator = LoggerScheduledMutator::new(StdScheduledMutator::new(tuple_list!(
+            BitFlipMutator::new()
+        )));
+        let mut stage =
+            StdMutationalStage::with_iterations_policy(mutator, FixedIterations::new(1));
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        let id = state.solutions().last().unwrap();
+        assert!(!state
+            .solutions()
+            .get(id)?
+            .borrow()
+            .has_metadata::<LogMutationMetadata>());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_after_restart() -> Result<(), Error> {
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::new(BytesInput::new(b"abcd".to_vec())),
+            tuple_list!(),
+            tuple_list!(),
+        )?;
+
+        let mut stage = StdMutationalStage::with_iterations_policy(
+            NopMutator::new(MutationResult::Mutated),
+            FixedIterations::new(4),
+        );
+
+        // The stage got restarted after crashing in the fourth iteration
+        assert!(stage.should_restart(&mut state)?);
+        RetryCountRestartHelper::set_completed_iterations(&mut state, stage.name(), 3);
+        assert!(stage.should_restart(&mut state)?);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        stage.clear_progress(&mut state)?;
+        assert_eq!(*state.executions(), 1);
+
+        // The next round starts over
+        assert!(stage.should_restart(&mut state)?);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        stage.clear_progress(&mut state)?;
+        assert_eq!(*state.executions(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exec_budget() -> Result<(), Error> {
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::new(BytesInput::new(b"abcd".to_vec())),
+            tuple_list!(),
+            tuple_list!(),
+        )?;
+        state.add_metadata(ExecBudget::new(6));
+
+        // both stages share the same budget
+        let mut first = StdMutationalStage::with_iterations_policy(
+            NopMutator::new(MutationResult::Mutated),
+            FixedIterations::new(4),
+        );
+        let mut second = StdMutationalStage::with_iterations_policy(
+            NopMutator::new(MutationResult::Mutated),
+            FixedIterations::new(4),
+        );
+        first.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert_eq!(state.metadata::<ExecBudget>()?.remaining(), 2);
+        assert!(!state.stop_requested());
+
+        second.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert_eq!(*state.executions(), 6);
+        assert!(state.metadata::<ExecBudget>()?.is_exhausted());
+        assert!(state.stop_requested());
+
+        // the budget survives a restart
+        let mut restored: StdState<_, _, _, _> =
+            postcard::from_bytes(&postcard::to_allocvec(&state).unwrap()).unwrap();
+        restored.discard_stop_request();
+        first.perform(&mut fuzzer, &mut executor, &mut restored, &mut mgr)?;
+        assert_eq!(*restored.executions(), 6);
+        assert!(restored.stop_requested());
+
+        Ok(())
+    }
+
+    /// Records its calls as `(id, post_exec)` in a shared log
+    struct RecordingMutator {
+        id: usize,
+        skip: bool,
+        log: Rc<RefCell<Vec<(usize, bool)>>>,
+    }
+
+    impl Named for RecordingMutator {
+        fn name(&self) -> &Cow<'static, str> {
+            static NAME: Cow<'static, str> = Cow::Borrowed("RecordingMutator");
+            &NAME
+        }
+    }
+
+    impl<I, S> Mutator<I, S> for RecordingMutator {
+        fn mutate(&mut self, _state: &mut S, _input: &mut I) -> Result<MutationResult, Error> {
+            self.log.borrow_mut().push((self.id, false));
+            Ok(if self.skip {
+                MutationResult::Skipped
+            } else {
+                MutationResult::Mutated
+            })
+        }
+
+        fn post_exec(
+            &mut self,
+            _state: &mut S,
+            _new_corpus_id: Option<CorpusId>,
+        ) -> Result<(), Error> {
+            self.log.borrow_mut().push((self.id, true));
+            Ok(())
+        }
+    }
+
+    /// Generates fixed inputs
+    struct FixedMultiMutator;
+
+    impl Named for FixedMultiMutator {
+        fn name(&self) -> &Cow<'static, str> {
+            static NAME: Cow<'static, str> = Cow::Borrowed("FixedMultiMutator");
+            &NAME
+        }
+    }
+
+    impl<S> MultiMutator<BytesInput, S> for FixedMultiMutator {
+        fn multi_mutate(
+            &mut self,
+            _state: &mut S,
+            _input: &BytesInput,
+            _max_count: Option<usize>,
+        ) -> Result<Vec<BytesInput>, Error> {
+            Ok(vec![
+                BytesInput::new(b"a".to_vec()),
+                BytesInput::new(b"crash".to_vec()),
+                BytesInput::new(b"b".to_vec()),
+            ])
+        }
+    }
+
+    #[test]
+    fn test_multi_mutational_stage_stats() -> Result<(), Error> {
+        let mut harness = |input: &BytesInput| {
+            if input.bytes() == b"crash" {
+                ExitKind::Crash
+            } else {
+                ExitKind::Ok
+            }
+        };
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::new(BytesInput::new(b"abcd".to_vec())),
+            ConstFeedback::new(true),
+            CrashFeedback::new(),
+        )?;
+
+        // off by default
+        let mut stage = MultiMutationalStage::new(FixedMultiMutator);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert!(stage.stats().is_none());
+
+        let mut stage = MultiMutationalStage::new(FixedMultiMutator).report_stats(Duration::ZERO);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert_eq!(
+            stage.stats(),
+            Some(&MultiMutationalStats {
+                generated: 6,
+                corpus_additions: 4,
+                objectives: 2,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_robin_mutational_stage() -> Result<(), Error> {
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::new(BytesInput::new(b"abcd".to_vec())),
+            tuple_list!(),
+            tuple_list!(),
+        )?;
+
+        let log = Rc::new(RefCell::new(vec![]));
+        let mutator = |id, skip| RecordingMutator {
+            id,
+            skip,
+            log: log.clone(),
+        };
+        // the second mutator always skips
+        let mut stage = RoundRobinMutationalStage::with_iterations_policy(
+            tuple_list!(mutator(0, false), mutator(1, true), mutator(2, false)),
+            FixedIterations::new(5),
+        )?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+
+        // each mutator is used in turn, and only the one that mutated the input gets its `post_exec`
+        assert_eq!(
+            *log.borrow(),
+            [
+                (0, false),
+                (0, true),
+                (1, false),
+                (2, false),
+                (2, true),
+                (0, false),
+                (0, true),
+                (1, false)
+            ]
+        );
+        assert_eq!(*state.executions(), 3);
+
+        // the inputs filtered by `pre_eval` are not executed
+        log.borrow_mut().clear();
+        let mut stage = RoundRobinMutationalStage::with_iterations_policy(
+            tuple_list!(mutator(0, false), mutator(1, false)),
+            FixedIterations::new(2),
+        )?
+        .with_pre_eval(|_input: &BytesInput| false);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert_eq!(*log.borrow(), [(0, false), (1, false)]);
+        assert_eq!(*state.executions(), 3);
+
+        // there has to be a mutator
+        assert!(RoundRobinMutationalStage::<(), (), BytesInput, (), (), _>::transforming_with_iterations_policy(
+            (),
+            FixedIterations::new(1)
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_testcase_without_input() -> Result<(), Error> {
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+
+        // e.g., a corrupt on-disk corpus entry
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::default(),
+            tuple_list!(),
+            tuple_list!(),
+        )?;
+
+        let mut testcase = state.current_testcase_mut()?;
+        assert!(
+            <BytesInput as MutatedTransform<BytesInput, _>>::try_transform_from(
+                &mut testcase,
+                &state
+            )
+            .is_err()
+        );
+        drop(testcase);
+
+        let mut stage = StdMutationalStage::with_iterations_policy(
+            NopMutator::new(MutationResult::Mutated),
+            FixedIterations::new(4),
+        );
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert_eq!(*state.executions(), 0);
+
+        Ok(())
+    }
+
+    /// Runs the inputs in batches, crashing on the third execution
+    struct BatchingExecutor<S> {
+        batch_sizes: Vec<usize>,
+        observers: (),
+        phantom: PhantomData<S>,
+    }
+
+    impl<S> UsesState for BatchingExecutor<S>
+    where
+        S: State,
+    {
+        type State = S;
+    }
+
+    impl<S> HasObservers for BatchingExecutor<S> {
+        type Observers = ();
+
+        fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+            RefIndexable::from(&self.observers)
+        }
+
+        fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+            RefIndexable::from(&mut self.observers)
+        }
+    }
+
+    impl<EM, S, Z> Executor<EM, Z> for BatchingExecutor<S>
+    where
+        EM: UsesState<State = S>,
+        S: State + HasExecutions,
+        Z: UsesState<State = S>,
+    {
+        fn run_target(
+            &mut self,
+            fuzzer: &mut Z,
+            state: &mut S,
+            mgr: &mut EM,
+            input: &S::Input,
+        ) -> Result<ExitKind, Error> {
+            Ok(self.run_batch(fuzzer, state, mgr, core::slice::from_ref(input))?[0])
+        }
+    }
+
+    impl<EM, S, Z> BatchExecutor<EM, Z> for BatchingExecutor<S>
+    where
+        EM: UsesState<State = S>,
+        S: State + HasExecutions,
+        Z: UsesState<State = S>,
+    {
+        fn run_batch(
+            &mut self,
+            _fuzzer: &mut Z,
+            state: &mut S,
+            _mgr: &mut EM,
+            inputs: &[S::Input],
+        ) -> Result<Vec<ExitKind>, Error> {
+            self.batch_sizes.push(inputs.len());
+            let mut exit_kinds = vec![];
+            for _ in inputs {
+                *state.executions_mut() += 1;
+                exit_kinds.push(if *state.executions() == 3 {
+                    ExitKind::Crash
+                } else {
+                    ExitKind::Ok
+                });
+            }
+            Ok(exit_kinds)
+        }
+
+        fn load_batch_observations(&mut self, _state: &mut S, _idx: usize) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_perform_mutational_batched() -> Result<(), Error> {
+        let mut corpus = InMemoryCorpus::new();
+        let corpus_id = corpus.add(Testcase::new(BytesInput::new(b"abcd".to_vec())))?;
+
+        let mut feedback = tuple_list!();
+        let mut objective = CrashFeedback::new();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )?;
+        let mut fuzzer = StdFuzzer::new(RandScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut executor = BatchingExecutor {
+            batch_sizes: vec![],
+            observers: (),
+            phantom: PhantomData,
+        };
+        state.set_corpus_id(corpus_id)?;
+
+        let mut stage = StdMutationalStage::with_iterations_policy(
+            NopMutator::new(MutationResult::Mutated),
+            FixedIterations::new(5),
+        );
+        stage.perform_mutational_batched(
+            &mut fuzzer,
+            &mut executor,
+            &mut state,
+            &mut mgr,
+            nonzero!(2),
+        )?;
+
+        assert_eq!(executor.batch_sizes, [2, 2, 1]);
+        assert_eq!(*state.executions(), 5);
+        assert_eq!(state.solutions().count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_max_iterations() -> Result<(), Error> {
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+
+        let (mut fuzzer, mut executor, mut state, mut mgr) = fuzz_setup(
+            &mut harness,
+            Testcase::new(BytesInput::new(b"abcd".to_vec())),
+            tuple_list!(),
+            tuple_list!(),
+        )?;
+
+        let mut stage = StdMutationalStage::new(NopMutator::new(MutationResult::Mutated));
+        let name = stage.name().clone();
+        assert_eq!(
+            stage.max_iterations().get(),
+            DEFAULT_MUTATIONAL_MAX_ITERATIONS
+        );
+
+        stage.set_max_iterations(nonzero!(1));
+        assert_eq!(stage.max_iterations().get(), 1);
+        assert_eq!(*stage.name(), name);
+
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert_eq!(*state.executions(), 1);
+
+        Ok(())
+    }
 }
@@ -74,13 +74,30 @@ where
 
     /// Gets the number of iterations as a random number
     #[allow(clippy::cast_sign_loss)]
-    fn iterations(&self, state: &mut Self::State) -> Result<usize, Error> {
+    fn iterations(&mut self, state: &mut Self::State) -> Result<usize, Error> {
         // Update handicap
         let mut testcase = state.current_testcase_mut()?;
         let score = F::compute(state, &mut testcase)? as usize;
 
         Ok(score)
     }
+
+    #[inline]
+    fn completed_iterations(&mut self, state: &mut Self::State) -> Result<usize, Error> {
+        Ok(RetryCountRestartHelper::completed_iterations(
+            state, &self.name,
+        ))
+    }
+
+    #[inline]
+    fn set_completed_iterations(
+        &mut self,
+        state: &mut Self::State,
+        completed_iterations: usize,
+    ) -> Result<(), Error> {
+        RetryCountRestartHelper::set_completed_iterations(state, &self.name, completed_iterations);
+        Ok(())
+    }
 }
 
 impl<E, F, EM, I, M, Z> Stage<E, EM, Z> for PowerMutationalStage<E, F, EM, I, M, Z>
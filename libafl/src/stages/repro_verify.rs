@@ -0,0 +1,339 @@
+//! The [`ReproVerifyStage`] re-executes new solutions and drops the ones that do not reproduce reliably.
+//! Note: the solutions are re-executed, so this will usually NOT work with in process executors,
+//! as reproducing a crash takes down the fuzzer.
+
+use alloc::{format, vec::Vec};
+use core::marker::PhantomData;
+
+use libafl_bolts::impl_serdeany;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId},
+    executors::{ExitKind, HasObservers},
+    observers::ObserversTuple,
+    stages::Stage,
+    state::{HasCorpus, HasSolutions, UsesState},
+    Error, ExecutesInput, HasMetadata,
+};
+
+/// The progress of the [`ReproVerifyStage`]
+#[cfg_attr(
+    any(not(feature = "serdeany_autoreg"), miri),
+    allow(clippy::unsafe_derive_deserialize)
+)] // for SerdeAny
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ReproVerifyMetadata {
+    /// The solutions from this id on were not verified yet
+    next_id: usize,
+    /// The number of solutions dropped as flaky
+    dropped: usize,
+}
+
+impl_serdeany!(ReproVerifyMetadata);
+
+impl ReproVerifyMetadata {
+    /// The number of solutions dropped as flaky so far
+    #[must_use]
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+/// Re-executes each new solution `attempts` times, and removes it from the solutions again
+/// if it does not end with the same [`ExitKind`] for at least `required_repro_ratio` of the attempts.
+///
+/// The expected [`ExitKind`] is the one stored as metadata of the solution's testcase, if any,
+/// else the most frequent non-[`ExitKind::Ok`] one of the attempts.
+/// Timeouts are inherently noisier, so they are verified against the stricter
+/// [`ReproVerifyStage::with_timeout_repro_ratio`], by default all attempts have to time out.
+///
+/// Each solution is only verified once, even if the fuzzer restarts while verifying it.
+/// Note: Will usually NOT work with in process executors, as the re-executions may crash the fuzzer.
+#[derive(Debug)]
+pub struct ReproVerifyStage<E, EM, Z> {
+    attempts: usize,
+    required_repro_ratio: f64,
+    timeout_repro_ratio: f64,
+    phantom: PhantomData<(E, EM, Z)>,
+}
+
+impl<E, EM, Z> UsesState for ReproVerifyStage<E, EM, Z>
+where
+    Z: UsesState,
+{
+    type State = Z::State;
+}
+
+impl<E, EM, Z> ReproVerifyStage<E, EM, Z> {
+    /// Creates a new [`ReproVerifyStage`], running each new solution `attempts` times,
+    /// and keeping it if at least `required_repro_ratio` of them, in `(0, 1]`, reproduce it.
+    pub fn new(attempts: usize, required_repro_ratio: f64) -> Result<Self, Error> {
+        if attempts == 0 {
+            return Err(Error::illegal_argument(
+                "ReproVerifyStage needs at least one attempt",
+            ));
+        }
+        check_ratio(required_repro_ratio)?;
+        Ok(Self {
+            attempts,
+            required_repro_ratio,
+            timeout_repro_ratio: 1.0,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Sets the ratio of the attempts, in `(0, 1]`, that have to time out again to keep a timeout.
+    /// Ratios below the `required_repro_ratio` are raised to it, so timeouts are never verified less strictly.
+    pub fn with_timeout_repro_ratio(mut self, timeout_repro_ratio: f64) -> Result<Self, Error> {
+        check_ratio(timeout_repro_ratio)?;
+        self.timeout_repro_ratio = timeout_repro_ratio;
+        Ok(self)
+    }
+
+    /// The number of times each new solution is re-executed
+    #[must_use]
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// The ratio of the attempts that have to reproduce a solution that is not a timeout
+    #[must_use]
+    pub fn required_repro_ratio(&self) -> f64 {
+        self.required_repro_ratio
+    }
+
+    /// The ratio of the attempts that have to time out again to keep a timeout
+    #[must_use]
+    pub fn timeout_repro_ratio(&self) -> f64 {
+        self.timeout_repro_ratio.max(self.required_repro_ratio)
+    }
+
+    /// Returns if `reproduced` of the attempts reproducing a solution of `kind` are enough to keep it
+    #[allow(clippy::cast_precision_loss)]
+    fn reproduces(&self, kind: ExitKind, reproduced: usize) -> bool {
+        let ratio = if kind == ExitKind::Timeout {
+            self.timeout_repro_ratio()
+        } else {
+            self.required_repro_ratio
+        };
+        reproduced as f64 >= ratio * self.attempts as f64
+    }
+}
+
+fn check_ratio(ratio: f64) -> Result<(), Error> {
+    if ratio > 0.0 && ratio <= 1.0 {
+        Ok(())
+    } else {
+        Err(Error::illegal_argument(format!(
+            "The repro ratio has to be in (0, 1], got {ratio}"
+        )))
+    }
+}
+
+/// The [`ExitKind`] a solution is expected to reproduce: the `recorded` one, if any,
+/// else the most frequent non-[`ExitKind::Ok`] one of the `kinds` of the attempts
+fn expected_exit_kind(recorded: Option<ExitKind>, kinds: &[ExitKind]) -> Option<ExitKind> {
+    if recorded.is_some() {
+        return recorded;
+    }
+    let mut counts: Vec<(ExitKind, usize)> = vec![];
+    for kind in kinds.iter().filter(|kind| **kind != ExitKind::Ok) {
+        match counts.iter_mut().find(|(k, _)| k == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*kind, 1)),
+        }
+    }
+    // the first of the most frequent ones
+    counts
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(kind, _)| kind)
+}
+
+impl<E, EM, Z> Stage<E, EM, Z> for ReproVerifyStage<E, EM, Z>
+where
+    E: HasObservers + UsesState<State = Self::State>,
+    E::Observers: ObserversTuple<Self::Input, Self::State>,
+    EM: UsesState<State = Self::State>,
+    Z: ExecutesInput<E, EM>,
+    Z::State: HasCorpus + HasSolutions + HasMetadata,
+    Self::Input: Clone,
+    <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
+    <<Self as UsesState>::State as HasSolutions>::Solutions: Corpus<Input = Self::Input>, //delete me
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let next_id = state
+            .metadata_or_insert_with(ReproVerifyMetadata::default)
+            .next_id;
+        let ids: Vec<CorpusId> = state
+            .solutions()
+            .ids()
+            .filter(|id| id.0 >= next_id)
+            .collect();
+
+        for id in ids {
+            // mark it as verified first, so a crash while verifying does not verify it again forever
+            state.metadata_mut::<ReproVerifyMetadata>()?.next_id = id.0 + 1;
+
+            let input = state.solutions().cloned_input_for_id(id)?;
+            let recorded = state
+                .solutions()
+                .get(id)?
+                .borrow()
+                .metadata::<ExitKind>()
+                .ok()
+                .copied();
+
+            let mut kinds = Vec::with_capacity(self.attempts);
+            for _ in 0..self.attempts {
+                kinds.push(fuzzer.execute_input(state, executor, manager, &input)?);
+            }
+
+            let keep = expected_exit_kind(recorded, &kinds).is_some_and(|expected| {
+                let reproduced = kinds.iter().filter(|kind| **kind == expected).count();
+                self.reproduces(expected, reproduced)
+            });
+            if !keep {
+                log::info!("Dropping solution {id}, it did not reproduce reliably: {kinds:?}");
+                state.solutions_mut().remove(id)?;
+                state.metadata_mut::<ReproVerifyMetadata>()?.dropped += 1;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn should_restart(&mut self, _state: &mut Self::State) -> Result<bool, Error> {
+        // The progress is kept in the `ReproVerifyMetadata`
+        Ok(true)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, _state: &mut Self::State) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::cell::Cell;
+
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list};
+
+    use super::{expected_exit_kind, ReproVerifyMetadata, ReproVerifyStage};
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        feedbacks::ConstFeedback,
+        fuzzer::StdFuzzer,
+        inputs::{BytesInput, HasMutatorBytes},
+        schedulers::RandScheduler,
+        stages::Stage,
+        state::{HasSolutions, StdState},
+        Error, HasMetadata,
+    };
+
+    /// The bytes of the inputs of all solutions
+    fn solutions<S>(state: &S) -> Result<Vec<Vec<u8>>, Error>
+    where
+        S: HasSolutions,
+        S::Solutions: Corpus<Input = BytesInput>,
+    {
+        state
+            .solutions()
+            .ids()
+            .map(|id| Ok(state.solutions().cloned_input_for_id(id)?.bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_expected_exit_kind() {
+        let kinds = [
+            ExitKind::Ok,
+            ExitKind::Timeout,
+            ExitKind::Crash,
+            ExitKind::Crash,
+        ];
+        assert_eq!(expected_exit_kind(None, &kinds), Some(ExitKind::Crash));
+        assert_eq!(
+            expected_exit_kind(Some(ExitKind::Oom), &kinds),
+            Some(ExitKind::Oom)
+        );
+        // ties go to the first one
+        assert_eq!(
+            expected_exit_kind(None, &kinds[..3]),
+            Some(ExitKind::Timeout)
+        );
+        assert_eq!(expected_exit_kind(None, &[ExitKind::Ok]), None);
+    }
+
+    #[test]
+    fn test_repro_verify_stage() -> Result<(), Error> {
+        // "stable" always crashes, "flaky" every other run, and "timeout" times out 3 of 4 runs
+        let runs = Cell::new(0_usize);
+        let mut harness = |input: &BytesInput| {
+            runs.set(runs.get() + 1);
+            match input.bytes() {
+                b"stable" => ExitKind::Crash,
+                b"flaky" if runs.get() & 1 == 0 => ExitKind::Crash,
+                b"timeout" if runs.get() & 3 != 0 => ExitKind::Timeout,
+                _ => ExitKind::Ok,
+            }
+        };
+
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )?;
+        let mut fuzzer = StdFuzzer::new(RandScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )?;
+
+        assert!(ReproVerifyStage::<(), (), ()>::new(0, 0.5).is_err());
+        assert!(ReproVerifyStage::<(), (), ()>::new(4, 1.5).is_err());
+        let mut stage = ReproVerifyStage::new(4, 0.5)?;
+
+        for input in [&b"stable"[..], b"flaky", b"timeout", b"ok"] {
+            state
+                .solutions_mut()
+                .add(Testcase::new(BytesInput::new(input.to_vec())))?;
+        }
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert_eq!(solutions(&state)?, [b"stable".to_vec(), b"flaky".to_vec()]);
+        assert_eq!(state.metadata::<ReproVerifyMetadata>()?.dropped(), 2);
+
+        // only new solutions are verified
+        let runs_before = runs.get();
+        state
+            .solutions_mut()
+            .add(Testcase::new(BytesInput::new(b"timeout".to_vec())))?;
+        let mut stage = ReproVerifyStage::new(4, 0.5)?.with_timeout_repro_ratio(0.75)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+        assert_eq!(runs.get(), runs_before + 4);
+        assert_eq!(solutions(&state)?.len(), 3);
+        assert_eq!(state.metadata::<ReproVerifyMetadata>()?.dropped(), 2);
+
+        Ok(())
+    }
+}
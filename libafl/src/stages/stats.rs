@@ -1,29 +1,119 @@
 //! Stage to compute/report minimal AFL-like stats
 
 #[cfg(feature = "std")]
-use alloc::{borrow::Cow, string::ToString};
-use core::{marker::PhantomData, time::Duration};
+use alloc::string::{String, ToString};
+use alloc::{borrow::Cow, rc::Rc};
+use core::{
+    cell::RefCell,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    time::Duration,
+};
+#[cfg(all(feature = "std", unix))]
+use std::os::unix::net::UnixStream;
+#[cfg(feature = "std")]
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
 
-use libafl_bolts::current_time;
+use libafl_bolts::{current_time, HasLen, Named};
 #[cfg(feature = "std")]
 use serde_json::json;
 
+#[cfg(all(feature = "std", feature = "introspection"))]
+use crate::monitors::{ClientPerfMonitor, PerfFeature};
+#[cfg(feature = "introspection")]
+use crate::state::HasClientPerfMonitor;
 use crate::{
     corpus::{Corpus, HasCurrentCorpusId},
     events::EventFirer,
+    inputs::UsesInput,
+    observers::{HarnessTimingMetadata, HarnessTimingObserver},
     schedulers::minimizer::IsFavoredMetadata,
-    stages::Stage,
-    state::{HasCorpus, HasImported, UsesState},
-    Error, HasMetadata,
+    stages::{AbandonedTestcaseCount, Stage},
+    state::{HasCorpus, HasExecutions, HasImported, HasSolutions, HasStartTime, UsesState},
+    Error, HasMetadata, HasNamedMetadata,
 };
 #[cfg(feature = "std")]
 use crate::{
-    events::Event,
+    events::{Event, ImportSourcesMetadata},
     monitors::{AggregatorOps, UserStats, UserStatsValue},
 };
 
-/// The [`StatsStage`] is a simple stage that computes and reports some stats.
+/// How the [`StatsStage`] aggregates each of its counts across clients, see [`AggregatorOps`].
+///
+/// Not every count is safe to aggregate: testcases imported from other clients end up in the
+/// corpus of every client, so summing up `corpus_count`, `pending`, `pend_fav` or `imported`
+/// counts the same testcase once per client. Only `own_finds` are disjoint between clients and
+/// can be summed up safely; for the others, [`AggregatorOps::Max`] or [`AggregatorOps::Avg`]
+/// give a more meaningful picture of the fleet.
+///
+/// The coverage density is not computed by the [`StatsStage`], it is already reported and
+/// averaged across clients by the map feedbacks.
+#[cfg(feature = "std")]
 #[derive(Debug, Clone)]
+pub struct StatsAggregation {
+    /// The aggregation for the number of testcases in the corpus
+    pub corpus_count: AggregatorOps,
+    /// The aggregation for the number of testcases that have not been fuzzed yet
+    pub pending: AggregatorOps,
+    /// The aggregation for the number of favored testcases that have not been fuzzed yet
+    pub pend_fav: AggregatorOps,
+    /// The aggregation for the number of testcases found by the client itself
+    pub own_finds: AggregatorOps,
+    /// The aggregation for the number of testcases imported from other clients
+    pub imported: AggregatorOps,
+}
+
+#[cfg(feature = "std")]
+impl Default for StatsAggregation {
+    /// Reports every count per client, without aggregation
+    fn default() -> Self {
+        Self {
+            corpus_count: AggregatorOps::None,
+            pending: AggregatorOps::None,
+            pend_fav: AggregatorOps::None,
+            own_finds: AggregatorOps::None,
+            imported: AggregatorOps::None,
+        }
+    }
+}
+
+/// A threshold for [`StatsStage::set_import_burst_threshold`] that suits the initial sync of a few clients
+pub const DEFAULT_IMPORT_BURST_THRESHOLD: usize = 16;
+
+/// The callback of [`StatsStage::on_corpus_growth`], shared by the clones of the stage
+type CorpusGrowthCallback = Rc<RefCell<dyn FnMut(usize)>>;
+
+/// The stats last computed by a [`StatsStage`], see [`StatsStage::snapshot`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AflStatsSnapshot {
+    /// The number of testcases in the corpus
+    pub corpus_count: usize,
+    /// The number of testcases that have not been fuzzed yet
+    pub pending: usize,
+    /// The number of favored testcases that have not been fuzzed yet
+    pub pend_fav: usize,
+    /// The number of testcases found by the client itself
+    pub own_finds: usize,
+    /// The number of testcases imported from other clients
+    pub imported: usize,
+    /// The average input size of the testcases fuzzed so far, see [`StatsStage::avg_corpus_size_bytes`]
+    pub avg_corpus_size_bytes: usize,
+    /// The seconds since the client last found a testcase or solution, see [`StatsStage::last_find_time`]
+    pub secs_since_last_find: u64,
+}
+
+/// The [`StatsStage`] is a simple stage that computes and reports some stats.
+///
+/// Stats that need more from the state are reported by the stages wrapping it, see
+/// [`InputSizeStatsStage`], [`HarnessTimingStatsStage`] and, with the `introspection` feature, `IntrospectionStatsStage`.
+///
+/// Clones share the callback of [`StatsStage::on_corpus_growth`] and the clock of [`StatsStage::with_clock`].
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone)]
 pub struct StatsStage<E, EM, Z> {
     // the number of testcases that have been fuzzed
     has_fuzzed_size: usize,
@@ -33,14 +123,111 @@ pub struct StatsStage<E, EM, Z> {
     own_finds_size: usize,
     // the number of testcases imported by other fuzzers
     imported_size: usize,
+    // the summed up input sizes of the testcases that have been fuzzed, see `InputSizeStatsStage`
+    fuzzed_input_bytes: usize,
+    // the number of testcases whose input sizes are summed up in `fuzzed_input_bytes`
+    sized_testcases: usize,
+    // the corpus size at the last computation
+    corpus_size: usize,
+    // the number of pending testcases at the last computation
+    pending_size: usize,
+    // the number of pending favored testcases at the last computation
+    pend_favored_size: usize,
     // the last time that we report all stats
     last_report_time: Duration,
     // the interval that we report all stats
     stats_report_interval: Duration,
+    // whether to report even if there is no new testcase to account for (e.g., during imports)
+    report_without_testcase: bool,
+    // whether to report only if a count changed, as soon as `stats_report_interval` elapsed
+    report_on_change: bool,
+    // the counts of the last report, to detect changes
+    last_reported_counts: Option<[usize; 5]>,
+    // whether to report at all
+    enabled: bool,
+    // the number of imports since the last computation from which on we don't report, if any
+    import_burst_threshold: Option<usize>,
+    // the number of imported testcases at the last computation
+    last_imported: usize,
+    // the time of the last increase of own finds or solutions, `None` before the first computation
+    last_find_time: Option<Duration>,
+    // the number of own finds and solutions at the last computation
+    last_finds: usize,
+    // the seconds since `last_find_time` at the last computation
+    secs_since_last_find: u64,
+    // the file to which we append `plot_data` rows, if any
+    #[cfg(feature = "std")]
+    plot_file_path: Option<PathBuf>,
+    // how to aggregate the single stats across clients, if they should be reported separately
+    #[cfg(feature = "std")]
+    aggregation: Option<StatsAggregation>,
+    // the Unix domain socket to which we write the stats as JSON lines, if any
+    #[cfg(all(feature = "std", unix))]
+    socket: Option<StatsSocket>,
+    // whether to write a status line to stderr, rewritten in place if stderr is a terminal
+    #[cfg(feature = "std")]
+    status_line: Option<StatusLineMode>,
+    // the stats added by the wrapping stages for the current computation, see `add_to_report`
+    #[cfg(feature = "std")]
+    extra_stats: serde_json::Map<String, serde_json::Value>,
+    // whether the last computation was reported
+    reported: bool,
+    // the largest corpus size seen so far
+    max_corpus_size: usize,
+    // called with the new size whenever the corpus grows beyond `max_corpus_size`
+    on_corpus_growth: Option<CorpusGrowthCallback>,
+    // the time source, `current_time` if not set
+    clock: Option<Rc<dyn Fn() -> Duration>>,
+    // the start of the campaign in the time of `clock`, taken on the first computation
+    start_time: Option<Duration>,
 
     phantom: PhantomData<(E, EM, Z)>,
 }
 
+impl<E, EM, Z> Debug for StatsStage<E, EM, Z> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("StatsStage");
+        debug
+            .field("has_fuzzed_size", &self.has_fuzzed_size)
+            .field("is_favored_size", &self.is_favored_size)
+            .field("own_finds_size", &self.own_finds_size)
+            .field("imported_size", &self.imported_size)
+            .field("fuzzed_input_bytes", &self.fuzzed_input_bytes)
+            .field("sized_testcases", &self.sized_testcases)
+            .field("corpus_size", &self.corpus_size)
+            .field("pending_size", &self.pending_size)
+            .field("pend_favored_size", &self.pend_favored_size)
+            .field("last_report_time", &self.last_report_time)
+            .field("stats_report_interval", &self.stats_report_interval)
+            .field("report_without_testcase", &self.report_without_testcase)
+            .field("report_on_change", &self.report_on_change)
+            .field("last_reported_counts", &self.last_reported_counts)
+            .field("enabled", &self.enabled)
+            .field("import_burst_threshold", &self.import_burst_threshold)
+            .field("last_imported", &self.last_imported)
+            .field("last_find_time", &self.last_find_time)
+            .field("last_finds", &self.last_finds)
+            .field("secs_since_last_find", &self.secs_since_last_find);
+        #[cfg(feature = "std")]
+        debug
+            .field("plot_file_path", &self.plot_file_path)
+            .field("aggregation", &self.aggregation);
+        #[cfg(all(feature = "std", unix))]
+        debug.field("socket", &self.socket);
+        #[cfg(feature = "std")]
+        debug
+            .field("status_line", &self.status_line)
+            .field("extra_stats", &self.extra_stats);
+        debug
+            .field("reported", &self.reported)
+            .field("max_corpus_size", &self.max_corpus_size)
+            .field("on_corpus_growth", &self.on_corpus_growth.is_some())
+            .field("clock", &self.clock.is_some())
+            .field("start_time", &self.start_time)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<E, EM, Z> UsesState for StatsStage<E, EM, Z>
 where
     E: UsesState,
@@ -53,7 +240,8 @@ where
     E: UsesState,
     EM: EventFirer<State = Self::State>,
     Z: UsesState<State = Self::State>,
-    Self::State: HasImported + HasCorpus + HasMetadata,
+    E::State: HasImported + HasCorpus + HasMetadata + HasExecutions + HasSolutions + HasStartTime,
+    <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
 {
     fn perform(
         &mut self,
@@ -62,7 +250,11 @@ where
         state: &mut Self::State,
         _manager: &mut EM,
     ) -> Result<(), Error> {
-        self.update_and_report_afl_stats(state, _manager)
+        let res = self.update_and_report_afl_stats(state, _manager);
+        // The stats of the wrapping stages are only meant for this computation
+        #[cfg(feature = "std")]
+        self.extra_stats.clear();
+        res
     }
 
     #[inline]
@@ -87,45 +279,138 @@ impl<E, EM, Z> StatsStage<E, EM, Z> {
     where
         E: UsesState,
         EM: EventFirer<State = E::State>,
-        <Self as UsesState>::State: HasCorpus + HasImported,
+        E::State:
+            HasCorpus + HasImported + HasExecutions + HasSolutions + HasStartTime + HasMetadata,
+        <E::State as HasCorpus>::Corpus: Corpus<Input = <E::State as UsesInput>::Input>, //delete me
     {
-        let Some(corpus_id) = state.current_corpus_id()? else {
-            return Err(Error::illegal_state(
-                "state is not currently processing a corpus index",
-            ));
-        };
+        self.reported = false;
+
+        // Growth is reported right away, independent of the interval
+        let corpus_size = state.corpus().count();
+        if corpus_size > self.max_corpus_size {
+            self.max_corpus_size = corpus_size;
+            if let Some(on_corpus_growth) = &self.on_corpus_growth {
+                (on_corpus_growth.borrow_mut())(corpus_size);
+            }
+        }
 
         // Report your stats every `STATS_REPORT_INTERVAL`
         // compute pending, pending_favored, imported, own_finds
-        {
-            let testcase = state.corpus().get(corpus_id)?.borrow();
-            if testcase.scheduled_count() == 0 {
-                self.has_fuzzed_size += 1;
-                if testcase.has_metadata::<IsFavoredMetadata>() {
-                    self.is_favored_size += 1;
+        let is_new_testcase = match state.current_corpus_id()? {
+            Some(corpus_id) => {
+                let testcase = state.corpus().get(corpus_id)?.borrow();
+                if testcase.scheduled_count() == 0 {
+                    self.has_fuzzed_size += 1;
+                    if testcase.has_metadata::<IsFavoredMetadata>() {
+                        self.is_favored_size += 1;
+                    }
+                    true
+                } else {
+                    false
                 }
-            } else {
-                return Ok(());
             }
+            None if self.report_without_testcase => false,
+            None => {
+                return Err(Error::illegal_state(
+                    "state is not currently processing a corpus index",
+                ));
+            }
+        };
+
+        if !is_new_testcase && !self.report_without_testcase {
+            return Ok(());
         }
 
-        let corpus_size = state.corpus().count();
-        let pending_size = corpus_size - self.has_fuzzed_size;
-        let pend_favored_size = corpus_size - self.is_favored_size;
+        // The counters are monotonic, while testcases may get removed from the corpus
+        // (e.g., by a minimizer), so they may exceed the current corpus size.
+        // They are kept for `snapshot` even if this computation is not reported.
+        let pending_size = corpus_size.saturating_sub(self.has_fuzzed_size);
+        let pend_favored_size = corpus_size.saturating_sub(self.is_favored_size);
+        self.corpus_size = corpus_size;
+        self.pending_size = pending_size;
+        self.pend_favored_size = pend_favored_size;
         self.imported_size = *state.imported();
-        self.own_finds_size = corpus_size - self.imported_size;
+        self.own_finds_size = corpus_size.saturating_sub(self.imported_size);
+
+        // A burst of imports means we are still syncing, e.g., with the other clients at startup
+        let importing = self.import_burst_threshold.is_some_and(|threshold| {
+            self.imported_size.saturating_sub(self.last_imported) >= threshold
+        });
+        self.last_imported = self.imported_size;
+
+        // The average is kept incrementally over the fuzzed testcases, see `avg_corpus_size_bytes`
+        let avg_corpus_size_bytes = self.avg_corpus_size_bytes();
+        let favored_ratio = self.favored_ratio();
+        let abandoned_testcases = AbandonedTestcaseCount::count_in(state);
+
+        let cur = self.now();
+        // All times are taken from the same clock: the state's start time is wall-clock time,
+        // so a custom clock starts the campaign at its first reading instead
+        let start_time = *self.start_time.get_or_insert(if self.clock.is_some() {
+            cur
+        } else {
+            *state.start_time()
+        });
+
+        // The first computation starts counting at the start of the campaign, not at the epoch
+        let finds = self.own_finds_size + state.solutions().count();
+        let last_find_time = match self.last_find_time {
+            Some(_) if finds > self.last_finds => cur,
+            Some(last_find_time) => last_find_time,
+            None => start_time,
+        };
+        self.last_find_time = Some(last_find_time);
+        self.last_finds = finds;
+        let secs_since_last_find = cur
+            .checked_sub(last_find_time)
+            .unwrap_or_default()
+            .as_secs();
+        self.secs_since_last_find = secs_since_last_find;
+        let since_last_report = cur.checked_sub(self.last_report_time).unwrap_or_default();
+        let counts = [
+            corpus_size,
+            pending_size,
+            pend_favored_size,
+            self.own_finds_size,
+            self.imported_size,
+        ];
 
-        let cur = current_time();
+        let should_report = self.enabled
+            && !importing
+            && if self.report_on_change {
+                self.last_reported_counts != Some(counts)
+                    && since_last_report >= self.stats_report_interval
+            } else {
+                since_last_report > self.stats_report_interval
+            };
+
+        if should_report {
+            let executions = *state.executions();
+            let elapsed = cur.checked_sub(start_time).unwrap_or_default();
+            let execs_per_sec = execs_per_sec(executions, elapsed);
 
-        if cur.checked_sub(self.last_report_time).unwrap_or_default() > self.stats_report_interval {
             #[cfg(feature = "std")]
             {
-                let json = json!({
+                let mut json = json!({
                         "pending":pending_size,
                         "pend_fav":pend_favored_size,
                         "own_finds":self.own_finds_size,
                         "imported":self.imported_size,
+                        "execs_done":executions,
+                        "execs_per_sec":execs_per_sec,
+                        "avg_corpus_size_bytes":avg_corpus_size_bytes,
+                        "secs_since_last_find":secs_since_last_find,
+                        "favored_ratio":favored_ratio,
+                        "abandoned_testcases":abandoned_testcases,
+                        "imported_by_client":Self::imported_by_client(state, self.imported_size),
                 });
+                if let Some(json) = json.as_object_mut() {
+                    json.append(&mut self.extra_stats);
+                }
+                #[cfg(unix)]
+                if let Some(socket) = &mut self.socket {
+                    socket.send(&json.to_string());
+                }
                 _manager.fire(
                     state,
                     Event::UpdateUserStats {
@@ -137,16 +422,74 @@ impl<E, EM, Z> StatsStage<E, EM, Z> {
                         phantom: PhantomData,
                     },
                 )?;
+
+                if let Some(aggregation) = &self.aggregation {
+                    for (name, count, op) in [
+                        ("corpus_count", corpus_size, &aggregation.corpus_count),
+                        ("pending", pending_size, &aggregation.pending),
+                        ("pend_fav", pend_favored_size, &aggregation.pend_fav),
+                        ("own_finds", self.own_finds_size, &aggregation.own_finds),
+                        ("imported", self.imported_size, &aggregation.imported),
+                    ] {
+                        _manager.fire(
+                            state,
+                            Event::UpdateUserStats {
+                                name: Cow::from(name),
+                                value: UserStats::new(
+                                    UserStatsValue::Number(count as u64),
+                                    op.clone(),
+                                ),
+                                phantom: PhantomData,
+                            },
+                        )?;
+                    }
+                }
+
+                if let Some(mode) = self.status_line {
+                    let line = status_line(
+                        corpus_size,
+                        state.solutions().count(),
+                        execs_per_sec,
+                        secs_since_last_find,
+                    );
+                    if let Err(err) = mode.write(&mut std::io::stderr().lock(), &line) {
+                        log::debug!("Could not write the status line: {err}");
+                    }
+                }
+
+                if let Some(plot_file_path) = &self.plot_file_path {
+                    Self::write_plot_data(
+                        plot_file_path,
+                        &PlotDataRow {
+                            relative_time: elapsed.as_secs(),
+                            cur_item: state.current_corpus_id()?.map_or(0, |id| id.0),
+                            corpus_count: corpus_size,
+                            pending_total: pending_size,
+                            pending_favs: pend_favored_size,
+                            saved_crashes: state.solutions().count(),
+                            execs_per_sec,
+                            total_execs: executions,
+                        },
+                    )?;
+                }
             }
             #[cfg(not(feature = "std"))]
             log::info!(
-                "pending: {}, pend_favored: {}, own_finds: {}, imported: {}",
+                "pending: {}, pend_favored: {}, own_finds: {}, imported: {}, execs_done: {}, execs_per_sec: {}, avg_corpus_size_bytes: {}, secs_since_last_find: {}, favored_ratio: {:.2}, abandoned_testcases: {}",
                 pending_size,
                 pend_favored_size,
                 self.own_finds_size,
-                self.imported_size
+                self.imported_size,
+                executions,
+                execs_per_sec,
+                avg_corpus_size_bytes,
+                secs_since_last_find,
+                favored_ratio,
+                abandoned_testcases
             );
             self.last_report_time = cur;
+            self.reported = true;
+            self.last_reported_counts = Some(counts);
         }
 
         Ok(())
@@ -154,6 +497,126 @@ impl<E, EM, Z> StatsStage<E, EM, Z> {
 }
 
 impl<E, EM, Z> StatsStage<E, EM, Z> {
+    /// Whether the last [`Stage::perform`] reported the stats, e.g., for a wrapping stage that
+    /// starts its own interval over with each report
+    #[must_use]
+    pub fn reported(&self) -> bool {
+        self.reported
+    }
+
+    /// Adds the stat `name` to the JSON report of the next [`Stage::perform`], e.g., by a wrapping stage.
+    ///
+    /// It is dropped if that computation is not reported, so wrapping stages add their stats before each computation.
+    #[cfg(feature = "std")]
+    pub fn add_to_report(&mut self, name: &str, value: serde_json::Value) {
+        self.extra_stats.insert(name.into(), value);
+    }
+
+    /// The stats computed by the last [`Stage::perform`], e.g., for an embedding harness
+    /// that does not subscribe to the events.
+    ///
+    /// The stats are computed whenever a new testcase is seen (or on every call, see
+    /// [`StatsStage::report_without_testcase`]), even if the interval did not elapse and they are not reported.
+    #[must_use]
+    pub fn snapshot(&self) -> AflStatsSnapshot {
+        AflStatsSnapshot {
+            corpus_count: self.corpus_size,
+            pending: self.pending_size,
+            pend_fav: self.pend_favored_size,
+            own_finds: self.own_finds_size,
+            imported: self.imported_size,
+            avg_corpus_size_bytes: self.avg_corpus_size_bytes(),
+            secs_since_last_find: self.secs_since_last_find,
+        }
+    }
+
+    /// The time the client last found a new testcase or solution, as of the last computation, reported
+    /// as `secs_since_last_find`, e.g., for a watchdog deciding that the campaign stalled.
+    ///
+    /// Before the first find, this is the start of the campaign, so that a fresh campaign does not look idle.
+    /// With [`StatsStage::with_clock`], the time is taken from that clock.
+    /// `None` before the first computation.
+    #[must_use]
+    pub fn last_find_time(&self) -> Option<Duration> {
+        self.last_find_time
+    }
+
+    /// Whether this stage reports its stats, see [`StatsStage::set_enabled`]
+    #[must_use]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Pauses (or resumes) the reports, e.g., during a corpus import. Enabled by default.
+    ///
+    /// The stats are still computed while paused, so that they are accurate once the reports resume.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Suppresses the reports while at least `threshold` testcases were imported since the last
+    /// computation, i.e., while the corpus of the other clients is synced, and resumes them once the imports calm down.
+    /// `None`, the default, never suppresses the reports.
+    ///
+    /// The threshold is an absolute count per computation, so pick it above the steady import rate of
+    /// the fleet (see [`DEFAULT_IMPORT_BURST_THRESHOLD`] for a start), or a busy client never reports.
+    pub fn set_import_burst_threshold(&mut self, threshold: Option<usize>) {
+        self.import_burst_threshold = threshold;
+    }
+
+    /// The number of testcases that have not been fuzzed yet, as of the last computation, see [`StatsStage::snapshot`]
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.pending_size
+    }
+
+    /// The number of favored testcases that have not been fuzzed yet, as of the last computation, see [`StatsStage::snapshot`]
+    #[must_use]
+    pub fn pending_favored(&self) -> usize {
+        self.pend_favored_size
+    }
+
+    /// The number of testcases found by the client itself, as of the last computation, see [`StatsStage::snapshot`]
+    #[must_use]
+    pub fn own_finds(&self) -> usize {
+        self.own_finds_size
+    }
+
+    /// The number of testcases imported from other clients, as of the last computation, see [`StatsStage::snapshot`]
+    #[must_use]
+    pub fn imported(&self) -> usize {
+        self.imported_size
+    }
+
+    /// The average input size, in bytes, of the testcases fuzzed so far, reported as `avg_corpus_size_bytes`.
+    ///
+    /// The sizes are only accounted for if the stage is wrapped in an [`InputSizeStatsStage`],
+    /// since not every input has a length. Otherwise, this is always `0`.
+    ///
+    /// To avoid going over the whole corpus for every report, the size of each testcase is added up
+    /// once, when it is fuzzed for the first time. Hence, testcases that are still pending are not
+    /// accounted for, and testcases removed from the corpus afterwards (e.g., by a minimizer) still are.
+    #[must_use]
+    pub fn avg_corpus_size_bytes(&self) -> usize {
+        self.fuzzed_input_bytes
+            .checked_div(self.sized_testcases)
+            .unwrap_or(0)
+    }
+
+    /// The ratio of favored testcases to all testcases in the corpus, as of the last computation,
+    /// reported as `favored_ratio`, e.g., to see how well a minimizing scheduler prunes the corpus.
+    ///
+    /// Like `pend_fav`, this only accounts for the favored testcases fuzzed so far. `0.0` for an empty corpus.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn favored_ratio(&self) -> f64 {
+        if self.corpus_size == 0 {
+            0.0
+        } else {
+            (self.is_favored_size as f64 / self.corpus_size as f64).min(1.0)
+        }
+    }
+
     /// create a new instance of the [`StatsStage`]
     #[must_use]
     pub fn new(interval: Duration) -> Self {
@@ -162,20 +625,1533 @@ impl<E, EM, Z> StatsStage<E, EM, Z> {
             ..Default::default()
         }
     }
+
+    /// The current time, from the clock given to [`StatsStage::with_clock`], if any
+    fn now(&self) -> Duration {
+        self.clock
+            .as_ref()
+            .map_or_else(current_time, |clock| clock())
+    }
+
+    /// Keep firing the interval report (with the corpus counts only) even if there is no
+    /// current testcase, or the current testcase was already fuzzed before.
+    ///
+    /// By default, the [`StatsStage`] only reports when it sees a new testcase, which means
+    /// nothing is reported during long seed imports.
+    #[must_use]
+    pub fn report_without_testcase(mut self, report_without_testcase: bool) -> Self {
+        self.report_without_testcase = report_without_testcase;
+        self
+    }
+
+    /// Calls `on_corpus_growth` with the new corpus size whenever the corpus grows beyond the
+    /// largest size seen so far, e.g., to trigger external snapshots.
+    ///
+    /// Unlike the reports, this is not throttled by the interval. Shrinking the corpus (e.g., by a
+    /// minimizer) and growing it back to the previous maximum does not call it.
+    #[must_use]
+    pub fn on_corpus_growth<F>(mut self, on_corpus_growth: F) -> Self
+    where
+        F: FnMut(usize) + 'static,
+    {
+        self.on_corpus_growth = Some(Rc::new(RefCell::new(on_corpus_growth)));
+        self
+    }
+
+    /// Create a new instance of the [`StatsStage`] that takes the current time from `clock` instead of
+    /// [`current_time`], e.g., to test the reporting with a fake clock or to replay a campaign deterministically.
+    ///
+    /// The first report is due `interval` after the time `clock` returns now. Since the start time of the
+    /// state is wall-clock time, the campaign starts at the first computation for `execs_per_sec` and
+    /// `secs_since_last_find`.
+    #[must_use]
+    pub fn with_clock<F>(interval: Duration, clock: F) -> Self
+    where
+        F: Fn() -> Duration + 'static,
+    {
+        Self {
+            stats_report_interval: interval,
+            last_report_time: clock(),
+            clock: Some(Rc::new(clock)),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new instance of the [`StatsStage`] that, in addition to the live stats events,
+    /// appends a row in the format of AFL++'s `plot_data` to the file at `path` every `interval`,
+    /// e.g., to generate graphs of the campaign with `afl-plot`.
+    ///
+    /// The columns this stage does not track, `cycles_done`, `map_size`, `saved_hangs`, `max_depth`
+    /// and `edges_found`, are always `0`. `saved_crashes` is the number of solutions.
+    /// If the file already exists (e.g., after a restart), new rows are appended to it.
+    /// Otherwise, the file is created and the header is written first.
+    #[cfg(feature = "std")]
+    pub fn with_plot_data(interval: Duration, path: PathBuf) -> Result<Self, Error> {
+        Self::create_plot_data_file(&path)?;
+        Ok(Self {
+            stats_report_interval: interval,
+            plot_file_path: Some(path),
+            ..Default::default()
+        })
+    }
+
+    /// Create a new instance of the [`StatsStage`] that, in addition to the combined stats,
+    /// reports each count as a separate stat, aggregated across clients as given in `aggregation`.
+    ///
+    /// See [`StatsAggregation`] for which counts are safe to aggregate.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn with_aggregation(interval: Duration, aggregation: StatsAggregation) -> Self {
+        Self {
+            stats_report_interval: interval,
+            aggregation: Some(aggregation),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new instance of the [`StatsStage`] that, in addition to the live stats events,
+    /// writes the stats as a JSON line to the Unix domain socket at `path` every `interval`,
+    /// e.g., for an external collector feeding a dashboard.
+    ///
+    /// The socket is connected lazily. If it cannot be connected or written to, that report is
+    /// skipped (the fuzzer keeps running), and the stage reconnects on the next interval.
+    #[cfg(all(feature = "std", unix))]
+    #[must_use]
+    pub fn with_socket(interval: Duration, path: PathBuf) -> Self {
+        Self {
+            stats_report_interval: interval,
+            socket: Some(StatsSocket { path, stream: None }),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new instance of the [`StatsStage`] that, in addition to the live stats events,
+    /// writes a compact status line to stderr every `interval`, e.g., for interactive local runs:
+    ///
+    /// `[corpus: 1234 | crashes: 5 | 4500 exec/s | last find 12s ago]`
+    ///
+    /// If stderr is a terminal, the line is rewritten in place. Otherwise, e.g., if stderr is piped to a file,
+    /// each report is written as a line of its own.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn with_tty(interval: Duration) -> Self {
+        let mode = if std::io::stderr().is_terminal() {
+            StatusLineMode::InPlace
+        } else {
+            StatusLineMode::Lines
+        };
+        Self {
+            stats_report_interval: interval,
+            status_line: Some(mode),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new instance of the [`StatsStage`] that reports as soon as any of the counts changed
+    /// since the last report, but at most once every `min_interval`.
+    ///
+    /// Unchanged counts are not reported again, so a fast-moving campaign gets responsive stats,
+    /// while an idle one does not repeat the same numbers.
+    #[must_use]
+    pub fn with_report_on_change(min_interval: Duration) -> Self {
+        Self {
+            stats_report_interval: min_interval,
+            report_on_change: true,
+            ..Default::default()
+        }
+    }
+
+    /// The imports per source client, as recorded in the [`ImportSourcesMetadata`], reported as `imported_by_client`.
+    ///
+    /// Imports from clients beyond [`crate::events::MAX_IMPORT_SOURCES`] are reported as `other`, and
+    /// imports without a recorded source, e.g., by a custom sync, as `unknown`.
+    #[cfg(feature = "std")]
+    fn imported_by_client<S>(state: &S, imported: usize) -> serde_json::Value
+    where
+        S: HasMetadata,
+    {
+        let mut by_client = serde_json::Map::new();
+        let mut recorded = 0;
+        if let Ok(meta) = state.metadata::<ImportSourcesMetadata>() {
+            for (client_id, count) in meta.counts() {
+                by_client.insert(client_id.0.to_string(), json!(count));
+                recorded += count;
+            }
+            recorded += meta.other();
+            by_client.insert("other".to_string(), json!(meta.other()));
+        } else {
+            by_client.insert("other".to_string(), json!(0));
+        }
+        by_client.insert(
+            "unknown".to_string(),
+            json!(imported.saturating_sub(recorded)),
+        );
+        serde_json::Value::Object(by_client)
+    }
+
+    #[cfg(feature = "std")]
+    fn create_plot_data_file(path: &Path) -> Result<(), Error> {
+        if path.exists() {
+            // only write the header if the file does not contain any data yet
+            let file = File::open(path)?;
+            if BufReader::new(file).lines().next().is_some() {
+                return Ok(());
+            }
+        }
+        std::fs::write(path, format!("{}\n", PlotDataRow::HEADER))?;
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    fn write_plot_data(path: &Path, row: &PlotDataRow) -> Result<(), Error> {
+        let mut file = OpenOptions::new().append(true).open(path)?;
+        // the columns this stage does not track are written as 0, in the format of AFL++
+        writeln!(
+            file,
+            "{}, 0, {}, {}, {}, {}, 0.00%, {}, 0, 0, {}, {}, 0",
+            row.relative_time,
+            row.cur_item,
+            row.corpus_count,
+            row.pending_total,
+            row.pending_favs,
+            row.saved_crashes,
+            row.execs_per_sec,
+            row.total_execs
+        )?;
+        Ok(())
+    }
 }
 
-impl<E, EM, Z> Default for StatsStage<E, EM, Z> {
-    /// the default instance of the [`StatsStage`]
+/// A [`StatsStage`] that also accounts for the input sizes of the testcases, reported as
+/// `avg_corpus_size_bytes`, see [`StatsStage::avg_corpus_size_bytes`].
+///
+/// This is a separate stage, so that the [`StatsStage`] itself works for inputs without a length.
+#[derive(Clone, Debug)]
+pub struct InputSizeStatsStage<E, EM, Z> {
+    inner: StatsStage<E, EM, Z>,
+}
+
+impl<E, EM, Z> InputSizeStatsStage<E, EM, Z> {
+    /// Wraps the `inner` [`StatsStage`], which computes and reports the stats
     #[must_use]
-    fn default() -> Self {
+    pub fn new(inner: StatsStage<E, EM, Z>) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped [`StatsStage`], e.g., for its [`StatsStage::snapshot`]
+    #[must_use]
+    pub fn inner(&self) -> &StatsStage<E, EM, Z> {
+        &self.inner
+    }
+
+    /// The wrapped [`StatsStage`] (mutable), e.g., to [`StatsStage::set_enabled`]
+    pub fn inner_mut(&mut self) -> &mut StatsStage<E, EM, Z> {
+        &mut self.inner
+    }
+}
+
+impl<E, EM, Z> AsRef<StatsStage<E, EM, Z>> for StatsStage<E, EM, Z> {
+    fn as_ref(&self) -> &StatsStage<E, EM, Z> {
+        self
+    }
+}
+
+impl<E, EM, Z> AsMut<StatsStage<E, EM, Z>> for StatsStage<E, EM, Z> {
+    fn as_mut(&mut self) -> &mut StatsStage<E, EM, Z> {
+        self
+    }
+}
+
+impl<E, EM, Z> AsRef<StatsStage<E, EM, Z>> for InputSizeStatsStage<E, EM, Z> {
+    fn as_ref(&self) -> &StatsStage<E, EM, Z> {
+        &self.inner
+    }
+}
+
+impl<E, EM, Z> AsMut<StatsStage<E, EM, Z>> for InputSizeStatsStage<E, EM, Z> {
+    fn as_mut(&mut self) -> &mut StatsStage<E, EM, Z> {
+        &mut self.inner
+    }
+}
+
+impl<E, EM, Z> UsesState for InputSizeStatsStage<E, EM, Z>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E, EM, Z> Stage<E, EM, Z> for InputSizeStatsStage<E, EM, Z>
+where
+    E: UsesState,
+    EM: EventFirer<State = Self::State>,
+    Z: UsesState<State = Self::State>,
+    E::State: HasImported + HasCorpus + HasMetadata + HasExecutions + HasSolutions + HasStartTime,
+    <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, //delete me
+    Self::Input: HasLen,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        // Account for the testcases the inner stage sees for the first time, before it reports
+        if let Some(corpus_id) = state.current_corpus_id()? {
+            let mut testcase = state.corpus().get(corpus_id)?.borrow_mut();
+            if testcase.scheduled_count() == 0 {
+                self.inner.fuzzed_input_bytes += testcase.load_len(state.corpus())?;
+                self.inner.sized_testcases += 1;
+            }
+        }
+        self.inner.perform(fuzzer, executor, state, manager)
+    }
+
+    #[inline]
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        self.inner.should_restart(state)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        self.inner.clear_progress(state)
+    }
+}
+
+/// A stage wrapping a [`StatsStage`] (or a stage wrapping one, see [`AsMut`]) that also reports the runtimes
+/// of the harness recorded by a [`HarnessTimingObserver`] as `harness_timing`: the last and the maximum
+/// runtime (`last_us`, `max_us`), the number of timed executions (`execs`) and the executions per second
+/// spent in the harness (`execs_per_sec`). The slowest execution and the number of timeouts since the last
+/// report are reported as `slowest_exec_ms` and `timeouts_since_last_report`.
+///
+/// The runtimes are read from the [`HarnessTimingMetadata`] of the state, so the observer does not have to be
+/// passed to this stage. Until the observer ran, `harness_timing` and `slowest_exec_ms` are `null`.
+#[derive(Clone, Debug)]
+pub struct HarnessTimingStatsStage<ST> {
+    inner: ST,
+    // the name of the `HarnessTimingObserver` whose runtimes to report
+    name: Cow<'static, str>,
+}
+
+impl<ST> HarnessTimingStatsStage<ST> {
+    /// Wraps the `inner` stage, which computes and reports the stats, to also report the runtimes recorded by the `observer`
+    #[must_use]
+    pub fn new(inner: ST, observer: &HarnessTimingObserver) -> Self {
         Self {
-            has_fuzzed_size: 0,
-            is_favored_size: 0,
-            own_finds_size: 0,
-            imported_size: 0,
-            last_report_time: current_time(),
-            stats_report_interval: Duration::from_secs(15),
-            phantom: PhantomData,
+            inner,
+            name: observer.name().clone(),
+        }
+    }
+
+    /// The wrapped stage
+    #[must_use]
+    pub fn inner(&self) -> &ST {
+        &self.inner
+    }
+
+    /// The wrapped stage (mutable)
+    pub fn inner_mut(&mut self) -> &mut ST {
+        &mut self.inner
+    }
+}
+
+impl<E, EM, ST, Z> AsRef<StatsStage<E, EM, Z>> for HarnessTimingStatsStage<ST>
+where
+    ST: AsRef<StatsStage<E, EM, Z>>,
+{
+    fn as_ref(&self) -> &StatsStage<E, EM, Z> {
+        self.inner.as_ref()
+    }
+}
+
+impl<E, EM, ST, Z> AsMut<StatsStage<E, EM, Z>> for HarnessTimingStatsStage<ST>
+where
+    ST: AsMut<StatsStage<E, EM, Z>>,
+{
+    fn as_mut(&mut self) -> &mut StatsStage<E, EM, Z> {
+        self.inner.as_mut()
+    }
+}
+
+impl<ST> UsesState for HarnessTimingStatsStage<ST>
+where
+    ST: UsesState,
+{
+    type State = ST::State;
+}
+
+impl<E, EM, ST, Z> Stage<E, EM, Z> for HarnessTimingStatsStage<ST>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState<State = Self::State>,
+    ST: Stage<E, EM, Z> + AsRef<StatsStage<E, EM, Z>> + AsMut<StatsStage<E, EM, Z>>,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasNamedMetadata,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let timing = HarnessTimingMetadata::of(state, &self.name);
+        #[cfg(feature = "std")]
+        {
+            let stats = self.inner.as_mut();
+            stats.add_to_report(
+                "slowest_exec_ms",
+                json!(timing
+                    .and_then(|timing| timing.slowest)
+                    .map(|slowest| u64::try_from(slowest.as_millis()).unwrap_or(u64::MAX))),
+            );
+            stats.add_to_report(
+                "timeouts_since_last_report",
+                json!(timing.map_or(0, |timing| timing.timeouts)),
+            );
+            stats.add_to_report(
+                "harness_timing",
+                json!(timing.map(|timing| json!({
+                    "last_us":timing.last.map(|last| u64::try_from(last.as_micros()).unwrap_or(u64::MAX)),
+                    "max_us":timing.max.map(|max| u64::try_from(max.as_micros()).unwrap_or(u64::MAX)),
+                    "execs":timing.count,
+                    "execs_per_sec":timing.execs_per_sec(),
+                }))),
+            );
+        }
+
+        self.inner.perform(fuzzer, executor, state, manager)?;
+
+        if self.inner.as_ref().reported() {
+            // The slowest execution is reported per interval, so it starts over with each report
+            HarnessTimingMetadata::take_interval_from(state, &self.name);
+            #[cfg(not(feature = "std"))]
+            log::info!("harness_timing: {timing:?}");
         }
+        Ok(())
+    }
+
+    #[inline]
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        self.inner.should_restart(state)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        self.inner.clear_progress(state)
+    }
+}
+
+/// A stage wrapping a [`StatsStage`] (or a stage wrapping one, see [`AsMut`]) that also reports the share of the
+/// time spent in each stage, e.g., in mutating, executing or the cmp observers, as measured by the
+/// [`crate::monitors::ClientPerfMonitor`], as `introspection`.
+#[cfg(feature = "introspection")]
+#[derive(Clone, Debug)]
+pub struct IntrospectionStatsStage<ST> {
+    inner: ST,
+}
+
+#[cfg(feature = "introspection")]
+impl<ST> IntrospectionStatsStage<ST> {
+    /// Wraps the `inner` stage, which computes and reports the stats, to also report the time breakdown
+    #[must_use]
+    pub fn new(inner: ST) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped stage
+    #[must_use]
+    pub fn inner(&self) -> &ST {
+        &self.inner
+    }
+
+    /// The wrapped stage (mutable)
+    pub fn inner_mut(&mut self) -> &mut ST {
+        &mut self.inner
+    }
+}
+
+#[cfg(feature = "introspection")]
+impl<E, EM, ST, Z> AsRef<StatsStage<E, EM, Z>> for IntrospectionStatsStage<ST>
+where
+    ST: AsRef<StatsStage<E, EM, Z>>,
+{
+    fn as_ref(&self) -> &StatsStage<E, EM, Z> {
+        self.inner.as_ref()
+    }
+}
+
+#[cfg(feature = "introspection")]
+impl<E, EM, ST, Z> AsMut<StatsStage<E, EM, Z>> for IntrospectionStatsStage<ST>
+where
+    ST: AsMut<StatsStage<E, EM, Z>>,
+{
+    fn as_mut(&mut self) -> &mut StatsStage<E, EM, Z> {
+        self.inner.as_mut()
+    }
+}
+
+#[cfg(feature = "introspection")]
+impl<ST> UsesState for IntrospectionStatsStage<ST>
+where
+    ST: UsesState,
+{
+    type State = ST::State;
+}
+
+#[cfg(feature = "introspection")]
+impl<E, EM, ST, Z> Stage<E, EM, Z> for IntrospectionStatsStage<ST>
+where
+    E: UsesState<State = Self::State>,
+    EM: UsesState<State = Self::State>,
+    ST: Stage<E, EM, Z> + AsRef<StatsStage<E, EM, Z>> + AsMut<StatsStage<E, EM, Z>>,
+    Z: UsesState<State = Self::State>,
+    Self::State: HasClientPerfMonitor,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        #[cfg(feature = "std")]
+        self.inner.as_mut().add_to_report(
+            "introspection",
+            perf_breakdown(state.introspection_monitor()),
+        );
+
+        self.inner.perform(fuzzer, executor, state, manager)?;
+
+        #[cfg(not(feature = "std"))]
+        if self.inner.as_ref().reported() {
+            log::info!("introspection:\n{}", state.introspection_monitor());
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        self.inner.should_restart(state)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        self.inner.clear_progress(state)
+    }
+}
+
+/// The Unix domain socket the [`StatsStage`] writes its JSON lines to
+#[cfg(all(feature = "std", unix))]
+#[derive(Debug)]
+struct StatsSocket {
+    path: PathBuf,
+    // the connected stream, `None` until the next (re)connect
+    stream: Option<UnixStream>,
+}
+
+#[cfg(all(feature = "std", unix))]
+impl Clone for StatsSocket {
+    /// The clone connects on its own, on its next report
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            stream: None,
+        }
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl StatsSocket {
+    /// Writes `line` to the socket, (re)connecting first if needed. Failures only skip this line.
+    fn send(&mut self, line: &str) {
+        if self.stream.is_none() {
+            match UnixStream::connect(&self.path) {
+                Ok(stream) => self.stream = Some(stream),
+                Err(err) => {
+                    log::debug!(
+                        "Could not connect to the stats socket {}: {err}",
+                        self.path.display()
+                    );
+                    return;
+                }
+            }
+        }
+        if let Some(stream) = &mut self.stream {
+            if let Err(err) = writeln!(stream, "{line}") {
+                log::debug!(
+                    "Could not write to the stats socket {}: {err}",
+                    self.path.display()
+                );
+                // reconnect on the next report
+                self.stream = None;
+            }
+        }
+    }
+}
+
+/// How the [`StatsStage`] writes its status line, see [`StatsStage::with_tty`]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusLineMode {
+    /// Rewrite the line in place, for terminals
+    InPlace,
+    /// Write each status as a line of its own, e.g., if stderr is piped
+    Lines,
+}
+
+#[cfg(feature = "std")]
+impl StatusLineMode {
+    fn write<W>(self, out: &mut W, line: &str) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            // return to the start of the line, and clear what is left of the previous status
+            Self::InPlace => write!(out, "\r{line}\x1b[K")?,
+            Self::Lines => writeln!(out, "{line}")?,
+        }
+        out.flush()
+    }
+}
+
+/// Renders the status line of [`StatsStage::with_tty`]
+#[cfg(feature = "std")]
+fn status_line(
+    corpus_count: usize,
+    solutions: usize,
+    execs_per_sec: u64,
+    secs_since_last_find: u64,
+) -> String {
+    format!(
+        "[corpus: {corpus_count} | crashes: {solutions} | {execs_per_sec} exec/s | last find {secs_since_last_find}s ago]"
+    )
+}
+
+/// The share of the time, in percent, spent in the scheduler, the manager, each feature of each
+/// stage and each feedback, as measured by the [`ClientPerfMonitor`]
+///
+/// Unused features are left out, and `null` is reported if no time has elapsed yet.
+#[cfg(all(feature = "std", feature = "introspection"))]
+#[allow(clippy::cast_precision_loss)]
+fn perf_breakdown(monitor: &ClientPerfMonitor) -> serde_json::Value {
+    let elapsed = monitor.elapsed_cycles();
+    if elapsed == 0 {
+        return serde_json::Value::Null;
+    }
+    let percent = |cycles: u64| cycles as f64 * 100.0 / elapsed as f64;
+
+    let mut measured = monitor.scheduler_cycles() + monitor.manager_cycles();
+    let mut stages = serde_json::Map::new();
+    for (stage_index, features) in monitor.used_stages() {
+        let mut stage = serde_json::Map::new();
+        for (feature_index, cycles) in features.iter().enumerate() {
+            if *cycles == 0 {
+                continue;
+            }
+            measured += cycles;
+            let feature: PerfFeature = feature_index.into();
+            stage.insert(format!("{feature:?}"), json!(percent(*cycles)));
+        }
+        stages.insert(stage_index.to_string(), serde_json::Value::Object(stage));
+    }
+    let mut feedbacks = serde_json::Map::new();
+    for (name, cycles) in monitor.feedbacks() {
+        if *cycles == 0 {
+            continue;
+        }
+        measured += cycles;
+        feedbacks.insert(name.clone(), json!(percent(*cycles)));
+    }
+
+    json!({
+        "scheduler":percent(monitor.scheduler_cycles()),
+        "manager":percent(monitor.manager_cycles()),
+        "stages":stages,
+        "feedbacks":feedbacks,
+        "not_measured":percent(elapsed.saturating_sub(measured)),
+    })
+}
+
+/// The columns of AFL++'s `plot_data` tracked by the [`StatsStage`], see [`StatsStage::with_plot_data`]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+struct PlotDataRow {
+    relative_time: u64,
+    cur_item: usize,
+    corpus_count: usize,
+    pending_total: usize,
+    pending_favs: usize,
+    saved_crashes: usize,
+    execs_per_sec: u64,
+    total_execs: u64,
+}
+
+#[cfg(feature = "std")]
+impl PlotDataRow {
+    /// The header of AFL++'s `plot_data`, `afl-plot` reads the columns by their position
+    const HEADER: &'static str = "# relative_time, cycles_done, cur_item, corpus_count, pending_total, pending_favs, map_size, saved_crashes, saved_hangs, max_depth, execs_per_sec, total_execs, edges_found";
+}
+
+/// Computes the executions per second for the given amount of executions in the elapsed time,
+/// `0` if no time has elapsed yet
+fn execs_per_sec(executions: u64, elapsed: Duration) -> u64 {
+    (u128::from(executions) * 1_000_000_000)
+        .checked_div(elapsed.as_nanos())
+        .map_or(0, |execs_per_sec| {
+            u64::try_from(execs_per_sec).unwrap_or(u64::MAX)
+        })
+}
+
+impl<E, EM, Z> Default for StatsStage<E, EM, Z> {
+    /// the default instance of the [`StatsStage`]
+    #[must_use]
+    fn default() -> Self {
+        Self {
+            has_fuzzed_size: 0,
+            is_favored_size: 0,
+            own_finds_size: 0,
+            imported_size: 0,
+            fuzzed_input_bytes: 0,
+            sized_testcases: 0,
+            corpus_size: 0,
+            pending_size: 0,
+            pend_favored_size: 0,
+            last_report_time: current_time(),
+            stats_report_interval: Duration::from_secs(15),
+            report_without_testcase: false,
+            report_on_change: false,
+            last_reported_counts: None,
+            enabled: true,
+            import_burst_threshold: None,
+            last_imported: 0,
+            last_find_time: None,
+            last_finds: 0,
+            secs_since_last_find: 0,
+            #[cfg(feature = "std")]
+            plot_file_path: None,
+            #[cfg(feature = "std")]
+            aggregation: None,
+            #[cfg(all(feature = "std", unix))]
+            socket: None,
+            #[cfg(feature = "std")]
+            status_line: None,
+            #[cfg(feature = "std")]
+            extra_stats: serde_json::Map::new(),
+            reported: false,
+            max_corpus_size: 0,
+            on_corpus_growth: None,
+            clock: None,
+            start_time: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    #[cfg(feature = "std")]
+    use alloc::{borrow::Cow, vec::Vec};
+    use core::{
+        cell::{Cell, RefCell},
+        marker::PhantomData,
+        time::Duration,
+    };
+
+    use libafl_bolts::Error;
+    #[cfg(feature = "std")]
+    use libafl_bolts::{current_time, ClientId};
+
+    use crate::{
+        corpus::{Corpus, HasCurrentCorpusId, Testcase},
+        events::NopEventManager,
+        fuzzer::NopFuzzer,
+        inputs::{BytesInput, NopInput},
+        stages::{
+            stats::{execs_per_sec, DEFAULT_IMPORT_BURST_THRESHOLD},
+            AflStatsSnapshot, InputSizeStatsStage, Stage, StatsStage,
+        },
+        state::{HasCorpus, State, StdState, UsesState},
+    };
+    #[cfg(feature = "std")]
+    use crate::{
+        events::{Event, EventFirer, ImportSourcesMetadata},
+        executors::ExitKind,
+        monitors::{AggregatorOps, UserStats, UserStatsValue},
+        observers::{HarnessTimingMetadata, HarnessTimingObserver},
+        schedulers::minimizer::IsFavoredMetadata,
+        stages::{
+            stats::{status_line, StatusLineMode},
+            HarnessTimingStatsStage, StatsAggregation,
+        },
+        state::{HasExecutions, HasImported, HasSolutions, HasStartTime},
+        HasMetadata, HasNamedMetadata,
+    };
+    #[cfg(all(feature = "std", feature = "introspection"))]
+    use crate::{
+        monitors::PerfFeature, stages::IntrospectionStatsStage, state::HasClientPerfMonitor,
+    };
+
+    /// The stats stages never run the executor, they only need its state type
+    struct NopExecutor<S>(PhantomData<S>);
+
+    impl<S> NopExecutor<S> {
+        fn new() -> Self {
+            Self(PhantomData)
+        }
+    }
+
+    impl<S> UsesState for NopExecutor<S>
+    where
+        S: State,
+    {
+        type State = S;
+    }
+
+    /// Removing testcases from the corpus must not underflow the pending counts
+    #[test]
+    fn test_stats_stage_corpus_removal() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = NopEventManager::new();
+        let mut stage = StatsStage::new(Duration::ZERO);
+
+        let mut ids = vec![];
+        for _ in 0..4 {
+            let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+            state.set_corpus_id(id)?;
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+            ids.push(id);
+        }
+
+        // a minimizer removes entries between reports
+        for id in &ids[1..] {
+            state.corpus_mut().remove(*id)?;
+        }
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+
+        // 5 fuzzed testcases, but only 2 left in the corpus
+        assert_eq!(state.corpus().count(), 2);
+        assert_eq!(stage.pending(), 0);
+        assert_eq!(stage.pending_favored(), 2);
+        assert_eq!(stage.own_finds(), 2);
+        assert_eq!(stage.snapshot().pending, 0);
+
+        Ok(())
+    }
+
+    /// Less than a second of runtime does not inflate the rate
+    #[test]
+    fn test_execs_per_sec() {
+        assert_eq!(execs_per_sec(1000, Duration::ZERO), 0);
+        assert_eq!(execs_per_sec(1000, Duration::from_millis(500)), 2000);
+        assert_eq!(execs_per_sec(1000, Duration::from_secs(4)), 250);
+        assert_eq!(execs_per_sec(u64::MAX, Duration::from_nanos(1)), u64::MAX);
+    }
+
+    /// Without a current testcase, we only report if asked to
+    #[test]
+    fn test_stats_stage_without_testcase() -> Result<(), Error> {
+        let mut state = StdState::nop::<NopInput>()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = NopEventManager::new();
+
+        let mut stage = StatsStage::new(Duration::ZERO);
+        assert!(stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut manager)
+            .is_err());
+
+        let mut stage = StatsStage::new(Duration::ZERO).report_without_testcase(true);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(stage.has_fuzzed_size, 0);
+
+        Ok(())
+    }
+
+    /// Records the user stats fired by the [`StatsStage`]
+    #[cfg(feature = "std")]
+    struct UserStatsRecorder<S> {
+        stats: Vec<(Cow<'static, str>, UserStats)>,
+        phantom: PhantomData<S>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<S> UsesState for UserStatsRecorder<S>
+    where
+        S: State,
+    {
+        type State = S;
+    }
+
+    #[cfg(feature = "std")]
+    impl<S> EventFirer for UserStatsRecorder<S>
+    where
+        S: State,
+    {
+        fn fire(&mut self, _state: &mut S, event: Event<S::Input>) -> Result<(), Error> {
+            if let Event::UpdateUserStats { name, value, .. } = event {
+                self.stats.push((name, value));
+            }
+            Ok(())
+        }
+
+        fn should_send(&self) -> bool {
+            true
+        }
+    }
+
+    /// The single counts are reported with their configured aggregation
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_aggregation() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let mut stage = StatsStage::with_aggregation(
+            Duration::ZERO,
+            StatsAggregation {
+                own_finds: AggregatorOps::Sum,
+                corpus_count: AggregatorOps::Max,
+                ..StatsAggregation::default()
+            },
+        );
+
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+
+        let stat = |name: &str| {
+            manager
+                .stats
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, stat)| stat)
+                .unwrap()
+        };
+        assert!(matches!(
+            stat("own_finds").value(),
+            UserStatsValue::Number(1)
+        ));
+        assert!(matches!(
+            stat("own_finds").aggregator_op(),
+            AggregatorOps::Sum
+        ));
+        assert!(matches!(
+            stat("corpus_count").aggregator_op(),
+            AggregatorOps::Max
+        ));
+        assert!(matches!(stat("pending").value(), UserStatsValue::Number(0)));
+        assert!(matches!(
+            stat("imported").aggregator_op(),
+            AggregatorOps::None
+        ));
+
+        Ok(())
+    }
+
+    /// The `plot_data` rows follow the columns of AFL++, and are appended after a restart
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_plot_data() -> Result<(), Error> {
+        let path = std::env::temp_dir().join(format!("libafl_plot_data_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = NopEventManager::new();
+        let now = Rc::new(Cell::new(Duration::from_secs(1000)));
+        let clock = now.clone();
+        let mut stage = StatsStage::with_plot_data(Duration::ZERO, path.clone())?;
+        stage.clock = Some(Rc::new(move || clock.get()));
+        stage.last_report_time = Duration::ZERO;
+        for secs in [1000, 1002] {
+            now.set(Duration::from_secs(secs));
+            let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+            state.set_corpus_id(id)?;
+            *state.executions_mut() += 100;
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        }
+
+        // after a restart, the rows are appended to the file
+        let clock = now.clone();
+        let mut stage = StatsStage::with_plot_data(Duration::ZERO, path.clone())?;
+        stage.clock = Some(Rc::new(move || clock.get()));
+        stage.last_report_time = Duration::ZERO;
+        state.solutions_mut().add(Testcase::new(NopInput {}))?;
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+
+        assert_eq!(
+            std::fs::read_to_string(&path)?,
+            "# relative_time, cycles_done, cur_item, corpus_count, pending_total, pending_favs, map_size, saved_crashes, saved_hangs, max_depth, execs_per_sec, total_execs, edges_found\n\
+             0, 0, 0, 1, 0, 1, 0.00%, 0, 0, 0, 0, 100, 0\n\
+             2, 0, 1, 2, 0, 2, 0.00%, 0, 0, 0, 100, 200, 0\n\
+             0, 0, 2, 3, 2, 3, 0.00%, 1, 0, 0, 0, 200, 0\n"
+        );
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    /// With a fake clock, reports happen once the interval has passed since the last one
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_with_clock() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let now = Rc::new(Cell::new(Duration::from_secs(100)));
+        let clock = now.clone();
+        let mut stage = StatsStage::with_clock(Duration::from_secs(10), move || clock.get())
+            .report_without_testcase(true);
+
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        *state.executions_mut() = 600;
+
+        let mut reports = vec![];
+        for secs in [105, 110, 111, 115, 121, 122] {
+            now.set(Duration::from_secs(secs));
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+            reports.push(
+                manager
+                    .stats
+                    .iter()
+                    .filter(|(name, _)| name == "Stats")
+                    .count(),
+            );
+        }
+        // only more than the interval after the last report
+        assert_eq!(reports, [0, 0, 1, 1, 1, 2]);
+
+        // the campaign started at the first computation, at 105s on the fake clock
+        let UserStatsValue::String(json) = manager.stats[0].1.value() else {
+            panic!("The stats are not a JSON string");
+        };
+        let stats: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(stats["secs_since_last_find"], 6);
+        assert_eq!(stats["execs_per_sec"], 100);
+        assert_eq!(stage.last_find_time(), Some(Duration::from_secs(105)));
+
+        Ok(())
+    }
+
+    /// In the on-change mode, unchanged counts are not reported again
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_report_on_change() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let mut stage = StatsStage::with_report_on_change(Duration::ZERO);
+
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(manager.stats.len(), 1);
+
+        // nothing changed
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(manager.stats.len(), 1);
+
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(manager.stats.len(), 2);
+
+        // changes are still throttled by the minimum interval
+        let mut stage = StatsStage::with_report_on_change(Duration::MAX);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(manager.stats.len(), 2);
+
+        Ok(())
+    }
+
+    /// Nothing is reported while paused or during an import burst
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_pause() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let mut stage = StatsStage::new(Duration::ZERO);
+        stage.set_import_burst_threshold(Some(DEFAULT_IMPORT_BURST_THRESHOLD));
+
+        for enabled in [false, true] {
+            stage.set_enabled(enabled);
+            let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+            state.set_corpus_id(id)?;
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        }
+        assert_eq!(manager.stats.len(), 1);
+
+        for imports in [DEFAULT_IMPORT_BURST_THRESHOLD, 1] {
+            *state.imported_mut() += imports;
+            let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+            state.set_corpus_id(id)?;
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        }
+        // only reported once the imports calmed down
+        assert_eq!(manager.stats.len(), 2);
+        // the pending testcases are still accounted for
+        assert_eq!(stage.pending(), 0);
+
+        // bursts are not suppressed by default
+        let mut stage = StatsStage::new(Duration::ZERO);
+        *state.imported_mut() += 100;
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(manager.stats.len(), 3);
+
+        Ok(())
+    }
+
+    /// The growth callback fires on each new maximum only
+    #[test]
+    fn test_stats_stage_on_corpus_growth() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = NopEventManager::new();
+
+        let sizes = Rc::new(RefCell::new(vec![]));
+        let sizes_clone = sizes.clone();
+        let mut stage = StatsStage::new(Duration::MAX)
+            .on_corpus_growth(move |size| sizes_clone.borrow_mut().push(size));
+
+        let mut ids = vec![];
+        for _ in 0..3 {
+            let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+            state.set_corpus_id(id)?;
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+            ids.push(id);
+        }
+        assert_eq!(*sizes.borrow(), [1, 2, 3]);
+
+        // shrinking and growing back to the maximum is not a growth
+        state.corpus_mut().remove(ids[1])?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(*sizes.borrow(), [1, 2, 3]);
+
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(*sizes.borrow(), [1, 2, 3, 4]);
+
+        Ok(())
+    }
+
+    /// Clones share the growth callback
+    #[test]
+    fn test_stats_stage_clone() {
+        let sizes = Rc::new(RefCell::new(vec![]));
+        let sizes_clone = sizes.clone();
+        let stage = StatsStage::<(), (), ()>::new(Duration::MAX)
+            .on_corpus_growth(move |size| sizes_clone.borrow_mut().push(size));
+        let cloned = stage.clone();
+
+        for stage in [&stage, &cloned] {
+            let on_corpus_growth = stage.on_corpus_growth.as_ref().unwrap();
+            (on_corpus_growth.borrow_mut())(1);
+        }
+        assert_eq!(*sizes.borrow(), [1, 1]);
+    }
+
+    /// The stats are written to the socket once it can be connected
+    #[test]
+    #[cfg(all(feature = "std", unix))]
+    fn test_stats_stage_socket() -> Result<(), Error> {
+        use alloc::string::String;
+        use std::{
+            io::{BufRead, BufReader},
+            os::unix::net::UnixListener,
+        };
+
+        let path = std::env::temp_dir().join(format!("libafl_stats_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = NopEventManager::new();
+        let mut stage = StatsStage::with_socket(Duration::ZERO, path.clone());
+
+        // nobody is listening yet, the report is skipped
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+
+        let listener = UnixListener::bind(&path)?;
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+
+        let (stream, _) = listener.accept()?;
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        let stats: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(stats["own_finds"], 2);
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    /// The snapshot is updated even if the stats are not reported
+    #[test]
+    fn test_stats_stage_snapshot() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = NopEventManager::new();
+        let mut stage = StatsStage::new(Duration::MAX);
+        assert_eq!(stage.snapshot(), AflStatsSnapshot::default());
+
+        let id = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![0; 4])))?;
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![0; 8])))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+
+        assert_eq!(
+            stage.snapshot(),
+            AflStatsSnapshot {
+                corpus_count: 2,
+                pending: 1,
+                pend_fav: 2,
+                own_finds: 2,
+                imported: 0,
+                avg_corpus_size_bytes: 0,
+                secs_since_last_find: 0,
+            }
+        );
+        assert_eq!(stage.pending(), 1);
+        assert_eq!(stage.pending_favored(), 2);
+        assert_eq!(stage.own_finds(), 2);
+        assert_eq!(stage.imported(), 0);
+
+        Ok(())
+    }
+
+    /// The idle time counts from the start of the campaign, and from each new find on
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_secs_since_last_find() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let mut stage = StatsStage::new(Duration::ZERO);
+        assert_eq!(stage.last_find_time(), None);
+
+        // a fresh campaign is not idle
+        *state.start_time_mut() = current_time().checked_sub(Duration::from_secs(5)).unwrap();
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(stage.last_find_time(), Some(*state.start_time()));
+        assert_eq!(stage.snapshot().secs_since_last_find, 5);
+
+        // no new find
+        stage.last_find_time = Some(current_time().checked_sub(Duration::from_secs(90)).unwrap());
+        state.corpus().get(id)?.borrow_mut().set_scheduled_count(1);
+        let mut stage = stage.report_without_testcase(true);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(stage.snapshot().secs_since_last_find, 90);
+        let UserStatsValue::String(json) = manager.stats.last().unwrap().1.value() else {
+            panic!("The stats are not a JSON string");
+        };
+        let stats: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(stats["secs_since_last_find"], 90);
+
+        // a new solution resets the idle time
+        state.solutions_mut().add(Testcase::new(NopInput {}))?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(stage.snapshot().secs_since_last_find, 0);
+
+        Ok(())
+    }
+
+    /// The favored ratio is reported, and is zero for an empty corpus
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_favored_ratio() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let mut stage = StatsStage::new(Duration::ZERO);
+        assert!(stage.favored_ratio().abs() < f64::EPSILON);
+
+        for favored in [true, false] {
+            let mut testcase = Testcase::new(NopInput {});
+            if favored {
+                testcase.add_metadata(IsFavoredMetadata {});
+            }
+            let id = state.corpus_mut().add(testcase)?;
+            state.set_corpus_id(id)?;
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        }
+        assert!((stage.favored_ratio() - 0.5).abs() < f64::EPSILON);
+
+        let UserStatsValue::String(json) = manager.stats.last().unwrap().1.value() else {
+            panic!("The stats are not a JSON string");
+        };
+        let stats: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(stats["favored_ratio"], 0.5);
+
+        Ok(())
+    }
+
+    /// The imports are broken down by their source client, with the unrecorded ones as unknown
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_imported_by_client() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let mut stage = StatsStage::new(Duration::ZERO);
+
+        for client_id in [1, 2, 1] {
+            ImportSourcesMetadata::record_in(&mut state, ClientId(client_id));
+        }
+        *state.imported_mut() = 5;
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+
+        let UserStatsValue::String(json) = manager.stats.last().unwrap().1.value() else {
+            panic!("The stats are not a JSON string");
+        };
+        let stats: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            stats["imported_by_client"],
+            serde_json::json!({"1": 2, "2": 1, "other": 0, "unknown": 2})
+        );
+
+        Ok(())
+    }
+
+    /// The slowest execution is reported for each interval, apart from the timeouts
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_slowest_exec() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let observer = HarnessTimingObserver::new("harness");
+        let mut stage = HarnessTimingStatsStage::new(StatsStage::new(Duration::ZERO), &observer);
+
+        let timing = state.named_metadata_or_insert_with("harness", HarnessTimingMetadata::default);
+        timing.record(Duration::from_millis(20), ExitKind::Ok);
+        timing.record(Duration::from_millis(70), ExitKind::Crash);
+        timing.record(Duration::from_secs(10), ExitKind::Timeout);
+        timing.record(Duration::from_millis(30), ExitKind::Ok);
+
+        let mut stats = vec![];
+        for _ in 0..2 {
+            let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+            state.set_corpus_id(id)?;
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+            let UserStatsValue::String(json) = manager.stats.last().unwrap().1.value() else {
+                panic!("The stats are not a JSON string");
+            };
+            stats.push(serde_json::from_str::<serde_json::Value>(json).unwrap());
+        }
+        assert_eq!(stats[0]["slowest_exec_ms"], 70);
+        assert_eq!(stats[0]["timeouts_since_last_report"], 1);
+        // the next interval starts over
+        assert!(stats[1]["slowest_exec_ms"].is_null());
+        assert_eq!(stats[1]["timeouts_since_last_report"], 0);
+
+        Ok(())
+    }
+
+    /// The harness timing is read from the metadata of the observer, by its name
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_harness_timing() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let observer = HarnessTimingObserver::new("harness");
+        let mut stage = HarnessTimingStatsStage::new(StatsStage::new(Duration::ZERO), &observer);
+
+        let mut stats = vec![];
+        for runtimes in [&[][..], &[30, 120, 50]] {
+            for runtime in runtimes {
+                state
+                    .named_metadata_or_insert_with("harness", HarnessTimingMetadata::default)
+                    .record(Duration::from_micros(*runtime), ExitKind::Ok);
+            }
+            let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+            state.set_corpus_id(id)?;
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+            let UserStatsValue::String(json) = manager.stats.last().unwrap().1.value() else {
+                panic!("The stats are not a JSON string");
+            };
+            stats.push(serde_json::from_str::<serde_json::Value>(json).unwrap());
+        }
+        assert!(stats[0]["harness_timing"].is_null());
+        assert_eq!(stats[1]["harness_timing"]["last_us"], 50);
+        assert_eq!(stats[1]["harness_timing"]["max_us"], 120);
+        assert_eq!(stats[1]["harness_timing"]["execs"], 3);
+        assert_eq!(stats[1]["harness_timing"]["execs_per_sec"], 15000.0);
+
+        Ok(())
+    }
+
+    /// The report breaks down the time measured by the introspection monitor
+    #[test]
+    #[cfg(all(feature = "std", feature = "introspection"))]
+    fn test_stats_stage_introspection() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = UserStatsRecorder {
+            stats: vec![],
+            phantom: PhantomData,
+        };
+        let mut stage = IntrospectionStatsStage::new(StatsStage::new(Duration::ZERO));
+
+        let monitor = state.introspection_monitor_mut();
+        monitor.set_current_time(libafl_bolts::cpu::read_time_counter() + 4_000_000);
+        let elapsed = monitor.elapsed_cycles();
+        monitor.update_scheduler(elapsed / 4);
+        monitor.update_feature(PerfFeature::Mutate, elapsed / 4);
+        monitor.update_feature(PerfFeature::TargetExecution, elapsed / 8);
+        monitor.finish_stage();
+        monitor.update_feature(PerfFeature::CmpObserverPostExec, elapsed / 8);
+
+        let id = state.corpus_mut().add(Testcase::new(NopInput {}))?;
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        let UserStatsValue::String(json) = manager.stats.last().unwrap().1.value() else {
+            panic!("The stats are not a JSON string");
+        };
+        let stats = serde_json::from_str::<serde_json::Value>(json).unwrap();
+        let breakdown = &stats["introspection"];
+        let assert_percent = |value: &serde_json::Value, expected: f64| {
+            assert!(
+                (value.as_f64().unwrap() - expected).abs() < 0.01,
+                "{value} != {expected}"
+            );
+        };
+        assert_percent(&breakdown["scheduler"], 25.0);
+        assert_percent(&breakdown["manager"], 0.0);
+        assert_percent(&breakdown["stages"]["0"]["Mutate"], 25.0);
+        assert_percent(&breakdown["stages"]["0"]["TargetExecution"], 12.5);
+        assert!(breakdown["stages"]["0"]["PreExec"].is_null());
+        assert_percent(&breakdown["stages"]["1"]["CmpObserverPostExec"], 12.5);
+        assert_percent(&breakdown["not_measured"], 25.0);
+
+        Ok(())
+    }
+
+    /// The average size accounts for each fuzzed testcase once, if the sizes are accounted for at all
+    #[test]
+    fn test_stats_stage_avg_corpus_size() -> Result<(), Error> {
+        let mut state = StdState::nop()?;
+        let mut fuzzer = NopFuzzer::new();
+        let mut executor = NopExecutor::new();
+        let mut manager = NopEventManager::new();
+        let mut stage = InputSizeStatsStage::new(StatsStage::new(Duration::ZERO));
+        let mut unsized_stage = StatsStage::new(Duration::ZERO);
+        assert_eq!(stage.inner().avg_corpus_size_bytes(), 0);
+
+        for len in [2, 4, 9] {
+            let id = state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(vec![0; len])))?;
+            state.set_corpus_id(id)?;
+            stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+            unsized_stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        }
+        assert_eq!(stage.inner().avg_corpus_size_bytes(), 5);
+        assert_eq!(stage.inner().snapshot().avg_corpus_size_bytes, 5);
+        assert_eq!(unsized_stage.avg_corpus_size_bytes(), 0);
+
+        // fuzzing a testcase again does not change the average
+        let id = state.corpus().first().unwrap();
+        state.corpus().get(id)?.borrow_mut().set_scheduled_count(1);
+        state.set_corpus_id(id)?;
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut manager)?;
+        assert_eq!(stage.inner().avg_corpus_size_bytes(), 5);
+
+        Ok(())
+    }
+
+    /// The status line is rewritten in place on terminals, and written line by line otherwise
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_stage_status_line() -> std::io::Result<()> {
+        let line = status_line(1234, 5, 4500, 12);
+        assert_eq!(
+            line,
+            "[corpus: 1234 | crashes: 5 | 4500 exec/s | last find 12s ago]"
+        );
+
+        let mut tty = Vec::new();
+        StatusLineMode::InPlace.write(&mut tty, &line)?;
+        StatusLineMode::InPlace.write(&mut tty, "[short]")?;
+        assert_eq!(tty, format!("\r{line}\x1b[K\r[short]\x1b[K").into_bytes());
+
+        let mut piped = Vec::new();
+        StatusLineMode::Lines.write(&mut piped, &line)?;
+        StatusLineMode::Lines.write(&mut piped, "[short]")?;
+        assert_eq!(piped, format!("{line}\n[short]\n").into_bytes());
+
+        let stage = StatsStage::<(), (), ()>::with_tty(Duration::ZERO);
+        assert!(stage.status_line.is_some());
+
+        Ok(())
     }
 }
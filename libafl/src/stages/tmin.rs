@@ -1,4 +1,5 @@
 //! The [`TMinMutationalStage`] is a stage which will attempt to minimize corpus entries.
+//! The [`MinimizationStage`] minimizes an input while preserving the objective it triggers, like `afl-tmin`.
 
 use alloc::{
     borrow::{Cow, ToOwned},
@@ -20,6 +21,7 @@ use crate::{
     events::EventFirer,
     executors::{ExitKind, HasObservers},
     feedbacks::{Feedback, FeedbackFactory, HasObserverHandle, StateInitializer},
+    fuzzer::HasObjective,
     inputs::UsesInput,
     mark_feature_time,
     mutators::{MutationResult, Mutator},
@@ -27,7 +29,7 @@ use crate::{
     schedulers::RemovableScheduler,
     stages::{
         mutational::{MutatedTransform, MutatedTransformPost},
-        ExecutionCountRestartHelper, Stage,
+        ExecutionCountRestartHelper, RetryCountRestartHelper, Stage,
     },
     start_timer,
     state::{
@@ -367,6 +369,211 @@ impl<E, EM, F, FF, IP, M, Z> StdTMinMutationalStage<E, EM, F, FF, IP, M, Z> {
     }
 }
 
+/// The counter for giving the [`MinimizationStage`] a unique id
+static mut MINIMIZATION_STAGE_ID: usize = 0;
+/// The name for the [`MinimizationStage`]
+pub static MINIMIZATION_STAGE_NAME: &str = "minimization";
+
+/// Default value, how many executions the [`MinimizationStage`] may do for a single input.
+pub const DEFAULT_MINIMIZATION_MAX_EXECUTIONS: usize = 4096;
+
+/// A stage which shrinks the current testcase while it keeps triggering the objective, like `afl-tmin`.
+///
+/// The current testcase is re-executed first. If it does not yield an objective (i.e., the crash is
+/// not reproducible), the stage gives up. Otherwise, the mutator (which should delete or truncate
+/// bytes) is applied over and over, and each shorter candidate is kept if its execution results in
+/// the same [`ExitKind`] and the objective feedback still considers it interesting.
+/// Minimization stops after `runs` consecutive attempts without any reduction,
+/// or after `max_executions` executions in total.
+/// The minimized input then replaces the testcase in the corpus.
+#[derive(Clone, Debug)]
+pub struct MinimizationStage<E, EM, M, Z> {
+    name: Cow<'static, str>,
+    mutator: M,
+    runs: usize,
+    max_executions: usize,
+    phantom: PhantomData<(E, EM, Z)>,
+}
+
+impl<E, EM, M, Z> UsesState for MinimizationStage<E, EM, M, Z>
+where
+    Z: UsesState,
+{
+    type State = Z::State;
+}
+
+impl<E, EM, M, Z> Named for MinimizationStage<E, EM, M, Z> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+impl<E, EM, M, Z> Stage<E, EM, Z> for MinimizationStage<E, EM, M, Z>
+where
+    Z: HasScheduler + HasObjective + ExecutesInput<E, EM>,
+    Z::Scheduler: RemovableScheduler<Self::Input, Self::State>,
+    Z::Objective: Feedback<EM, Self::Input, E::Observers, Self::State>,
+    E: HasObservers + UsesState<State = Z::State>,
+    E::Observers: ObserversTuple<Self::Input, Self::State>,
+    EM: UsesState<State = Self::State>,
+    M: Mutator<Self::Input, Self::State>,
+    Self::Input: Clone + HasLen,
+    Z::State: HasCorpus + HasCurrentTestcase + HasNamedMetadata,
+    <<Self as UsesState>::State as HasCorpus>::Corpus: Corpus<Input = Self::Input>, // delete me
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut Self::State,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let Some(base_corpus_id) = state.current_corpus_id()? else {
+            return Err(Error::illegal_state(
+                "state is not currently processing a corpus index",
+            ));
+        };
+
+        let orig = state.current_input_cloned()?;
+        let orig_len = orig.len();
+
+        let exit_kind = fuzzer.execute_input(state, executor, manager, &orig)?;
+        let mut executions = 1;
+        if !Self::is_objective(fuzzer, state, executor, manager, &orig, exit_kind)? {
+            log::info!(
+                "Input {base_corpus_id} does not reproduce the objective, not minimizing it"
+            );
+            return Ok(());
+        }
+
+        let mut base = orig;
+        let mut fails = 0;
+        while fails < self.runs && executions < self.max_executions {
+            fails += 1;
+
+            let mut input = base.clone();
+            if self.mutator.mutate(state, &mut input)? == MutationResult::Skipped
+                || input.len() >= base.len()
+            {
+                continue;
+            }
+
+            let new_exit_kind = fuzzer.execute_input(state, executor, manager, &input)?;
+            executions += 1;
+            let reduced = new_exit_kind == exit_kind
+                && Self::is_objective(fuzzer, state, executor, manager, &input, new_exit_kind)?;
+            self.mutator.post_exec(state, None)?;
+
+            if reduced {
+                base = input;
+                // maybe we can minimize further
+                fails = 0;
+            }
+        }
+
+        if base.len() < orig_len {
+            // re-run the minimized input, so the objective can attach its metadata to the testcase
+            let exit_kind = fuzzer.execute_input(state, executor, manager, &base)?;
+            let observers = executor.observers();
+            let reproduces = fuzzer.objective_mut().is_interesting(
+                state,
+                manager,
+                &base,
+                &*observers,
+                &exit_kind,
+            )?;
+            if !reproduces {
+                fuzzer.objective_mut().discard_metadata(state, &base)?;
+            }
+            let mut testcase = Testcase::from(base);
+            if reproduces {
+                fuzzer.objective_mut().append_metadata(
+                    state,
+                    manager,
+                    &*observers,
+                    &mut testcase,
+                )?;
+            }
+            let prev = state.corpus_mut().replace(base_corpus_id, testcase)?;
+            fuzzer
+                .scheduler_mut()
+                .on_replace(state, base_corpus_id, &prev)?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn should_restart(&mut self, state: &mut Self::State) -> Result<bool, Error> {
+        // Make sure we don't get stuck crashing on a single testcase
+        RetryCountRestartHelper::should_restart(state, &self.name, 3)
+    }
+
+    #[inline]
+    fn clear_progress(&mut self, state: &mut Self::State) -> Result<(), Error> {
+        RetryCountRestartHelper::clear_progress(state, &self.name)
+    }
+}
+
+impl<E, EM, M, Z> MinimizationStage<E, EM, M, Z>
+where
+    Z: HasObjective,
+    Z::Objective: Feedback<EM, Z::Input, E::Observers, Z::State>,
+    E: HasObservers,
+{
+    /// Checks if the last execution of `input` triggered the objective
+    ///
+    /// The input is never added to the solutions, so the metadata of the objective is discarded.
+    fn is_objective(
+        fuzzer: &mut Z,
+        state: &mut Z::State,
+        executor: &E,
+        manager: &mut EM,
+        input: &Z::Input,
+        exit_kind: ExitKind,
+    ) -> Result<bool, Error> {
+        let observers = executor.observers();
+        let res = fuzzer.objective_mut().is_interesting(
+            state,
+            manager,
+            input,
+            &*observers,
+            &exit_kind,
+        )?;
+        fuzzer.objective_mut().discard_metadata(state, input)?;
+        Ok(res)
+    }
+}
+
+impl<E, EM, M, Z> MinimizationStage<E, EM, M, Z> {
+    /// Creates a new [`MinimizationStage`] which gives up after `runs` consecutive attempts without
+    /// any reduction, or [`DEFAULT_MINIMIZATION_MAX_EXECUTIONS`] executions in total.
+    ///
+    /// The `mutator` should reduce the size of the input, larger inputs are ignored.
+    pub fn new(mutator: M, runs: usize) -> Self {
+        Self::with_max_executions(mutator, runs, DEFAULT_MINIMIZATION_MAX_EXECUTIONS)
+    }
+
+    /// Creates a new [`MinimizationStage`] that executes the target at most `max_executions` times per input
+    pub fn with_max_executions(mutator: M, runs: usize, max_executions: usize) -> Self {
+        // unsafe but impossible that you create two threads both instantiating this instance
+        let stage_id = unsafe {
+            let ret = MINIMIZATION_STAGE_ID;
+            MINIMIZATION_STAGE_ID += 1;
+            ret
+        };
+        Self {
+            name: Cow::Owned(
+                MINIMIZATION_STAGE_NAME.to_owned() + ":" + stage_id.to_string().as_str(),
+            ),
+            mutator,
+            runs,
+            max_executions,
+            phantom: PhantomData,
+        }
+    }
+}
+
 /// A feedback which checks if the hash of the currently observed map is equal to the original hash
 /// provided
 #[derive(Clone, Debug)]
@@ -477,3 +684,150 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, vec::Vec};
+
+    use libafl_bolts::{rands::StdRand, tuples::tuple_list, Error, Named};
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        corpus::{Corpus, HasCurrentCorpusId, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        feedbacks::{Feedback, StateInitializer},
+        inputs::{BytesInput, HasMutatorBytes},
+        mutators::BytesDeleteMutator,
+        schedulers::QueueScheduler,
+        stages::{MinimizationStage, Stage},
+        state::{HasCorpus, HasExecutions, StdState},
+        HasMetadata, StdFuzzer,
+    };
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct CrashLenMetadata {
+        len: usize,
+    }
+
+    libafl_bolts::impl_serdeany!(CrashLenMetadata);
+
+    /// A stateful crash objective, which records the length of the last crashing input until its
+    /// metadata is either appended or discarded.
+    #[derive(Debug, Default)]
+    struct CrashLenFeedback {
+        pending: Option<usize>,
+    }
+
+    impl Named for CrashLenFeedback {
+        fn name(&self) -> &Cow<'static, str> {
+            static NAME: Cow<'static, str> = Cow::Borrowed("CrashLenFeedback");
+            &NAME
+        }
+    }
+
+    impl<S> StateInitializer<S> for CrashLenFeedback {}
+
+    impl<EM, OT, S> Feedback<EM, BytesInput, OT, S> for CrashLenFeedback {
+        fn is_interesting(
+            &mut self,
+            _state: &mut S,
+            _manager: &mut EM,
+            input: &BytesInput,
+            _observers: &OT,
+            exit_kind: &ExitKind,
+        ) -> Result<bool, Error> {
+            assert!(self.pending.is_none(), "metadata of a previous run leaked");
+            if *exit_kind == ExitKind::Crash {
+                self.pending = Some(input.bytes().len());
+            }
+            Ok(self.pending.is_some())
+        }
+
+        fn append_metadata(
+            &mut self,
+            _state: &mut S,
+            _manager: &mut EM,
+            _observers: &OT,
+            testcase: &mut Testcase<BytesInput>,
+        ) -> Result<(), Error> {
+            let len = self.pending.take().unwrap();
+            testcase.add_metadata(CrashLenMetadata { len });
+            Ok(())
+        }
+
+        fn discard_metadata(&mut self, _state: &mut S, _input: &BytesInput) -> Result<(), Error> {
+            self.pending = None;
+            Ok(())
+        }
+    }
+
+    fn minimize(crashes: bool) -> Result<(Vec<u8>, u64), Error> {
+        let mut harness = |input: &BytesInput| {
+            if crashes && input.bytes().contains(&b'X') {
+                ExitKind::Crash
+            } else {
+                ExitKind::Ok
+            }
+        };
+
+        let mut corpus = InMemoryCorpus::new();
+        let corpus_id = corpus.add(Testcase::new(BytesInput::new(b"abcdXefgh".to_vec())))?;
+
+        let mut feedback = tuple_list!();
+        let mut objective = CrashLenFeedback::default();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )?;
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )?;
+        state.set_corpus_id(corpus_id)?;
+
+        let mut stage = MinimizationStage::new(BytesDeleteMutator::new(), 64);
+        stage.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr)?;
+
+        let minimized = state
+            .corpus()
+            .cloned_input_for_id(corpus_id)?
+            .bytes()
+            .to_vec();
+        if minimized.len() < 9 {
+            // the replaced testcase carries the metadata of the objective
+            let testcase = state.corpus().get(corpus_id)?.borrow();
+            assert_eq!(
+                testcase.metadata::<CrashLenMetadata>()?.len,
+                minimized.len()
+            );
+        }
+        Ok((minimized, *state.executions()))
+    }
+
+    #[test]
+    fn test_minimization_stage() -> Result<(), Error> {
+        let (minimized, _) = minimize(true)?;
+        // the `BytesDeleteMutator` does not shrink inputs of two bytes or less
+        assert_eq!(minimized.len(), 2);
+        assert!(minimized.contains(&b'X'));
+        Ok(())
+    }
+
+    /// If the objective does not reproduce, the input is left untouched
+    #[test]
+    fn test_minimization_stage_not_reproducible() -> Result<(), Error> {
+        let (minimized, executions) = minimize(false)?;
+        assert_eq!(minimized, b"abcdXefgh");
+        assert_eq!(executions, 1);
+        Ok(())
+    }
+}
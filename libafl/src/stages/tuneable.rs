@@ -13,7 +13,7 @@ use crate::{
     nonzero,
     stages::{
         mutational::{MutatedTransform, MutatedTransformPost, DEFAULT_MUTATIONAL_MAX_ITERATIONS},
-        ExecutionCountRestartHelper, MutationalStage, Stage,
+        ExecBudget, ExecutionCountRestartHelper, MutationalStage, Stage,
     },
     start_timer,
     state::{HasCorpus, HasCurrentTestcase, HasExecutions, HasRand, UsesState},
@@ -196,7 +196,9 @@ where
                 // perform n iterations or fuzz for provided time, whichever comes first
                 let start_time = current_time();
                 for _ in 1..=iters {
-                    if current_time() - start_time >= fuzz_time {
+                    if current_time() - start_time >= fuzz_time
+                        || ExecBudget::is_exhausted_in(state)
+                    {
                         break;
                     }
 
@@ -207,7 +209,9 @@ where
                 // fuzz for provided time
                 let start_time = current_time();
                 for _ in 1.. {
-                    if current_time() - start_time >= fuzz_time {
+                    if current_time() - start_time >= fuzz_time
+                        || ExecBudget::is_exhausted_in(state)
+                    {
                         break;
                     }
 
@@ -217,6 +221,9 @@ where
             (None, Some(iters)) => {
                 // perform n iterations
                 for _ in 1..=iters {
+                    if ExecBudget::is_exhausted_in(state) {
+                        break;
+                    }
                     self.perform_mutation(fuzzer, executor, state, manager, &input)?;
                 }
             }
@@ -226,6 +233,9 @@ where
                     .iterations(state)?
                     .saturating_sub(self.execs_since_progress_start(state)? as usize);
                 for _ in 1..=iters {
+                    if ExecBudget::is_exhausted_in(state) {
+                        break;
+                    }
                     self.perform_mutation(fuzzer, executor, state, manager, &input)?;
                 }
             }
@@ -246,7 +256,7 @@ where
     }
 
     /// Gets the number of iterations as a random number
-    fn iterations(&self, state: &mut Self::State) -> Result<usize, Error> {
+    fn iterations(&mut self, state: &mut Self::State) -> Result<usize, Error> {
         Ok(
             // fall back to random
             1 + state
@@ -466,6 +476,7 @@ where
         if mutated == MutationResult::Skipped {
             return Ok(());
         }
+        ExecBudget::spend_in(state, 1);
 
         // Time is measured directly the `evaluate_input` function
         let (untransformed, post) = input.try_transform_into(state)?;
@@ -265,6 +265,93 @@ pub fn hash_std(input: &[u8]) -> u64 {
     }
 }
 
+/// Encodes a mostly zeroed map, like a coverage map, in a compact, sparse form.
+///
+/// Only the runs of non-zero entries are stored, each with the number of zeroes before it.
+/// All numbers are `LEB128` encoded, so a map without any entries takes a few bytes only.
+/// Use [`decode_sparse`] to get the map back.
+#[cfg(feature = "alloc")]
+#[must_use]
+pub fn encode_sparse(map: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    push_leb128(&mut encoded, map.len());
+
+    let mut pos = 0;
+    while let Some(start) = map[pos..].iter().position(|&b| b != 0) {
+        let start = pos + start;
+        let end = map[start..]
+            .iter()
+            .position(|&b| b == 0)
+            .map_or(map.len(), |len| start + len);
+        push_leb128(&mut encoded, start - pos);
+        push_leb128(&mut encoded, end - start);
+        encoded.extend_from_slice(&map[start..end]);
+        pos = end;
+    }
+    encoded
+}
+
+/// Decodes a map encoded with [`encode_sparse`]
+///
+/// The encoded input is not trusted: maps longer than `max_len`, the size the caller expects
+/// at most, are rejected with an error before anything is allocated.
+#[cfg(feature = "alloc")]
+pub fn decode_sparse(encoded: &[u8], max_len: usize) -> Result<Vec<u8>, Error> {
+    let mut encoded = encoded;
+    let len = pop_leb128(&mut encoded)?;
+    if len > max_len {
+        return Err(Error::illegal_argument(format!(
+            "The sparse map is corrupted: length {len} exceeds the maximum of {max_len}"
+        )));
+    }
+    let mut map = Vec::with_capacity(len);
+
+    while !encoded.is_empty() {
+        let zeroes = pop_leb128(&mut encoded)?;
+        let run_len = pop_leb128(&mut encoded)?;
+        if run_len > encoded.len() || zeroes.saturating_add(run_len) > len - map.len() {
+            return Err(Error::illegal_argument(
+                "The sparse map is corrupted: a run is out of bounds",
+            ));
+        }
+        map.resize(map.len() + zeroes, 0);
+        map.extend_from_slice(&encoded[..run_len]);
+        encoded = &encoded[run_len..];
+    }
+    map.resize(len, 0);
+    Ok(map)
+}
+
+#[cfg(feature = "alloc")]
+fn push_leb128(buf: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        #[allow(clippy::cast_possible_truncation)]
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    buf.push(value as u8);
+}
+
+#[cfg(feature = "alloc")]
+fn pop_leb128(buf: &mut &[u8]) -> Result<usize, Error> {
+    let mut value: usize = 0;
+    for (i, &byte) in buf.iter().enumerate() {
+        let shift = 7 * i;
+        if shift >= usize::BITS as usize {
+            break;
+        }
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            *buf = &buf[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err(Error::illegal_argument(
+        "The sparse map is corrupted: invalid length",
+    ))
+}
+
 /// Main error struct for `LibAFL`
 #[derive(Debug)]
 pub enum Error {
@@ -1269,6 +1356,50 @@ mod tests {
 
     #[cfg(all(feature = "std", unix))]
     use crate::LIBAFL_RAWFD_LOGGER;
+    #[cfg(feature = "alloc")]
+    use crate::{decode_sparse, encode_sparse};
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_sparse_roundtrip() {
+        for map in [
+            vec![],
+            vec![0; 10],
+            vec![1, 2, 3],
+            vec![0, 0, 7, 0, 1, 1, 0],
+            vec![255; 300],
+        ] {
+            assert_eq!(decode_sparse(&encode_sparse(&map), map.len()).unwrap(), map);
+        }
+
+        // some garbage must not panic
+        assert!(decode_sparse(&[0xff], 1 << 16).is_err());
+        assert!(decode_sparse(&[4, 2, 3, 1], 1 << 16).is_err());
+        // a run past the end of the map
+        assert!(decode_sparse(&[4, 2, 3, 1, 1, 1], 1 << 16).is_err());
+        // huge lengths must neither abort nor allocate
+        let huge = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+        assert!(decode_sparse(&huge, 1 << 16).is_err());
+        assert!(decode_sparse(&[0x80, 0x80, 0x80, 0x80, 0x10], 1 << 16).is_err());
+        // a map longer than the caller expects
+        assert!(decode_sparse(&encode_sparse(&[1; 10]), 9).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_sparse_size() {
+        // a 64KiB edges map with a few hundred hit edges, some of them adjacent
+        let len = 1 << 16;
+        let mut map = vec![0_u8; len];
+        for i in 0..300 {
+            map[(i * 7919) % len] = 1;
+            map[(i * 7919 + 1) % len] = 4;
+        }
+
+        let encoded = encode_sparse(&map);
+        assert!(encoded.len() < map.len() / 32);
+        assert_eq!(decode_sparse(&encoded, len).unwrap(), map);
+    }
 
     #[test]
     #[cfg(all(unix, feature = "std"))]
@@ -16,6 +16,8 @@ fn enable_nightly() {}
 #[allow(clippy::too_many_lines)]
 fn main() {
     println!("cargo:rustc-check-cfg=cfg(nightly)");
+    // checked by `libafl_bolts::impl_serdeany`, never set for this crate
+    println!("cargo:rustc-check-cfg=cfg(feature, values(\"serdeany_autoreg\"))");
     enable_nightly();
     let out_dir = env::var_os("OUT_DIR").unwrap();
     let out_dir = out_dir.to_string_lossy().to_string();
@@ -45,6 +47,7 @@ fn main() {
         .expect("Could not parse LIBAFL_CMPLOG_MAP_W");
 
     let cmplog_map_h: usize = option_env!("LIBAFL_CMPLOG_MAP_H")
+        .or(option_env!("LIBAFL_CMP_MAP_H")) // the name of the AFL++ define
         .map_or(Ok(32), str::parse)
         .expect("Could not parse LIBAFL_CMPLOG_MAP_H");
 
@@ -56,8 +59,18 @@ fn main() {
         .map_or(Ok(SIXTY_FIVE_KB), str::parse)
         .expect("Could not parse LIBAFL_DDG_MAP_SIZE");
 
+    let trace_order_size: usize = option_env!("LIBAFL_TRACE_ORDER_SIZE")
+        .map_or(Ok(4096), str::parse)
+        .expect("Could not parse LIBAFL_TRACE_ORDER_SIZE");
+
     assert!(edges_map_default_size <= edges_map_allocated_size);
     assert!(edges_map_default_size.is_power_of_two());
+    assert!(trace_order_size > 0);
+    // the instrumentation wraps the logged executions with a mask
+    assert!(
+        cmplog_map_h.is_power_of_two(),
+        "LIBAFL_CMPLOG_MAP_H must be a power of two"
+    );
 
     write!(
         constants_file,
@@ -71,12 +84,19 @@ fn main() {
         pub const CMP_MAP_SIZE: usize = {cmp_map_size};
         /// The width of the `CmpLog` map
         pub const CMPLOG_MAP_W: usize = {cmplog_map_w};
-        /// The height of the `CmpLog` map
+        /// The height of the `CmpLog` map, i.e., the number of executions logged per cmp.
+        /// Set by `LIBAFL_CMPLOG_MAP_H` (or `LIBAFL_CMP_MAP_H`) at compile time, 32 by default, like in AFL++.
+        ///
+        /// The log of the maps grows linearly with it: each additional execution costs `CMPLOG_MAP_W` operands,
+        /// i.e., 4.5 MiB for the AFL++ map with the default width.
+        /// Maps written by unmodified AFL++ binaries always have a height of 32.
         pub const CMPLOG_MAP_H: usize = {cmplog_map_h};
         /// The size of the accounting maps
         pub const ACCOUNTING_MAP_SIZE: usize = {acc_map_size};
         /// The size of the accounting maps
-        pub const DDG_MAP_SIZE: usize = {ddg_map_size};        
+        pub const DDG_MAP_SIZE: usize = {ddg_map_size};
+        /// The number of edges kept in the `sancov_trace_order` ring buffer
+        pub const TRACE_ORDER_SIZE: usize = {trace_order_size};
 "
     )
     .expect("Could not write file");
@@ -88,8 +108,10 @@ fn main() {
     println!("cargo:rerun-if-env-changed=LIBAFL_CMP_MAP_SIZE");
     println!("cargo:rerun-if-env-changed=LIBAFL_CMPLOG_MAP_W");
     println!("cargo:rerun-if-env-changed=LIBAFL_CMPLOG_MAP_H");
+    println!("cargo:rerun-if-env-changed=LIBAFL_CMP_MAP_H");
     println!("cargo:rerun-if-env-changed=LIBAFL_ACCOUNTING_MAP_SIZE");
     println!("cargo:rerun-if-env-changed=LIBAFL_DDG_MAP_SIZE");
+    println!("cargo:rerun-if-env-changed=LIBAFL_TRACE_ORDER_SIZE");
 
     #[cfg(feature = "common")]
     {
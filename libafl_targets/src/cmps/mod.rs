@@ -4,19 +4,23 @@ pub use observers::*;
 
 /// cmp related stages
 pub mod stages;
-use alloc::{alloc::alloc_zeroed, boxed::Box, vec::Vec};
+use alloc::{alloc::alloc_zeroed, boxed::Box, format, vec::Vec};
+#[cfg(all(feature = "std", unix))]
+use core::ffi::c_void;
 use core::{
     alloc::Layout,
     fmt::{self, Debug, Formatter},
     mem::{size_of, zeroed},
     ptr, slice,
 };
+#[cfg(all(feature = "std", unix))]
+use std::{fs::File, os::fd::AsRawFd, path::Path};
 
 use libafl::{
-    observers::{cmp::AFLppCmpLogHeader, CmpMap, CmpValues, CmplogBytes},
+    observers::{cmp::AFLppCmpLogHeader, CmpKind, CmpMap, CmpValues, CmplogBytes},
     Error,
 };
-use libafl_bolts::HasLen;
+use libafl_bolts::{ownedref::OwnedMutPtr, shmem::ShMem, HasLen};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 pub use stages::*;
 
@@ -38,6 +42,9 @@ pub const CMPLOG_MAP_RTN_H: usize =
 pub const CMPLOG_MAP_RTN_EXTENDED_H: usize =
     CMPLOG_MAP_H * size_of::<AFLppCmpLogOperands>() / size_of::<AFLppCmpLogFnOperands>();
 
+/// The number of executions AFL++ logs per cmp, i.e., its `CMP_MAP_H`, see [`AFLppCmpMap`]
+pub const AFLPP_CMPLOG_MAP_H: usize = 32;
+
 /// `CmpLog` instruction kind
 pub const CMPLOG_KIND_INS: u8 = 0;
 /// `CmpLog` routine kind
@@ -163,6 +170,26 @@ impl AFLppCmpLogOperands {
         self.v1_128
     }
 
+    #[must_use]
+    /// The bytes of the 256bit first cmp operand, in little endian order
+    pub fn v0_256(&self) -> [u8; 32] {
+        Self::le_bytes_256([self.v0, self.v0_128, self.v0_256_0, self.v0_256_1])
+    }
+
+    #[must_use]
+    /// The bytes of the 256bit second cmp operand, in little endian order
+    pub fn v1_256(&self) -> [u8; 32] {
+        Self::le_bytes_256([self.v1, self.v1_128, self.v1_256_0, self.v1_256_1])
+    }
+
+    fn le_bytes_256(words: [u64; 4]) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (chunk, word) in bytes.chunks_exact_mut(8).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
     /// Set the v0 (left) side of the comparison
     pub fn set_v0(&mut self, v0: u64) {
         self.v0 = v0;
@@ -410,6 +437,15 @@ impl CmpMap for CmpLogMap {
         }
     }
 
+    fn raw_operands_of(&self, idx: usize, execution: usize) -> Option<(u64, u64)> {
+        if self.headers[idx].kind == CMPLOG_KIND_INS {
+            let operands = unsafe { &self.vals.operands[idx][execution] };
+            Some((operands.0, operands.1))
+        } else {
+            None
+        }
+    }
+
     fn reset(&mut self) -> Result<(), Error> {
         // For performance, we reset just the headers
         self.headers.fill(CmpLogHeader {
@@ -420,6 +456,30 @@ impl CmpMap for CmpLogMap {
 
         Ok(())
     }
+
+    fn kind_of(&self, idx: usize) -> CmpKind {
+        if self.headers[idx].kind == CMPLOG_KIND_INS {
+            CmpKind::Instruction
+        } else {
+            CmpKind::Routine
+        }
+    }
+
+    fn reset_used(&mut self, count: usize) -> Result<(), Error> {
+        let count = count.min(self.headers.len());
+        self.headers[..count].fill(CmpLogHeader {
+            hits: 0,
+            shape: 0,
+            kind: 0,
+        });
+
+        Ok(())
+    }
+
+    fn snapshot_into(&self, dst: &mut Self) -> Result<(), Error> {
+        *dst = *self;
+        Ok(())
+    }
 }
 
 /// The global `CmpLog` map for the current `LibAFL` run.
@@ -487,6 +547,16 @@ impl AFLppCmpLogMap {
         }
     }
 
+    #[must_use]
+    /// Copy this map into a new boxed `AFLppCmpLogMap`, e.g., to keep the cmps logged in a shared memory
+    /// for the original input while the target overwrites them with the ones of a mutated input.
+    pub fn boxed_snapshot(&self) -> Box<Self> {
+        let mut snapshot = Self::boxed();
+        // can't fail for this map
+        let _ = self.snapshot_into(&mut snapshot);
+        snapshot
+    }
+
     #[must_use]
     /// Handle the headers for the map
     pub fn headers(&self) -> &[AFLppCmpLogHeader] {
@@ -510,6 +580,28 @@ impl AFLppCmpLogMap {
     pub fn values_mut(&mut self) -> &mut AFLppCmpLogVals {
         &mut self.vals
     }
+
+    #[must_use]
+    /// Checks if the operands logged for the `execution` of the cmp at `idx` fit into the operand size
+    /// given by the `shape` of its header. Otherwise, [`CmpMap::values_of`] does not return any values for it.
+    ///
+    /// A mismatch usually means that the instrumentation does not match the map layout.
+    /// Shapes wider than 8 bytes can't be checked and always match, even if [`CmpMap::values_of`]
+    /// does not support them.
+    pub fn shape_matches(&self, idx: usize, execution: usize) -> bool {
+        if self.headers[idx]._type() != CMPLOG_KIND_INS {
+            return true;
+        }
+        let operands = &self.vals.operands()[idx][execution];
+        match self.headers[idx].shape() {
+            // odd sizes, e.g., 3 bytes for shape 2, are valid, too. For the 8 bytes of shape 7, this is trivially true
+            shape @ 0..=7 => {
+                let max = u64::MAX >> (64 - 8 * (shape + 1));
+                operands.v0() <= max && operands.v1() <= max
+            }
+            _ => true,
+        }
+    }
 }
 
 impl Serialize for AFLppCmpLogMap {
@@ -559,6 +651,9 @@ impl CmpMap for AFLppCmpLogMap {
 
     fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues> {
         if self.headers[idx]._type() == CMPLOG_KIND_INS {
+            if !self.shape_matches(idx, execution) {
+                return None;
+            }
             unsafe {
                 match self.headers[idx].shape() {
                     0 => Some(CmpValues::U8((
@@ -581,7 +676,16 @@ impl CmpMap for AFLppCmpLogMap {
                         self.vals.operands[idx][execution].v1,
                         false,
                     ))),
-                    // TODO handle 128 bits & 256 bits cmps
+                    // Wider than 128 bits, e.g., AVX vector cmps
+                    31 => {
+                        let operands = &self.vals.operands[idx][execution];
+                        Some(CmpValues::Vector {
+                            width: 256,
+                            v0: operands.v0_256().to_vec(),
+                            v1: operands.v1_256().to_vec(),
+                        })
+                    }
+                    // TODO handle 128 bits cmps
                     // other => panic!("Invalid CmpLog shape {}", other),
                     _ => None,
                 }
@@ -598,10 +702,403 @@ impl CmpMap for AFLppCmpLogMap {
         }
     }
 
+    fn raw_operands_of(&self, idx: usize, execution: usize) -> Option<(u64, u64)> {
+        if self.headers[idx]._type() == CMPLOG_KIND_INS {
+            let operands = &self.vals.operands()[idx][execution];
+            Some((operands.v0(), operands.v1()))
+        } else {
+            None
+        }
+    }
+
     fn reset(&mut self) -> Result<(), Error> {
         // For performance, we reset just the headers
         self.headers.fill(AFLppCmpLogHeader { data: [0; 2] });
 
         Ok(())
     }
+
+    fn kind_of(&self, idx: usize) -> CmpKind {
+        if self.headers[idx]._type() == CMPLOG_KIND_INS {
+            CmpKind::Instruction
+        } else {
+            CmpKind::Routine
+        }
+    }
+
+    fn reset_used(&mut self, count: usize) -> Result<(), Error> {
+        let count = count.min(self.headers.len());
+        self.headers[..count].fill(AFLppCmpLogHeader { data: [0; 2] });
+
+        Ok(())
+    }
+
+    fn snapshot_into(&self, dst: &mut Self) -> Result<(), Error> {
+        // the map is too large to be copied through the stack
+        unsafe {
+            ptr::copy_nonoverlapping(ptr::from_ref(self), ptr::from_mut(dst), 1);
+        }
+        Ok(())
+    }
+}
+
+/// A [`CmpMap`] over an [`AFLppCmpLogMap`] that lives outside of `LibAFL`, e.g., the classic AFL++ `struct cmp_map`
+/// in the shared memory populated by an unmodified AFL++ cmplog binary.
+///
+/// The headers are decoded from their packed bitfield layout, see [`AFLppCmpLogHeader`].
+/// AFL++ always logs [`AFLPP_CMPLOG_MAP_H`] executions per cmp, so [`crate::CMPLOG_MAP_H`] has to keep its default to read such maps.
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct AFLppCmpMap {
+    map: OwnedMutPtr<AFLppCmpLogMap>,
+}
+
+impl AFLppCmpMap {
+    /// Creates a new [`AFLppCmpMap`] from a raw pointer to an AFL++ `struct cmp_map`
+    ///
+    /// # Errors
+    /// Returns an error if this crate was built with a [`crate::CMPLOG_MAP_H`] other than [`AFLPP_CMPLOG_MAP_H`].
+    ///
+    /// # Safety
+    /// The pointer must point to a valid, AFL++ cmp map, which must outlive this [`AFLppCmpMap`].
+    pub unsafe fn from_mut_ptr(map: *mut AFLppCmpLogMap) -> Result<Self, Error> {
+        if CMPLOG_MAP_H != AFLPP_CMPLOG_MAP_H {
+            return Err(Error::illegal_state(format!(
+                "The layout of an AFL++ cmp map needs CMPLOG_MAP_H = {AFLPP_CMPLOG_MAP_H}, not {CMPLOG_MAP_H}"
+            )));
+        }
+        Ok(Self {
+            map: OwnedMutPtr::from_raw_mut(map),
+        })
+    }
+
+    /// Creates a new [`AFLppCmpMap`] on the given shared memory, e.g., the one passed to an AFL++ cmplog binary
+    /// in the `__AFL_CMPLOG_SHM_ID` environment variable.
+    ///
+    /// # Errors
+    /// Returns an error if the shared memory is too small, or for a [`crate::CMPLOG_MAP_H`] other than [`AFLPP_CMPLOG_MAP_H`].
+    ///
+    /// # Safety
+    /// The shared memory must outlive this [`AFLppCmpMap`].
+    pub unsafe fn from_shmem<SHM: ShMem>(shmem: &mut SHM) -> Result<Self, Error> {
+        let map = shmem.as_mut_ptr_of::<AFLppCmpLogMap>().ok_or_else(|| {
+            Error::illegal_argument(format!(
+                "The shared memory is too small for an AFL++ cmp map ({} < {} bytes)",
+                shmem.len(),
+                size_of::<AFLppCmpLogMap>()
+            ))
+        })?;
+        Self::from_mut_ptr(map)
+    }
+
+    /// The underlying [`AFLppCmpLogMap`]
+    #[must_use]
+    pub fn map(&self) -> &AFLppCmpLogMap {
+        self.map.as_ref()
+    }
+
+    /// The underlying [`AFLppCmpLogMap`] (mutable)
+    pub fn map_mut(&mut self) -> &mut AFLppCmpLogMap {
+        self.map.as_mut()
+    }
+}
+
+impl CmpMap for AFLppCmpMap {
+    fn len(&self) -> usize {
+        CmpMap::len(self.map())
+    }
+
+    fn executions_for(&self, idx: usize) -> usize {
+        self.map().executions_for(idx)
+    }
+
+    fn usable_executions_for(&self, idx: usize) -> usize {
+        self.map().usable_executions_for(idx)
+    }
+
+    fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues> {
+        self.map().values_of(idx, execution)
+    }
+
+    fn raw_operands_of(&self, idx: usize, execution: usize) -> Option<(u64, u64)> {
+        self.map().raw_operands_of(idx, execution)
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        self.map_mut().reset()
+    }
+
+    fn kind_of(&self, idx: usize) -> CmpKind {
+        self.map().kind_of(idx)
+    }
+
+    fn reset_used(&mut self, count: usize) -> Result<(), Error> {
+        self.map_mut().reset_used(count)
+    }
+
+    /// Copies the underlying map into the one of `dst`, e.g., into an owned map of [`AFLppCmpLogMap::boxed_snapshot`]
+    fn snapshot_into(&self, dst: &mut Self) -> Result<(), Error> {
+        self.map().snapshot_into(dst.map_mut())
+    }
+}
+
+/// A read-only [`CmpMap`] over an AFL++ `struct cmp_map` captured to a file, e.g., to inspect the
+/// comparisons of an instrumented run offline, with the same [`libafl::observers::CmpValuesMetadata::add_from`]
+/// extraction as during fuzzing.
+///
+/// The file is memory-mapped and needs the layout of an [`AFLppCmpLogMap`], built with the same
+/// [`CMPLOG_MAP_W`] and [`CMPLOG_MAP_H`] as this crate. The map cannot be reset.
+#[cfg(all(feature = "std", unix))]
+#[derive(Debug)]
+pub struct FileCmpMap {
+    map: *const AFLppCmpLogMap,
+}
+
+#[cfg(all(feature = "std", unix))]
+impl FileCmpMap {
+    /// Maps the cmp map captured in the file at `path`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be mapped, does not have the size of an [`AFLppCmpLogMap`]
+    /// with this [`CMPLOG_MAP_H`], or is corrupt, i.e., logs routine operands longer than [`CMPLOG_RTN_LEN`].
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        // A map captured with another `CMPLOG_MAP_H` has another size, and its cmps would be misread
+        if len != size_of::<AFLppCmpLogMap>() as u64 {
+            return Err(Error::illegal_argument(format!(
+                "The file {} is not an AFL++ cmp map with CMPLOG_MAP_W = {CMPLOG_MAP_W} and CMPLOG_MAP_H = {CMPLOG_MAP_H} ({len} != {} bytes)",
+                path.display(),
+                size_of::<AFLppCmpLogMap>()
+            )));
+        }
+
+        // The mapping stays valid after the file is closed
+        let map = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                size_of::<AFLppCmpLogMap>(),
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            return Err(Error::last_os_error(format!(
+                "Could not map the cmp map file {}",
+                path.display()
+            )));
+        }
+        let map = Self {
+            map: map as *const AFLppCmpLogMap,
+        };
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// The underlying [`AFLppCmpLogMap`]
+    #[must_use]
+    pub fn map(&self) -> &AFLppCmpLogMap {
+        // Safe, the map is mapped (read-only) for the lifetime of self
+        unsafe { &*self.map }
+    }
+
+    /// Checks that the logged routine operands fit into their buffers, so that reading them later on does not fail
+    fn validate(&self) -> Result<(), Error> {
+        let map = self.map();
+        for idx in 0..CMPLOG_MAP_W {
+            if map.kind_of(idx) != CmpKind::Routine {
+                continue;
+            }
+            for execution in 0..map.usable_executions_for(idx) {
+                let operands = &map.vals.fn_operands()[idx][execution];
+                let v0_len = usize::from(operands.v0_len() & (0x80 - 1));
+                let v1_len = usize::from(operands.v1_len() & (0x80 - 1));
+                if v0_len > CMPLOG_RTN_LEN || v1_len > CMPLOG_RTN_LEN {
+                    return Err(Error::illegal_argument(format!(
+                        "Corrupt cmp map: the routine operands of cmp {idx} are too long ({v0_len} and {v1_len} bytes)"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl Drop for FileCmpMap {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.map as *mut c_void, size_of::<AFLppCmpLogMap>());
+        }
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl CmpMap for FileCmpMap {
+    fn len(&self) -> usize {
+        CmpMap::len(self.map())
+    }
+
+    fn executions_for(&self, idx: usize) -> usize {
+        self.map().executions_for(idx)
+    }
+
+    fn usable_executions_for(&self, idx: usize) -> usize {
+        self.map().usable_executions_for(idx)
+    }
+
+    fn values_of(&self, idx: usize, execution: usize) -> Option<CmpValues> {
+        self.map().values_of(idx, execution)
+    }
+
+    fn raw_operands_of(&self, idx: usize, execution: usize) -> Option<(u64, u64)> {
+        self.map().raw_operands_of(idx, execution)
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Err(Error::illegal_state(
+            "a cmp map mapped from a file cannot be reset",
+        ))
+    }
+
+    fn kind_of(&self, idx: usize) -> CmpKind {
+        self.map().kind_of(idx)
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl HasLen for FileCmpMap {
+    fn len(&self) -> usize {
+        CMPLOG_MAP_W
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use libafl::observers::cmp::{AFLppCmpLogHeader, CmpMap, CmpValues};
+
+    use super::{AFLppCmpLogMap, AFLppCmpLogOperands, CMPLOG_KIND_INS, CMPLOG_KIND_RTN};
+
+    #[test]
+    fn test_aflpp_shape_matches() {
+        let mut map = AFLppCmpLogMap::boxed();
+        let mut set = |idx: usize, shape: u32, v0: u64, v1: u64| {
+            let mut header = AFLppCmpLogHeader { data: [0; 2] };
+            header.set_hits(1);
+            header.set_shape(shape);
+            header.set__type(CMPLOG_KIND_INS);
+            map.headers_mut()[idx] = header;
+            map.values_mut().operands_mut()[idx][0] = AFLppCmpLogOperands::new(v0, v1);
+        };
+        // u8, u16 and u32 operands have to fit
+        set(0, 0, 0xff, 1);
+        set(1, 0, 0x100, 1);
+        set(2, 1, 0xffff, 1);
+        set(3, 3, 1, 0x1_0000_0000);
+        // every u64 fits
+        set(4, 7, u64::MAX, 0);
+        // 128 and 256 bits
+        set(5, 15, u64::MAX, u64::MAX);
+        set(6, 31, u64::MAX, u64::MAX);
+        // odd sizes, here 3 bytes
+        set(7, 2, 0xff_ffff, 1);
+        set(8, 2, 0x100_0000, 1);
+
+        let matches: Vec<bool> = (0..9).map(|idx| map.shape_matches(idx, 0)).collect();
+        assert_eq!(
+            matches,
+            [true, false, true, false, true, true, true, true, false]
+        );
+        assert_eq!(
+            map.values_of(4, 0),
+            Some(CmpValues::U64((u64::MAX, 0, false)))
+        );
+        assert_eq!(map.values_of(1, 0), None);
+        // matching, but not supported
+        assert_eq!(map.values_of(7, 0), None);
+
+        // the shape of routines is not checked
+        map.headers_mut()[1].set__type(CMPLOG_KIND_RTN);
+        assert!(map.shape_matches(1, 0));
+    }
+
+    /// Only files of the exact size of the map with this `CMPLOG_MAP_H` are mapped
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn test_file_cmp_map() {
+        use core::{mem::size_of, ptr, slice};
+        use std::{env, fs, process};
+
+        use super::FileCmpMap;
+
+        let mut map = AFLppCmpLogMap::boxed();
+        let mut header = AFLppCmpLogHeader { data: [0; 2] };
+        header.set_hits(1);
+        header.set_shape(3);
+        header.set__type(CMPLOG_KIND_INS);
+        map.headers_mut()[0] = header;
+        map.values_mut().operands_mut()[0][0] = AFLppCmpLogOperands::new(0x1234, 0x5678);
+        let bytes = unsafe {
+            slice::from_raw_parts(
+                ptr::from_ref(&*map).cast::<u8>(),
+                size_of::<AFLppCmpLogMap>(),
+            )
+        };
+
+        let dir = env::temp_dir().join(format!("libafl_file_cmp_map_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let valid = dir.join("valid");
+        fs::write(&valid, bytes).unwrap();
+        let truncated = dir.join("truncated");
+        fs::write(&truncated, &bytes[..bytes.len() - 1]).unwrap();
+        let oversized = dir.join("oversized");
+        fs::write(&oversized, [bytes, &[0]].concat()).unwrap();
+
+        let file_map = FileCmpMap::open(&valid).unwrap();
+        assert_eq!(
+            file_map.values_of(0, 0),
+            Some(CmpValues::U32((0x1234, 0x5678, false)))
+        );
+        assert!(FileCmpMap::open(&truncated).is_err());
+        assert!(FileCmpMap::open(&oversized).is_err());
+
+        drop(file_map);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Reads and resets an AFL++ cmp map in external memory, if `CMPLOG_MAP_H` matches
+    #[test]
+    fn test_aflpp_cmp_map() {
+        use core::ptr;
+
+        use super::{AFLppCmpMap, AFLPP_CMPLOG_MAP_H};
+        use crate::CMPLOG_MAP_H;
+
+        let mut map = AFLppCmpLogMap::boxed();
+        let Ok(mut cmp_map) = (unsafe { AFLppCmpMap::from_mut_ptr(ptr::from_mut(&mut *map)) })
+        else {
+            assert_ne!(CMPLOG_MAP_H, AFLPP_CMPLOG_MAP_H);
+            return;
+        };
+
+        let mut header = AFLppCmpLogHeader { data: [0; 2] };
+        header.set_hits(2);
+        header.set_shape(1);
+        header.set__type(CMPLOG_KIND_INS);
+        cmp_map.map_mut().headers_mut()[3] = header;
+        cmp_map.map_mut().values_mut().operands_mut()[3][1] = AFLppCmpLogOperands::new(7, 8);
+
+        assert_eq!(cmp_map.executions_for(3), 2);
+        assert_eq!(cmp_map.values_of(3, 1), Some(CmpValues::U16((7, 8, false))));
+        cmp_map.reset().unwrap();
+        assert_eq!(cmp_map.executions_for(3), 0);
+    }
 }
@@ -192,6 +192,7 @@ impl<'a> AFLppCmpLogObserver<'a> {
             meta.orig_cmpvals.clear();
             // Clear headers
             meta.headers.clear();
+            meta.hit_buckets.clear();
         } else {
             // If this observer is for the mutated input
             meta.new_cmpvals.clear();
@@ -216,7 +217,7 @@ pub fn add_to_aflpp_cmp_metadata(
         if execs > 0 {
             if original {
                 // Update header
-                meta.headers.push((i, cmp_map.headers[i]));
+                meta.push_header(i, cmp_map.headers[i]);
             }
 
             // Recongize loops and discard if needed
@@ -265,6 +266,10 @@ pub fn add_to_aflpp_cmp_metadata(
                 // push into orig_cmpvals
                 // println!("Adding to orig_cmpvals");
                 for j in 0..execs {
+                    if !cmp_map.shape_matches(i, j) {
+                        reject_aflpp_cmp_entry(meta, i);
+                        continue;
+                    }
                     if let Some(val) = cmp_map.values_of(i, j) {
                         cmp_values.push(val);
                     }
@@ -287,6 +292,10 @@ pub fn add_to_aflpp_cmp_metadata(
                 }
                 */
                 for j in 0..execs {
+                    if !cmp_map.shape_matches(i, j) {
+                        reject_aflpp_cmp_entry(meta, i);
+                        continue;
+                    }
                     if let Some(val) = cmp_map.values_of(i, j) {
                         cmp_values.push(val);
                     }
@@ -297,3 +306,13 @@ pub fn add_to_aflpp_cmp_metadata(
         }
     }
 }
+
+/// Count an entry with a `shape` that does not match its operands, warning about the first one
+fn reject_aflpp_cmp_entry(meta: &mut AFLppCmpValuesMetadata, idx: usize) {
+    if meta.rejected_entries == 0 {
+        log::warn!(
+            "The operands of cmp {idx} do not match the shape of its header, is the cmplog instrumentation compatible with this map?"
+        );
+    }
+    meta.rejected_entries += 1;
+}
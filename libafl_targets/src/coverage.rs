@@ -12,6 +12,10 @@ use alloc::borrow::Cow;
 #[cfg(any(target_os = "linux", target_vendor = "apple"))]
 use libafl::{mutators::Tokens, Error};
 
+/// Buckets raw hitcounts into the AFL classes, e.g., for the [`EDGES_MAP`] incremented by the
+/// `sancov_pcguard_hitcounts` runtime
+pub use libafl::observers::{classify_counts, COUNT_CLASS_LOOKUP};
+
 use crate::{ACCOUNTING_MAP_SIZE, DDG_MAP_SIZE, EDGES_MAP_ALLOCATED_SIZE, EDGES_MAP_DEFAULT_SIZE};
 
 /// The map for edges.
@@ -38,6 +42,23 @@ pub use __afl_acc_memop_ptr_local as ACCOUNTING_MEMOP_MAP;
 /// You can use this for the initial map size for the observer only if you compute this time at compilation time.
 pub static mut MAX_EDGES_FOUND: usize = 0;
 
+/// Serializes the tests that write to the global coverage state, e.g., the edges map, [`MAX_EDGES_FOUND`],
+/// or the ctx and ngram state of the instrumentation, as the tests run in parallel.
+#[cfg(all(test, feature = "std"))]
+#[cfg(any(
+    feature = "sancov_pcguard_edges",
+    feature = "sancov_pcguard_hitcounts",
+    feature = "sancov_ngram4",
+    feature = "sancov_ngram8",
+    feature = "sancov_ctx"
+))]
+pub(crate) fn lock_coverage_state() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    // a failing test must not fail all the others
+    LOCK.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
 extern "C" {
     /// The area pointer points to the edges map.
     pub static mut __afl_area_ptr: *mut u8;
@@ -89,7 +110,7 @@ pub static mut __afl_map_size: usize = EDGES_MAP_DEFAULT_SIZE;
     feature = "sancov_ngram8",
     feature = "sancov_ctx"
 ))]
-use libafl::observers::StdMapObserver;
+use libafl::observers::{HitcountsMapObserver, StdMapObserver};
 #[cfg(any(
     feature = "sancov_pcguard_edges",
     feature = "sancov_pcguard_hitcounts",
@@ -159,6 +180,27 @@ where
     StdMapObserver::from_mut_slice(name, edges_map_mut_slice())
 }
 
+/// Gets a new [`HitcountsMapObserver`] over the [`std_edges_map_observer`], bucketing the hitcounts
+/// of the edges map into the AFL classes in its `post_exec`, see [`classify_counts`].
+///
+/// # Safety
+/// This will dereference [`edges_map_mut_ptr`] and crash if it is not a valid address.
+#[cfg(any(
+    feature = "sancov_pcguard_edges",
+    feature = "sancov_pcguard_hitcounts",
+    feature = "sancov_ngram4",
+    feature = "sancov_ngram8",
+    feature = "sancov_ctx"
+))]
+pub unsafe fn hitcounts_edges_map_observer<'a, S>(
+    name: S,
+) -> HitcountsMapObserver<StdMapObserver<'a, u8, false>>
+where
+    S: Into<Cow<'static, str>>,
+{
+    HitcountsMapObserver::new(std_edges_map_observer(name))
+}
+
 /// Gets the current edges map pt
 /// It will usually take `EDGES_MAP`, but `EDGES_MAP_PTR`,
 /// if built with the `pointer_maps` feature.
@@ -184,20 +226,460 @@ pub fn edges_map_mut_ptr() -> *mut u8 {
 ))]
 #[must_use]
 pub fn edges_max_num() -> usize {
+    let used = edges_map_used();
+    if used > 0 {
+        used
+    } else {
+        edges_map_capacity()
+    }
+}
+
+/// Gets the number of edges registered by the instrumentation so far, i.e., [`MAX_EDGES_FOUND`].
+///
+/// This is the used portion at the start of the edges map, or `0` if the edges are not counted at runtime.
+#[must_use]
+pub fn edges_map_used() -> usize {
+    unsafe { MAX_EDGES_FOUND }
+}
+
+/// Gets the number of entries the edges map can hold.
+///
+/// With the `pointer_maps` feature, this is the upper bound [`EDGES_MAP_ALLOCATED_SIZE`],
+/// since the size of the map behind `EDGES_MAP_PTR` is not known.
+#[must_use]
+pub fn edges_map_capacity() -> usize {
+    #[cfg(feature = "pointer_maps")]
+    {
+        EDGES_MAP_ALLOCATED_SIZE
+    }
+    #[cfg(not(feature = "pointer_maps"))]
     unsafe {
-        if MAX_EDGES_FOUND > 0 {
-            MAX_EDGES_FOUND
-        } else {
-            #[cfg(feature = "pointer_maps")]
-            {
-                EDGES_MAP_ALLOCATED_SIZE // the upper bound
+        let edges_map_ptr = &raw const EDGES_MAP;
+        (*edges_map_ptr).len()
+    }
+}
+
+#[cfg(any(
+    feature = "sancov_pcguard_edges",
+    feature = "sancov_pcguard_hitcounts",
+    feature = "sancov_ngram4",
+    feature = "sancov_ngram8",
+    feature = "sancov_ctx"
+))]
+pub use snapshot::*;
+
+#[cfg(any(
+    feature = "sancov_pcguard_edges",
+    feature = "sancov_pcguard_hitcounts",
+    feature = "sancov_ngram4",
+    feature = "sancov_ngram8",
+    feature = "sancov_ctx"
+))]
+mod snapshot {
+    use alloc::{borrow::Cow, vec::Vec};
+    use core::{marker::PhantomData, slice};
+
+    use libafl::{
+        executors::{hooks::ExecutorHook, ExitKind, HasObservers},
+        inputs::{HasTargetBytes, UsesInput},
+        observers::Observer,
+        Error,
+    };
+    #[cfg(doc)]
+    use libafl_bolts::encode_sparse;
+    use libafl_bolts::{hash_std, AsSlice, Named};
+    use serde::{Deserialize, Serialize};
+
+    use super::{edges_map_mut_ptr, edges_map_used, edges_max_num};
+
+    /// (De)serializes a map snapshot in its sparse encoding
+    mod sparse_map {
+        use alloc::vec::Vec;
+
+        use libafl_bolts::{decode_sparse, encode_sparse};
+        use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+        use crate::coverage::edges_map_capacity;
+
+        pub(super) fn serialize<S>(map: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            encode_sparse(map).serialize(serializer)
+        }
+
+        pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let encoded = Vec::<u8>::deserialize(deserializer)?;
+            // a snapshot never holds more entries than the edges map
+            decode_sparse(&encoded, edges_map_capacity()).map_err(de::Error::custom)
+        }
+    }
+
+    /// Observer that snapshots the edges map after each execution, to detect nondeterministic
+    /// coverage: if the same input is executed twice in a row and the edges map differs,
+    /// the run is marked as diverged and the differing indices are recorded.
+    /// If the map grew in between, only the common prefix is compared, see [`MapSnapshotObserver::len_change`].
+    ///
+    /// The map is read through [`edges_map_mut_ptr`] and [`edges_max_num`], so it follows
+    /// `EDGES_MAP_PTR` with the `pointer_maps` feature, and the `MAX_EDGES_FOUND` length.
+    ///
+    /// When the observer is serialized, e.g., to be sent with an event, the snapshot is
+    /// compressed with [`encode_sparse`], as most of its entries are zero.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct MapSnapshotObserver {
+        name: Cow<'static, str>,
+        last_input_hash: Option<u64>,
+        last_map_hash: u64,
+        #[serde(with = "sparse_map")]
+        last_map: Vec<u8>,
+        diverged: bool,
+        diverging_indices: Vec<usize>,
+        len_change: Option<(usize, usize)>,
+    }
+
+    impl MapSnapshotObserver {
+        /// Create a new [`MapSnapshotObserver`] with the given name
+        #[must_use]
+        pub fn new<N>(name: N) -> Self
+        where
+            N: Into<Cow<'static, str>>,
+        {
+            Self {
+                name: name.into(),
+                last_input_hash: None,
+                last_map_hash: 0,
+                last_map: Vec::new(),
+                diverged: false,
+                diverging_indices: Vec::new(),
+                len_change: None,
             }
-            #[cfg(not(feature = "pointer_maps"))]
-            {
-                let edges_map_ptr = &raw const EDGES_MAP;
-                (*edges_map_ptr).len()
+        }
+
+        /// Whether the last execution produced a different edges map than the previous
+        /// execution of the same input
+        #[must_use]
+        pub fn diverged(&self) -> bool {
+            self.diverged
+        }
+
+        /// The indices of the edges map that differed in the last diverged execution
+        #[must_use]
+        pub fn diverging_indices(&self) -> &[usize] {
+            &self.diverging_indices
+        }
+
+        /// The previous and the current length of the edges map, if it changed between the last two
+        /// executions of the same input, e.g., as `MAX_EDGES_FOUND` grew.
+        ///
+        /// Only the entries within both lengths are compared, so a length change alone is no divergence.
+        #[must_use]
+        pub fn len_change(&self) -> Option<(usize, usize)> {
+            self.len_change
+        }
+
+        /// The snapshot of the edges map taken after the last execution
+        #[must_use]
+        pub fn last_map(&self) -> &[u8] {
+            &self.last_map
+        }
+    }
+
+    impl Named for MapSnapshotObserver {
+        fn name(&self) -> &Cow<'static, str> {
+            &self.name
+        }
+    }
+
+    impl<I, S> Observer<I, S> for MapSnapshotObserver
+    where
+        I: HasTargetBytes,
+    {
+        fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+            self.diverged = false;
+            self.diverging_indices.clear();
+            self.len_change = None;
+            Ok(())
+        }
+
+        fn post_exec(
+            &mut self,
+            _state: &mut S,
+            input: &I,
+            _exit_kind: &ExitKind,
+        ) -> Result<(), Error> {
+            // # Safety
+            // The edges map pointer is valid for at least `edges_max_num` entries.
+            let map = unsafe { slice::from_raw_parts(edges_map_mut_ptr(), edges_max_num()) };
+            let input_hash = hash_std(input.target_bytes().as_slice());
+            let map_hash = hash_std(map);
+
+            if self.last_input_hash == Some(input_hash) {
+                // the map may have grown in between (`MAX_EDGES_FOUND`)
+                if map.len() != self.last_map.len() {
+                    self.len_change = Some((self.last_map.len(), map.len()));
+                }
+                if self.len_change.is_some() || self.last_map_hash != map_hash {
+                    // `zip` stops at the common length
+                    self.diverging_indices.extend(
+                        map.iter()
+                            .zip(self.last_map.iter())
+                            .enumerate()
+                            .filter(|(_, (cur, last))| cur != last)
+                            .map(|(i, _)| i),
+                    );
+                    self.diverged = !self.diverging_indices.is_empty();
+                }
+            }
+
+            self.last_input_hash = Some(input_hash);
+            self.last_map_hash = map_hash;
+            self.last_map.clear();
+            self.last_map.extend_from_slice(map);
+            Ok(())
+        }
+    }
+
+    /// Hook that records the edges set by each execution that were not set in a baseline, e.g., to
+    /// reward inputs reaching edges the base input did not reach, in directed fuzzing.
+    ///
+    /// The baseline is taken explicitly, with [`DeltaCoverageHook::capture_baseline`] after running
+    /// the base input, or given with [`DeltaCoverageHook::set_baseline`]. It is kept for all later
+    /// executions: the map observers reset the map before the executor hooks run, so a snapshot in
+    /// `pre_exec` would always be empty. Without a baseline, every edge set by an execution is new.
+    ///
+    /// The map is read through [`edges_map_mut_ptr`] and [`edges_map_used`], so it follows
+    /// `EDGES_MAP_PTR` with the `pointer_maps` feature, and the `MAX_EDGES_FOUND` length.
+    #[derive(Debug)]
+    pub struct DeltaCoverageHook<S> {
+        baseline: Vec<u8>,
+        new_edges: Vec<usize>,
+        phantom: PhantomData<S>,
+    }
+
+    impl<S> DeltaCoverageHook<S> {
+        /// The constructor for this struct
+        #[must_use]
+        pub fn new() -> Self {
+            Self {
+                baseline: Vec::new(),
+                new_edges: Vec::new(),
+                phantom: PhantomData,
             }
         }
+
+        /// The edges map indices set by the last execution that were not set before it
+        pub fn new_edges(&self) -> impl Iterator<Item = usize> + '_ {
+            self.new_edges.iter().copied()
+        }
+
+        /// The baseline the executions are compared to
+        #[must_use]
+        pub fn baseline(&self) -> &[u8] {
+            &self.baseline
+        }
+
+        /// Takes the current edges map as the baseline, e.g., right after running the base input
+        pub fn capture_baseline(&mut self) {
+            // # Safety
+            // The edges map pointer is valid for at least `edges_map_used` entries.
+            let map = unsafe { slice::from_raw_parts(edges_map_mut_ptr(), edges_map_used()) };
+            self.set_baseline(map);
+        }
+
+        /// Sets the baseline the executions are compared to, indexed like the edges map
+        pub fn set_baseline(&mut self, baseline: &[u8]) {
+            self.baseline.clear();
+            self.baseline.extend_from_slice(baseline);
+        }
+    }
+
+    impl<S> Default for DeltaCoverageHook<S> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<S> ExecutorHook<S> for DeltaCoverageHook<S>
+    where
+        S: UsesInput,
+    {
+        fn init<E: HasObservers>(&mut self, _state: &mut S) {}
+
+        fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) {
+            self.new_edges.clear();
+        }
+
+        fn post_exec(&mut self, _state: &mut S, _input: &S::Input) {
+            // # Safety
+            // The edges map pointer is valid for at least `edges_map_used` entries.
+            let map = unsafe { slice::from_raw_parts(edges_map_mut_ptr(), edges_map_used()) };
+            // the map may have grown since the baseline was taken (`MAX_EDGES_FOUND`)
+            self.new_edges.extend(
+                map.iter()
+                    .enumerate()
+                    .filter(|(i, hits)| {
+                        **hits != 0 && self.baseline.get(*i).copied().unwrap_or(0) == 0
+                    })
+                    .map(|(i, _)| i),
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "std"))]
+    mod tests {
+        use alloc::vec::Vec;
+
+        use libafl::{
+            corpus::InMemoryCorpus,
+            events::NopEventManager,
+            executors::{inprocess::GenericInProcessExecutor, ExitKind},
+            fuzzer::StdFuzzer,
+            inputs::{BytesInput, HasTargetBytes},
+            observers::{Observer, StdMapObserver},
+            schedulers::QueueScheduler,
+            state::StdState,
+            Error,
+        };
+        use libafl_bolts::{rands::StdRand, tuples::tuple_list, AsSlice};
+
+        use super::{DeltaCoverageHook, MapSnapshotObserver};
+        use crate::coverage::{edges_map_mut_ptr, MAX_EDGES_FOUND};
+
+        fn run(observer: &mut MapSnapshotObserver, input: &BytesInput) {
+            Observer::<BytesInput, ()>::pre_exec(observer, &mut (), input).unwrap();
+            Observer::<BytesInput, ()>::post_exec(observer, &mut (), input, &ExitKind::Ok).unwrap();
+        }
+
+        #[test]
+        fn test_map_snapshot_observer() {
+            let _lock = crate::coverage::lock_coverage_state();
+            let map = edges_map_mut_ptr();
+            let input = BytesInput::new(b"a".to_vec());
+            let mut observer = MapSnapshotObserver::new("snapshot");
+
+            unsafe {
+                MAX_EDGES_FOUND = 16;
+                *map.add(10) = 1;
+            }
+            run(&mut observer, &input);
+            assert!(!observer.diverged());
+
+            // the map grew, with an edge in the new tail: no divergence
+            unsafe {
+                MAX_EDGES_FOUND = 24;
+                *map.add(20) = 1;
+            }
+            run(&mut observer, &input);
+            assert!(!observer.diverged());
+            assert!(observer.diverging_indices().is_empty());
+            assert_eq!(observer.len_change(), Some((16, 24)));
+
+            unsafe {
+                *map.add(11) = 1;
+            }
+            run(&mut observer, &input);
+            assert!(observer.diverged());
+            assert_eq!(observer.diverging_indices(), [11]);
+            assert_eq!(observer.len_change(), None);
+
+            // another input may cover different edges
+            unsafe {
+                *map.add(11) = 0;
+            }
+            run(&mut observer, &BytesInput::new(b"b".to_vec()));
+            assert!(!observer.diverged());
+
+            unsafe {
+                *map.add(10) = 0;
+                *map.add(20) = 0;
+                MAX_EDGES_FOUND = 0;
+            }
+        }
+
+        /// Only the edges not hit by the base input are new, including the ones in a grown map
+        #[test]
+        fn test_delta_coverage_hook() -> Result<(), Error> {
+            let _lock = crate::coverage::lock_coverage_state();
+            unsafe {
+                MAX_EDGES_FOUND = 16;
+            }
+            // every byte of the input is an edge
+            let harness: &mut dyn FnMut(&BytesInput) -> ExitKind = &mut |input: &BytesInput| {
+                for edge in input.target_bytes().as_slice() {
+                    unsafe {
+                        *edges_map_mut_ptr().add(usize::from(*edge)) = 1;
+                        MAX_EDGES_FOUND = MAX_EDGES_FOUND.max(usize::from(*edge) + 1);
+                    }
+                }
+                ExitKind::Ok
+            };
+            let observer =
+                unsafe { StdMapObserver::from_mut_ptr("edges", edges_map_mut_ptr(), 32) };
+
+            let mut feedback = tuple_list!();
+            let mut objective = tuple_list!();
+            let mut state = StdState::new(
+                StdRand::with_seed(0),
+                InMemoryCorpus::<BytesInput>::new(),
+                InMemoryCorpus::new(),
+                &mut feedback,
+                &mut objective,
+            )?;
+            let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+            let mut mgr = NopEventManager::new();
+            let mut executor = GenericInProcessExecutor::<
+                dyn FnMut(&BytesInput) -> ExitKind,
+                _,
+                _,
+                _,
+                _,
+            >::generic(
+                tuple_list!(DeltaCoverageHook::new()),
+                harness,
+                tuple_list!(observer),
+                &mut fuzzer,
+                &mut state,
+                &mut mgr,
+            )?;
+
+            fuzzer.execute_input(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                &BytesInput::new(vec![4]),
+            )?;
+            executor.hooks_mut().1 .0.capture_baseline();
+            assert_eq!(executor.hooks().1 .0.baseline().len(), 16);
+            assert_eq!(executor.hooks().1 .0.baseline()[4], 1);
+
+            let input = BytesInput::new(vec![4, 5, 18]);
+            fuzzer.execute_input(&mut state, &mut executor, &mut mgr, &input)?;
+            assert_eq!(
+                executor.hooks().1 .0.new_edges().collect::<Vec<_>>(),
+                [5, 18]
+            );
+
+            // the baseline is kept, even if the map was reset for the next execution
+            let input = BytesInput::new(vec![4, 5]);
+            fuzzer.execute_input(&mut state, &mut executor, &mut mgr, &input)?;
+            assert_eq!(executor.hooks().1 .0.new_edges().collect::<Vec<_>>(), [5]);
+
+            executor.hooks_mut().1 .0.set_baseline(&[]);
+            fuzzer.execute_input(&mut state, &mut executor, &mut mgr, &input)?;
+            assert_eq!(
+                executor.hooks().1 .0.new_edges().collect::<Vec<_>>(),
+                [4, 5]
+            );
+
+            unsafe {
+                edges_map_mut_ptr().write_bytes(0, 32);
+                MAX_EDGES_FOUND = 0;
+            }
+            Ok(())
+        }
     }
 }
 
@@ -305,3 +787,41 @@ mod swap {
         }
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+#[cfg(any(
+    feature = "sancov_pcguard_edges",
+    feature = "sancov_pcguard_hitcounts",
+    feature = "sancov_ngram4",
+    feature = "sancov_ngram8",
+    feature = "sancov_ctx"
+))]
+mod tests {
+    use super::{
+        edges_map_capacity, edges_map_used, edges_max_num, lock_coverage_state, MAX_EDGES_FOUND,
+    };
+    use crate::EDGES_MAP_ALLOCATED_SIZE;
+
+    /// Until edges are registered, the whole map is tracked
+    #[test]
+    fn test_edges_map_used() {
+        let _lock = lock_coverage_state();
+        assert_eq!(edges_map_capacity(), EDGES_MAP_ALLOCATED_SIZE);
+
+        unsafe {
+            MAX_EDGES_FOUND = 0;
+        }
+        assert_eq!(edges_map_used(), 0);
+        assert_eq!(edges_max_num(), EDGES_MAP_ALLOCATED_SIZE);
+
+        unsafe {
+            MAX_EDGES_FOUND = 42;
+        }
+        assert_eq!(edges_map_used(), 42);
+        assert_eq!(edges_max_num(), 42);
+
+        unsafe {
+            MAX_EDGES_FOUND = 0;
+        }
+    }
+}
@@ -1,4 +1,13 @@
 //! Sanitizer Coverage comparison functions
+//!
+//! The `__sanitizer_cov_trace_cmp*`, `__sanitizer_cov_trace_const_cmp*` and `__sanitizer_cov_trace_switch`
+//! callbacks emitted for `-fsanitize-coverage=trace-cmp` are defined in `sancov_cmp.c`.
+//! With the `sancov_cmplog` feature, they log the comparison operands to the cmplog map, so that a target
+//! compiled with `trace-cmp` instead of a cmplog pass can be observed with the
+//! [`CmpLogObserver`](crate::CmpLogObserver) as usual.
+//! For a switch, each case is logged as a separate comparison of the switch value with the (constant)
+//! case value, at the width of the switch value.
+//! With the `sancov_value_profile` feature, they update the value profile map instead (or as well).
 
 use core::{
     cmp,
@@ -1,5 +1,7 @@
 //! [`LLVM` `PcGuard`](https://clang.llvm.org/docs/SanitizerCoverage.html#tracing-pcs-with-guards) runtime for `LibAFL`.
 
+#[cfg(feature = "sancov_ctx_ngram_split")]
+use alloc::borrow::Cow;
 #[rustversion::nightly]
 #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
 use core::simd::num::SimdUint;
@@ -10,7 +12,12 @@ use core::{mem::align_of, slice};
     feature = "sancov_ctx",
     feature = "sancov_ngram8"
 ))]
-use libafl::executors::{hooks::ExecutorHook, HasObservers};
+use libafl::executors::{
+    hooks::{ExecutorHook, COVERAGE_RESET_HOOK_PRIORITY},
+    HasObservers,
+};
+#[cfg(feature = "sancov_ctx_ngram_split")]
+use libafl::observers::StdMapObserver;
 
 #[cfg(any(
     feature = "pointer_maps",
@@ -25,6 +32,8 @@ use crate::coverage::MAX_EDGES_FOUND;
 #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
 #[allow(unused)]
 use crate::EDGES_MAP_DEFAULT_SIZE;
+#[cfg(feature = "sancov_trace_order")]
+use crate::TRACE_ORDER_SIZE;
 #[cfg(feature = "pointer_maps")]
 use crate::{coverage::EDGES_MAP_PTR, EDGES_MAP_ALLOCATED_SIZE};
 
@@ -34,6 +43,16 @@ compile_error!(
     "the libafl_targets `sancov_pcguard_edges` and `sancov_pcguard_hitcounts` features are mutually exclusive."
 );
 
+#[cfg(feature = "sancov_ctx_ngram_split")]
+#[cfg(not(all(
+    feature = "sancov_ctx",
+    any(feature = "sancov_ngram4", feature = "sancov_ngram8")
+)))]
+#[cfg(not(any(doc, feature = "clippy")))]
+compile_error!(
+    "the libafl_targets `sancov_ctx_ngram_split` feature requires `sancov_ctx` and one of `sancov_ngram4` or `sancov_ngram8`."
+);
+
 #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
 #[allow(unused)]
 use core::ops::ShlAssign;
@@ -46,27 +65,89 @@ type Ngram4 = core::simd::u32x4;
 #[rustversion::nightly]
 type Ngram8 = core::simd::u32x8;
 
+#[cfg(feature = "sancov_ngram4")]
+#[rustversion::not(nightly)]
+type Ngram4 = ScalarNgram<4>;
+
+#[cfg(feature = "sancov_ngram8")]
+#[rustversion::not(nightly)]
+type Ngram8 = ScalarNgram<8>;
+
+/// The previous locs of the ngram instrumentation on stable Rust, where `core::simd` is not available.
+///
+/// Provides the subset of the `core::simd::Simd` API used by the instrumentation, with the same results,
+/// so the ngram coverage does not depend on the toolchain; it is only slower.
+#[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+#[rustversion::not(nightly)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScalarNgram<const N: usize>([u32; N]);
+
+#[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+#[rustversion::not(nightly)]
+impl<const N: usize> ScalarNgram<N> {
+    /// Creates the ngram from its lanes
+    #[must_use]
+    pub const fn from_array(array: [u32; N]) -> Self {
+        Self(array)
+    }
+
+    /// The lanes of the ngram
+    #[must_use]
+    pub const fn as_array(&self) -> &[u32; N] {
+        &self.0
+    }
+
+    /// The mutable lanes of the ngram
+    #[must_use]
+    pub fn as_mut_array(&mut self) -> &mut [u32; N] {
+        &mut self.0
+    }
+
+    /// Rotates the lanes right by `OFFSET`, like `core::simd::Simd::rotate_elements_right`
+    #[must_use]
+    pub fn rotate_elements_right<const OFFSET: usize>(self) -> Self {
+        let mut array = self.0;
+        array.rotate_right(OFFSET % N);
+        Self(array)
+    }
+
+    /// The xor of all lanes
+    #[must_use]
+    pub fn reduce_xor(self) -> u32 {
+        self.0.iter().fold(0, |acc, lane| acc ^ lane)
+    }
+}
+
+#[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+#[rustversion::not(nightly)]
+impl<const N: usize> ShlAssign for ScalarNgram<N> {
+    /// Shifts each lane left by the corresponding lane of `rhs`, wrapping the shift amount like `core::simd`
+    fn shl_assign(&mut self, rhs: Self) {
+        for (lane, shift) in self.0.iter_mut().zip(rhs.0) {
+            *lane = lane.wrapping_shl(shift);
+        }
+    }
+}
+
 /// The array holding the previous locs. This is required for NGRAM-4 instrumentation
 #[cfg(feature = "sancov_ngram4")]
-#[rustversion::nightly]
 pub static mut PREV_ARRAY_4: Ngram4 = Ngram4::from_array([0, 0, 0, 0]);
 
 /// The array holding the previous locs. This is required for NGRAM-4 instrumentation
 #[cfg(feature = "sancov_ngram8")]
-#[rustversion::nightly]
 pub static mut PREV_ARRAY_8: Ngram8 = Ngram8::from_array([0, 0, 0, 0, 0, 0, 0, 0]);
 
 /// We shift each of the values in ngram4 everytime we see new edges
 #[cfg(feature = "sancov_ngram4")]
-#[rustversion::nightly]
 pub static SHR_4: Ngram4 = Ngram4::from_array([1, 1, 1, 1]);
 
 /// We shift each of the values in ngram8 everytime we see new edges
 #[cfg(feature = "sancov_ngram8")]
-#[rustversion::nightly]
 pub static SHR_8: Ngram8 = Ngram8::from_array([1, 1, 1, 1, 1, 1, 1, 1]);
 
 static mut PC_TABLES: Vec<&'static [PcTableEntry]> = Vec::new();
+/// The edges map index of the first guard of each of the [`PC_TABLES`]
+static mut PC_TABLE_GUARD_BASES: Vec<usize> = Vec::new();
 
 use alloc::vec::Vec;
 #[cfg(any(
@@ -77,20 +158,31 @@ use alloc::vec::Vec;
 use core::marker::PhantomData;
 
 /// The hook to initialize ngram everytime we run the harness
+///
+/// It runs before other hooks, see [`COVERAGE_RESET_HOOK_PRIORITY`].
+///
+/// For persistent harnesses that run a batch of inputs per execution, the reset can be turned off
+/// with [`NgramHook::reset_each_exec`].
 #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
-#[rustversion::nightly]
 #[derive(Debug, Clone, Copy)]
 pub struct NgramHook<S>
 where
     S: libafl::inputs::UsesInput,
 {
+    reset_each_exec: bool,
     phantom: PhantomData<S>,
 }
 
 /// The hook to initialize ctx everytime we run the harness
+///
+/// It runs before other hooks, see [`COVERAGE_RESET_HOOK_PRIORITY`].
+///
+/// For persistent harnesses that run a batch of inputs per execution, the reset can be turned off
+/// with [`CtxHook::reset_each_exec`].
 #[cfg(feature = "sancov_ctx")]
 #[derive(Debug, Clone, Copy)]
 pub struct CtxHook<S> {
+    reset_each_exec: bool,
     phantom: PhantomData<S>,
 }
 
@@ -103,9 +195,22 @@ where
     #[must_use]
     pub fn new() -> Self {
         Self {
+            reset_each_exec: true,
             phantom: PhantomData,
         }
     }
+
+    /// Whether to reset the ctx before each execution, `true` by default.
+    ///
+    /// If `false`, the ctx carries over from one execution to the next, so the same edges
+    /// may be reported under a different ctx (and be considered new) depending on the previous
+    /// executions. Only turn this off if the context should accumulate over a batch of inputs,
+    /// and reset the ctx yourself at the start of each batch.
+    #[must_use]
+    pub fn reset_each_exec(mut self, reset_each_exec: bool) -> Self {
+        self.reset_each_exec = reset_each_exec;
+        self
+    }
 }
 
 #[cfg(feature = "sancov_ctx")]
@@ -119,28 +224,154 @@ where
 }
 
 #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
-#[rustversion::nightly]
 impl<S> ExecutorHook<S> for NgramHook<S>
 where
     S: libafl::inputs::UsesInput,
 {
     fn init<E: HasObservers>(&mut self, _state: &mut S) {}
     fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) {
-        #[cfg(feature = "sancov_ngram4")]
-        unsafe {
-            PREV_ARRAY_4 = Ngram4::from_array([0, 0, 0, 0]);
+        if self.reset_each_exec {
+            reset_ngram();
         }
+    }
+    fn post_exec(&mut self, _state: &mut S, _input: &S::Input) {}
+
+    fn priority(&self) -> i32 {
+        COVERAGE_RESET_HOOK_PRIORITY
+    }
+}
+
+/// Resets the previous locs of the ngram instrumentation
+#[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+#[inline]
+fn reset_ngram() {
+    #[cfg(feature = "sancov_ngram4")]
+    unsafe {
+        PREV_ARRAY_4 = Ngram4::from_array([0, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "sancov_ngram8")]
+    unsafe {
+        PREV_ARRAY_8 = Ngram8::from_array([0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+}
+
+/// Returns the previous locs of the ngram instrumentation, e.g., to snapshot the ngram history
+/// in a test harness and restore it later with [`set_ngram_state`].
+///
+/// If both `sancov_ngram4` and `sancov_ngram8` are enabled, the locs of [`PREV_ARRAY_4`] come first.
+#[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+#[must_use]
+pub fn ngram_state() -> Vec<u32> {
+    let mut state = Vec::new();
+    #[cfg(feature = "sancov_ngram4")]
+    unsafe {
+        let prev_array_4_ptr = &raw const PREV_ARRAY_4;
+        state.extend_from_slice((*prev_array_4_ptr).as_array());
+    }
+    #[cfg(feature = "sancov_ngram8")]
+    unsafe {
+        let prev_array_8_ptr = &raw const PREV_ARRAY_8;
+        state.extend_from_slice((*prev_array_8_ptr).as_array());
+    }
+    state
+}
+
+/// Sets the previous locs of the ngram instrumentation, e.g., to start the ngram history of an
+/// execution from a known state, as returned by [`ngram_state`].
+///
+/// Missing values are set to `0`, additional values are ignored.
+/// Note that the [`NgramHook`] resets the state before each execution.
+///
+/// # Safety
+/// Writes to the global ngram state, which must not be updated by the instrumentation concurrently.
+#[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+pub unsafe fn set_ngram_state(values: &[u32]) {
+    let mut values = values.iter().copied();
+    #[cfg(feature = "sancov_ngram4")]
+    {
+        PREV_ARRAY_4 = Ngram4::from_array(core::array::from_fn(|_| values.next().unwrap_or(0)));
+    }
+    #[cfg(feature = "sancov_ngram8")]
+    {
+        PREV_ARRAY_8 = Ngram8::from_array(core::array::from_fn(|_| values.next().unwrap_or(0)));
+    }
+}
+
+/// The hook to reset all the coverage state (ctx and ngram, depending on the enabled features)
+/// everytime we run the harness.
+///
+/// This replaces registering both a [`CtxHook`] and a `NgramHook`.
+/// It runs before other hooks, see [`COVERAGE_RESET_HOOK_PRIORITY`].
+#[cfg(any(
+    feature = "sancov_ngram4",
+    feature = "sancov_ctx",
+    feature = "sancov_ngram8"
+))]
+#[derive(Debug, Clone, Copy)]
+pub struct CoverageStateResetHook<S> {
+    phantom: PhantomData<S>,
+}
 
-        #[cfg(feature = "sancov_ngram8")]
+#[cfg(any(
+    feature = "sancov_ngram4",
+    feature = "sancov_ctx",
+    feature = "sancov_ngram8"
+))]
+impl<S> CoverageStateResetHook<S>
+where
+    S: libafl::inputs::UsesInput,
+{
+    /// The constructor for this struct
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "sancov_ngram4",
+    feature = "sancov_ctx",
+    feature = "sancov_ngram8"
+))]
+impl<S> Default for CoverageStateResetHook<S>
+where
+    S: libafl::inputs::UsesInput,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(
+    feature = "sancov_ngram4",
+    feature = "sancov_ctx",
+    feature = "sancov_ngram8"
+))]
+impl<S> ExecutorHook<S> for CoverageStateResetHook<S>
+where
+    S: libafl::inputs::UsesInput,
+{
+    fn init<E: HasObservers>(&mut self, _state: &mut S) {}
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) {
+        #[cfg(feature = "sancov_ctx")]
         unsafe {
-            PREV_ARRAY_8 = Ngram8::from_array([0, 0, 0, 0, 0, 0, 0, 0]);
+            __afl_prev_ctx = 0;
         }
+
+        #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+        reset_ngram();
     }
     fn post_exec(&mut self, _state: &mut S, _input: &S::Input) {}
+
+    fn priority(&self) -> i32 {
+        COVERAGE_RESET_HOOK_PRIORITY
+    }
 }
 
 #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
-#[rustversion::nightly]
 impl<S> NgramHook<S>
 where
     S: libafl::inputs::UsesInput,
@@ -149,13 +380,25 @@ where
     #[must_use]
     pub fn new() -> Self {
         Self {
+            reset_each_exec: true,
             phantom: PhantomData,
         }
     }
+
+    /// Whether to reset the previous locs before each execution, `true` by default.
+    ///
+    /// If `false`, the ngram history carries over from one execution to the next, so which edges
+    /// are new depends on the previous executions, and the coverage of an input may not be
+    /// reproducible on its own. Only turn this off if the ngram context should accumulate over
+    /// a batch of inputs, and reset it (e.g., with [`set_ngram_state`]) at the start of each batch.
+    #[must_use]
+    pub fn reset_each_exec(mut self, reset_each_exec: bool) -> Self {
+        self.reset_each_exec = reset_each_exec;
+        self
+    }
 }
 
 #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
-#[rustversion::nightly]
 impl<S> Default for NgramHook<S>
 where
     S: libafl::inputs::UsesInput,
@@ -172,14 +415,22 @@ where
 {
     fn init<E: HasObservers>(&mut self, _state: &mut S) {}
     fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) {
-        unsafe {
-            __afl_prev_ctx = 0;
+        if self.reset_each_exec {
+            unsafe {
+                __afl_prev_ctx = 0;
+            }
         }
     }
     fn post_exec(&mut self, _state: &mut S, _input: &S::Input) {}
+
+    fn priority(&self) -> i32 {
+        COVERAGE_RESET_HOOK_PRIORITY
+    }
 }
 
-#[rustversion::nightly]
+/// Shifts `pos` into the previous locs and returns their hash, the position in the edges map.
+///
+/// Uses `core::simd` on nightly, and the equivalent `ScalarNgram` on stable.
 #[allow(unused)]
 #[inline]
 #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
@@ -207,10 +458,48 @@ unsafe fn update_ngram(pos: usize) -> usize {
     reduced
 }
 
-#[rustversion::not(nightly)]
-#[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
-unsafe fn update_ngram(pos: usize) -> usize {
-    pos
+/// The ring buffer holding the last [`TRACE_ORDER_SIZE`] hit edges, for the `sancov_trace_order` feature.
+/// Use [`trace_order`] to read it in order.
+#[cfg(feature = "sancov_trace_order")]
+pub static mut TRACE_ORDER: [usize; TRACE_ORDER_SIZE] = [0; TRACE_ORDER_SIZE];
+
+/// The number of edges recorded to [`TRACE_ORDER`] since the last [`reset_trace_order`]
+#[cfg(feature = "sancov_trace_order")]
+pub static mut TRACE_ORDER_COUNT: usize = 0;
+
+/// Returns the edges recorded by the `sancov_trace_order` feature, oldest first.
+///
+/// Only the last [`TRACE_ORDER_SIZE`] edges are kept; set the `LIBAFL_TRACE_ORDER_SIZE` env
+/// at compile time to change it (default: 4096).
+/// The recorded positions are the indices written to the edges map, i.e., after `ngram` or `ctx` hashing.
+#[cfg(feature = "sancov_trace_order")]
+#[must_use]
+pub fn trace_order() -> Vec<usize> {
+    // # Safety
+    // The buffer is only written by the instrumentation of the (single-threaded) target.
+    unsafe {
+        let trace_order_ptr = &raw const TRACE_ORDER;
+        let trace_order = &*trace_order_ptr;
+        let count = TRACE_ORDER_COUNT;
+        if count <= TRACE_ORDER_SIZE {
+            trace_order[..count].to_vec()
+        } else {
+            let start = count % TRACE_ORDER_SIZE;
+            let mut edges = trace_order[start..].to_vec();
+            edges.extend_from_slice(&trace_order[..start]);
+            edges
+        }
+    }
+}
+
+/// Clears the edges recorded by the `sancov_trace_order` feature, e.g., before each execution.
+#[cfg(feature = "sancov_trace_order")]
+pub fn reset_trace_order() {
+    // # Safety
+    // The buffer is only written by the instrumentation of the (single-threaded) target.
+    unsafe {
+        TRACE_ORDER_COUNT = 0;
+    }
 }
 
 extern "C" {
@@ -218,29 +507,43 @@ extern "C" {
     pub static mut __afl_prev_ctx: u32;
 }
 
-/// Callback for sancov `pc_guard` - usually called by `llvm` on each block or edge.
+/// The size of each half of the edges map with the `sancov_ctx_ngram_split` feature.
 ///
-/// # Safety
-/// Dereferences `guard`, reads the position from there, then dereferences the [`EDGES_MAP`] at that position.
-/// Should usually not be called directly.
-#[no_mangle]
-#[allow(unused_assignments)]
-pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard(guard: *mut u32) {
-    #[allow(unused_mut)]
-    let mut pos = *guard as usize;
+/// The `ngram`-adjusted positions are mapped to the first half, `[0, CTX_NGRAM_SPLIT_SIZE)`,
+/// and the `ctx`-adjusted positions to the second half, `[CTX_NGRAM_SPLIT_SIZE, 2 * CTX_NGRAM_SPLIT_SIZE)`,
+/// so that both schemes contribute their novelty independently, see [`ctx_ngram_split_observers`].
+#[cfg(feature = "sancov_ctx_ngram_split")]
+pub const CTX_NGRAM_SPLIT_SIZE: usize = EDGES_MAP_DEFAULT_SIZE / 2;
 
-    #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
-    {
-        pos = update_ngram(pos);
-        // println!("Wrinting to {} {}", pos, EDGES_MAP_DEFAULT_SIZE);
-    }
-
-    #[cfg(feature = "sancov_ctx")]
-    {
-        pos ^= __afl_prev_ctx as usize;
-        // println!("Wrinting to {} {}", pos, EDGES_MAP_DEFAULT_SIZE);
-    }
+/// Creates two observers for the halves of the edges map written with the `sancov_ctx_ngram_split` feature,
+/// the first one for the `ngram` coverage, the second one for the `ctx` coverage, see [`CTX_NGRAM_SPLIT_SIZE`].
+///
+/// # Safety
+/// This will dereference [`edges_map_mut_ptr`](crate::coverage::edges_map_mut_ptr) and crash if it is not a valid address.
+#[cfg(feature = "sancov_ctx_ngram_split")]
+pub unsafe fn ctx_ngram_split_observers<'a, S1, S2>(
+    ngram_name: S1,
+    ctx_name: S2,
+) -> (StdMapObserver<'a, u8, false>, StdMapObserver<'a, u8, false>)
+where
+    S1: Into<Cow<'static, str>>,
+    S2: Into<Cow<'static, str>>,
+{
+    let map_ptr = crate::coverage::edges_map_mut_ptr();
+    (
+        StdMapObserver::from_mut_ptr(ngram_name, map_ptr, CTX_NGRAM_SPLIT_SIZE),
+        StdMapObserver::from_mut_ptr(
+            ctx_name,
+            map_ptr.add(CTX_NGRAM_SPLIT_SIZE),
+            CTX_NGRAM_SPLIT_SIZE,
+        ),
+    )
+}
 
+/// Updates the edges map at `pos`, and records it with the `sancov_trace_order` feature.
+#[inline]
+#[allow(unused_variables)]
+unsafe fn update_edges_map(pos: usize) {
     #[cfg(feature = "pointer_maps")]
     {
         #[cfg(feature = "sancov_pcguard_edges")]
@@ -269,6 +572,57 @@ pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard(guard: *mut u32) {
             *edges_map.get_unchecked_mut(pos) = val;
         }
     }
+
+    #[cfg(feature = "sancov_trace_order")]
+    {
+        let trace_order_ptr = &raw mut TRACE_ORDER;
+        let trace_order = &mut *trace_order_ptr;
+        *trace_order.get_unchecked_mut(TRACE_ORDER_COUNT % TRACE_ORDER_SIZE) = pos;
+        TRACE_ORDER_COUNT = TRACE_ORDER_COUNT.wrapping_add(1);
+    }
+}
+
+/// Callback for sancov `pc_guard` - usually called by `llvm` on each block or edge.
+///
+/// With the `sancov_trace_order` feature, the position is additionally appended to `TRACE_ORDER`,
+/// after the edges map has been updated.
+///
+/// With the `sancov_ctx_ngram_split` feature, the `ngram`- and `ctx`-adjusted positions are not combined,
+/// but each written to its own half of the map, see `CTX_NGRAM_SPLIT_SIZE`.
+///
+/// # Safety
+/// Dereferences `guard`, reads the position from there, then dereferences the [`EDGES_MAP`] at that position.
+/// Should usually not be called directly.
+#[no_mangle]
+#[allow(unused_assignments)]
+pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard(guard: *mut u32) {
+    #[allow(unused_mut)]
+    let mut pos = *guard as usize;
+
+    #[cfg(feature = "sancov_ctx_ngram_split")]
+    {
+        let ngram_pos = update_ngram(pos) % CTX_NGRAM_SPLIT_SIZE;
+        let ctx_pos = (pos ^ __afl_prev_ctx as usize) % CTX_NGRAM_SPLIT_SIZE;
+        update_edges_map(ngram_pos);
+        update_edges_map(CTX_NGRAM_SPLIT_SIZE + ctx_pos);
+    }
+
+    #[cfg(not(feature = "sancov_ctx_ngram_split"))]
+    {
+        #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+        {
+            pos = update_ngram(pos);
+            // println!("Wrinting to {} {}", pos, EDGES_MAP_DEFAULT_SIZE);
+        }
+
+        #[cfg(feature = "sancov_ctx")]
+        {
+            pos ^= __afl_prev_ctx as usize;
+            // println!("Wrinting to {} {}", pos, EDGES_MAP_DEFAULT_SIZE);
+        }
+
+        update_edges_map(pos);
+    }
 }
 
 /// Initialize the sancov `pc_guard` - usually called by `llvm`.
@@ -276,7 +630,23 @@ pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard(guard: *mut u32) {
 /// # Safety
 /// Dereferences at `start` and writes to it.
 #[no_mangle]
-pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard_init(mut start: *mut u32, stop: *mut u32) {
+pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard_init(start: *mut u32, stop: *mut u32) {
+    register_guards(start, stop);
+}
+
+/// Registers the sancov guards from `start` to `stop` that were not seen by
+/// [`__sanitizer_cov_trace_pc_guard_init`], e.g., of code instrumented by a JIT at runtime.
+///
+/// Like the init callback, this assigns the next free indices of the edges map to the guards,
+/// and counts them in `MAX_EDGES_FOUND`. A range that was already registered, i.e., whose first
+/// guard is non-zero, is left alone.
+///
+/// # Safety
+/// `start` to `stop` must be a valid, writable range of guards that lives as long as the
+/// instrumented code may run.
+/// The edge indices are assigned without synchronization: this must be called before the fuzzing
+/// starts, or under a lock that also keeps other threads from registering guards concurrently.
+pub unsafe fn register_guards(mut start: *mut u32, stop: *mut u32) {
     #[cfg(feature = "pointer_maps")]
     if EDGES_MAP_PTR.is_null() {
         EDGES_MAP_PTR = &raw mut EDGES_MAP as *mut u8;
@@ -324,7 +694,15 @@ unsafe extern "C" fn __sanitizer_cov_pcs_init(pcs_beg: *const usize, pcs_end: *c
 
     let pc_tables_ptr = &raw mut PC_TABLES;
     let pc_tables = &mut *pc_tables_ptr;
-    pc_tables.push(slice::from_raw_parts(pcs_beg as *const PcTableEntry, len));
+    // each entry is a pair of `usize`s
+    pc_tables.push(slice::from_raw_parts(
+        pcs_beg as *const PcTableEntry,
+        len / 2,
+    ));
+    // The guards of a module are initialized right before its PC table, one per entry,
+    // so they are the last ones counted in `MAX_EDGES_FOUND`.
+    let guard_bases_ptr = &raw mut PC_TABLE_GUARD_BASES;
+    (*guard_bases_ptr).push(MAX_EDGES_FOUND.saturating_sub(len / 2));
 }
 
 /// An entry to the `sanitizer_cov` `pc_table`
@@ -349,13 +727,697 @@ impl PcTableEntry {
     }
 }
 
-/// Returns an iterator over the PC tables. If no tables were registered, this will be empty.
-pub fn sanitizer_cov_pc_table<'a>() -> impl Iterator<Item = &'a [PcTableEntry]> {
+/// The PC tables registered so far
+fn pc_tables() -> &'static [&'static [PcTableEntry]] {
     // SAFETY: Once PCS_BEG and PCS_END have been initialized, will not be written to again. So
     // there's no TOCTOU issue.
     unsafe {
         let pc_tables_ptr = &raw const PC_TABLES;
         let pc_tables = &*pc_tables_ptr;
-        pc_tables.iter().copied()
+        pc_tables.as_slice()
+    }
+}
+
+/// The edges map index of the first guard of each PC table, in the order of [`pc_tables`]
+#[cfg(not(any(
+    feature = "sancov_ngram4",
+    feature = "sancov_ngram8",
+    feature = "sancov_ctx"
+)))]
+fn pc_table_guard_bases() -> &'static [usize] {
+    // SAFETY: Written only by `__sanitizer_cov_pcs_init`, along with `PC_TABLES`.
+    unsafe {
+        let guard_bases_ptr = &raw const PC_TABLE_GUARD_BASES;
+        (*guard_bases_ptr).as_slice()
+    }
+}
+
+/// Returns an iterator over the PC tables. If no tables were registered, this will be empty.
+pub fn sanitizer_cov_pc_table<'a>() -> impl Iterator<Item = &'a [PcTableEntry]> {
+    pc_tables().iter().copied()
+}
+
+/// Returns whether a non-empty PC table was registered, i.e., whether the target was built with
+/// `-fsanitize-coverage=pc-table`. Without one, [`sanitizer_cov_pc_table`] is empty.
+#[must_use]
+pub fn has_pc_table() -> bool {
+    pc_tables().iter().any(|table| !table.is_empty())
+}
+
+/// Returns the number of entries in all PC tables registered so far, i.e., the number of instrumented PCs.
+#[must_use]
+pub fn pc_table_len() -> usize {
+    pc_tables().iter().map(|table| table.len()).sum()
+}
+
+/// Returns the `idx`-th entry of the PC tables registered so far, counting through the tables in
+/// the order they were registered, or `None` if `idx` is out of bounds.
+#[must_use]
+pub fn pc_table_entry(idx: usize) -> Option<&'static PcTableEntry> {
+    let mut idx = idx;
+    for table in pc_tables() {
+        if idx < table.len() {
+            return Some(&table[idx]);
+        }
+        idx -= table.len();
+    }
+    None
+}
+
+#[cfg(all(
+    any(feature = "sancov_pcguard_edges", feature = "sancov_pcguard_hitcounts"),
+    not(any(
+        feature = "sancov_ngram4",
+        feature = "sancov_ngram8",
+        feature = "sancov_ctx"
+    ))
+))]
+pub use function_entry::*;
+
+#[cfg(all(
+    any(feature = "sancov_pcguard_edges", feature = "sancov_pcguard_hitcounts"),
+    not(any(
+        feature = "sancov_ngram4",
+        feature = "sancov_ngram8",
+        feature = "sancov_ctx"
+    ))
+))]
+mod function_entry {
+    use alloc::{borrow::Cow, vec::Vec};
+    use core::slice;
+
+    use libafl::{
+        corpus::Testcase,
+        executors::ExitKind,
+        feedbacks::{Feedback, HasObserverHandle, StateInitializer},
+        observers::Observer,
+        Error, HasNamedMetadata,
+    };
+    use libafl_bolts::{
+        tuples::{Handle, Handled, MatchName, MatchNameRef},
+        Named,
+    };
+    use serde::{Deserialize, Serialize};
+
+    use super::{has_pc_table, pc_table_guard_bases, pc_tables};
+    use crate::coverage::{edges_map_mut_ptr, edges_max_num};
+
+    /// Returns the edges map indices of all function entries in the registered PC tables.
+    ///
+    /// The n-th entry of a PC table belongs to the n-th guard of its module, whose index in the
+    /// edges map is recorded when the table is registered.
+    /// With ngram or ctx coverage, the edges map is indexed by hashes instead, so there is no such mapping.
+    #[must_use]
+    pub fn function_entry_edges() -> Vec<usize> {
+        pc_tables()
+            .iter()
+            .zip(pc_table_guard_bases())
+            .flat_map(|(table, base)| {
+                table
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.is_function_entry())
+                    .map(move |(idx, _)| base + idx)
+            })
+            .collect()
+    }
+
+    /// Observer that records which function entries were covered in the last execution,
+    /// using the [`PcTableEntry::is_function_entry`](super::PcTableEntry::is_function_entry) flags of the PC tables.
+    ///
+    /// The edges map is read through [`edges_map_mut_ptr`] and [`edges_max_num`], after the execution.
+    /// Requires the target to be built with `-fsanitize-coverage=pc-table`.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct FunctionEntryObserver {
+        name: Cow<'static, str>,
+        entry_edges: Vec<usize>,
+        entered: Vec<usize>,
+    }
+
+    impl FunctionEntryObserver {
+        /// Create a new [`FunctionEntryObserver`] with the given name, for the PC tables registered so far
+        ///
+        /// # Errors
+        /// Returns an error if no PC table was registered (see [`has_pc_table`]), instead of reporting
+        /// zero functions for every execution.
+        pub fn new<N>(name: N) -> Result<Self, Error>
+        where
+            N: Into<Cow<'static, str>>,
+        {
+            if !has_pc_table() {
+                return Err(Error::illegal_state(
+                    "No PC table was registered, build the target with -fsanitize-coverage=pc-table to observe function entries",
+                ));
+            }
+            Ok(Self {
+                name: name.into(),
+                entry_edges: function_entry_edges(),
+                entered: Vec::new(),
+            })
+        }
+
+        /// The edges map indices of the function entries, indexed by function id
+        #[must_use]
+        pub fn entry_edges(&self) -> &[usize] {
+            &self.entry_edges
+        }
+
+        /// The ids of the functions entered in the last execution
+        #[must_use]
+        pub fn entered(&self) -> &[usize] {
+            &self.entered
+        }
+    }
+
+    impl Named for FunctionEntryObserver {
+        fn name(&self) -> &Cow<'static, str> {
+            &self.name
+        }
+    }
+
+    impl<I, S> Observer<I, S> for FunctionEntryObserver {
+        fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+            self.entered.clear();
+            Ok(())
+        }
+
+        fn post_exec(
+            &mut self,
+            _state: &mut S,
+            _input: &I,
+            _exit_kind: &ExitKind,
+        ) -> Result<(), Error> {
+            // # Safety
+            // The edges map pointer is valid for at least `edges_max_num` entries.
+            let map = unsafe { slice::from_raw_parts(edges_map_mut_ptr(), edges_max_num()) };
+            self.entered.extend(
+                self.entry_edges
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, edge)| map.get(**edge).is_some_and(|hits| *hits != 0))
+                    .map(|(id, _)| id),
+            );
+            Ok(())
+        }
+    }
+
+    /// The functions entered so far in the campaign, as a bitset of function ids,
+    /// stored in the state by a [`NewFunctionFeedback`]
+    ///
+    /// It is not registered with the `SerdeAny` registry automatically: call [`NewFunctionMetadata::register`]
+    /// once at startup to (de)serialize a state holding it.
+    #[allow(clippy::unsafe_derive_deserialize)]
+    #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+    pub struct NewFunctionMetadata {
+        seen: Vec<u64>,
+    }
+
+    libafl_bolts::impl_serdeany!(NewFunctionMetadata);
+
+    impl NewFunctionMetadata {
+        /// Create a new, empty [`NewFunctionMetadata`]
+        #[must_use]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Whether the function with the given id was entered before
+        #[must_use]
+        pub fn is_seen(&self, id: usize) -> bool {
+            self.seen
+                .get(id / 64)
+                .is_some_and(|word| word & (1 << (id % 64)) != 0)
+        }
+
+        /// Marks the function with the given id as entered
+        pub fn set_seen(&mut self, id: usize) {
+            if id / 64 >= self.seen.len() {
+                self.seen.resize(id / 64 + 1, 0);
+            }
+            self.seen[id / 64] |= 1 << (id % 64);
+        }
+
+        /// The number of functions entered so far
+        #[must_use]
+        pub fn seen_count(&self) -> usize {
+            self.seen
+                .iter()
+                .map(|word| word.count_ones() as usize)
+                .sum()
+        }
+    }
+
+    /// A [`Feedback`] that considers an execution interesting if it entered a function
+    /// never entered before in the campaign, as recorded by a [`FunctionEntryObserver`].
+    ///
+    /// This is a coarser novelty signal than edge coverage, useful to reward reaching deep code.
+    /// The seen functions are kept in a [`NewFunctionMetadata`] in the state, updated when a testcase is added to the corpus.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct NewFunctionFeedback {
+        name: Cow<'static, str>,
+        o_ref: Handle<FunctionEntryObserver>,
+        #[cfg(feature = "track_hit_feedbacks")]
+        // The previous run's result of `Self::is_interesting`
+        last_result: Option<bool>,
+    }
+
+    impl NewFunctionFeedback {
+        /// Create a new [`NewFunctionFeedback`] for the given [`FunctionEntryObserver`]
+        #[must_use]
+        pub fn new(observer: &FunctionEntryObserver) -> Self {
+            Self {
+                name: Cow::Owned(format!("new_function_{}", observer.name())),
+                o_ref: observer.handle(),
+                #[cfg(feature = "track_hit_feedbacks")]
+                last_result: None,
+            }
+        }
+    }
+
+    impl Named for NewFunctionFeedback {
+        fn name(&self) -> &Cow<'static, str> {
+            &self.name
+        }
+    }
+
+    impl HasObserverHandle for NewFunctionFeedback {
+        type Observer = FunctionEntryObserver;
+
+        fn observer_handle(&self) -> &Handle<FunctionEntryObserver> {
+            &self.o_ref
+        }
+    }
+
+    impl<S> StateInitializer<S> for NewFunctionFeedback
+    where
+        S: HasNamedMetadata,
+    {
+        fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+            state.add_named_metadata(&self.name, NewFunctionMetadata::new());
+            Ok(())
+        }
+    }
+
+    impl<EM, I, OT, S> Feedback<EM, I, OT, S> for NewFunctionFeedback
+    where
+        OT: MatchName,
+        S: HasNamedMetadata,
+    {
+        fn is_interesting(
+            &mut self,
+            state: &mut S,
+            _manager: &mut EM,
+            _input: &I,
+            observers: &OT,
+            _exit_kind: &ExitKind,
+        ) -> Result<bool, Error> {
+            let observer = observers
+                .get(&self.o_ref)
+                .ok_or_else(|| Error::key_not_found("FunctionEntryObserver not found"))?;
+            let meta = state
+                .named_metadata_map()
+                .get::<NewFunctionMetadata>(&self.name)
+                .ok_or_else(|| Error::key_not_found("NewFunctionMetadata not found"))?;
+            let res = observer.entered().iter().any(|id| !meta.is_seen(*id));
+            #[cfg(feature = "track_hit_feedbacks")]
+            {
+                self.last_result = Some(res);
+            }
+            Ok(res)
+        }
+
+        fn append_metadata(
+            &mut self,
+            state: &mut S,
+            _manager: &mut EM,
+            observers: &OT,
+            _testcase: &mut Testcase<I>,
+        ) -> Result<(), Error> {
+            let observer = observers
+                .get(&self.o_ref)
+                .ok_or_else(|| Error::key_not_found("FunctionEntryObserver not found"))?;
+            let meta = state
+                .named_metadata_map_mut()
+                .get_mut::<NewFunctionMetadata>(&self.name)
+                .ok_or_else(|| Error::key_not_found("NewFunctionMetadata not found"))?;
+            for id in observer.entered() {
+                meta.set_seen(*id);
+            }
+            Ok(())
+        }
+
+        #[cfg(feature = "track_hit_feedbacks")]
+        fn last_result(&self) -> Result<bool, Error> {
+            self.last_result
+                .ok_or_else(|| Error::illegal_state("last_result called before Feedback was run"))
+        }
+    }
+
+    #[cfg(all(test, feature = "std"))]
+    mod tests {
+        use libafl::{executors::ExitKind, observers::Observer};
+
+        use super::{function_entry_edges, FunctionEntryObserver, NewFunctionMetadata};
+        use crate::coverage::edges_map_mut_ptr;
+
+        #[test]
+        fn test_function_entry_observer() {
+            let _lock = crate::coverage::lock_coverage_state();
+            super::super::tests::register_pc_table();
+            // the empty PC table is skipped
+            assert_eq!(function_entry_edges(), [10, 12, 13]);
+
+            let mut observer = FunctionEntryObserver::new("functions").unwrap();
+            let map = edges_map_mut_ptr();
+            // cover the first and the third function, and an edge that is no function entry
+            unsafe {
+                *map.add(10) = 1;
+                *map.add(11) = 1;
+                *map.add(13) = 4;
+            }
+            Observer::<(), ()>::pre_exec(&mut observer, &mut (), &()).unwrap();
+            Observer::<(), ()>::post_exec(&mut observer, &mut (), &(), &ExitKind::Ok).unwrap();
+            assert_eq!(observer.entered(), [0, 2]);
+
+            unsafe {
+                *map.add(10) = 0;
+                *map.add(11) = 0;
+                *map.add(13) = 0;
+            }
+            Observer::<(), ()>::pre_exec(&mut observer, &mut (), &()).unwrap();
+            Observer::<(), ()>::post_exec(&mut observer, &mut (), &(), &ExitKind::Ok).unwrap();
+            assert!(observer.entered().is_empty());
+        }
+
+        #[test]
+        fn test_new_function_metadata() {
+            let mut meta = NewFunctionMetadata::new();
+            assert!(!meta.is_seen(70));
+            meta.set_seen(70);
+            meta.set_seen(3);
+            meta.set_seen(3);
+            assert!(meta.is_seen(70));
+            assert!(meta.is_seen(3));
+            assert!(!meta.is_seen(6));
+            assert!(!meta.is_seen(1000));
+            assert_eq!(meta.seen_count(), 2);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+    use alloc::vec::Vec;
+    use std::sync::Once;
+
+    #[cfg(any(
+        feature = "sancov_ngram4",
+        feature = "sancov_ctx",
+        feature = "sancov_ngram8"
+    ))]
+    use libafl::{executors::hooks::ExecutorHook, inputs::NopInput, state::NopState};
+
+    #[cfg(any(
+        feature = "sancov_ngram4",
+        feature = "sancov_ctx",
+        feature = "sancov_ngram8"
+    ))]
+    use super::CoverageStateResetHook;
+    #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+    #[rustversion::not(nightly)]
+    use super::ScalarNgram;
+    #[cfg(feature = "sancov_ctx")]
+    use super::{__afl_prev_ctx, CtxHook};
+    use super::{
+        __sanitizer_cov_pcs_init, has_pc_table, pc_table_entry, pc_table_len, register_guards,
+        PcTableEntry,
+    };
+    #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+    use super::{ngram_state, set_ngram_state, update_ngram, NgramHook};
+    #[cfg(all(feature = "sancov_trace_order", not(feature = "pointer_maps")))]
+    use super::{reset_trace_order, trace_order, update_edges_map, TRACE_ORDER_SIZE};
+    #[cfg(all(feature = "sancov_ctx_ngram_split", not(feature = "pointer_maps")))]
+    use libafl::observers::MapObserver;
+
+    #[cfg(all(feature = "sancov_ctx_ngram_split", not(feature = "pointer_maps")))]
+    use super::{__sanitizer_cov_trace_pc_guard, ctx_ngram_split_observers, CTX_NGRAM_SPLIT_SIZE};
+    #[cfg(any(
+        all(feature = "sancov_trace_order", not(feature = "pointer_maps")),
+        all(feature = "sancov_ctx_ngram_split", not(feature = "pointer_maps"))
+    ))]
+    use crate::coverage::edges_map_mut_ptr;
+    use crate::coverage::{edges_map_used, MAX_EDGES_FOUND};
+
+    /// A PC table of five PCs, with function entries at the edges `0`, `2` and `3`
+    static PCS: [usize; 10] = [0x1000, 1, 0x1004, 0, 0x1008, 1, 0x100c, 1, 0x1010, 0];
+
+    /// Registers [`PCS`] as the only non-empty PC table, once for all tests, for the guards at the edges `10` to `14`.
+    ///
+    /// It is preceded by an empty table, as registered by a module built without `-fsanitize-coverage=pc-table`.
+    /// Must be called with the [`lock_coverage_state`](crate::coverage::lock_coverage_state) held.
+    pub(super) fn register_pc_table() {
+        static REGISTER: Once = Once::new();
+        REGISTER.call_once(|| unsafe {
+            let max_edges_found = MAX_EDGES_FOUND;
+            MAX_EDGES_FOUND = 15;
+            __sanitizer_cov_pcs_init(PCS.as_ptr(), PCS.as_ptr());
+            __sanitizer_cov_pcs_init(PCS.as_ptr(), PCS.as_ptr().add(PCS.len()));
+            MAX_EDGES_FOUND = max_edges_found;
+        });
+    }
+
+    /// Guards get the next free edges, once
+    #[test]
+    fn test_register_guards() {
+        let _lock = crate::coverage::lock_coverage_state();
+        let mut guards = [0_u32; 3];
+        let range = guards.as_mut_ptr_range();
+        unsafe {
+            MAX_EDGES_FOUND = 7;
+            register_guards(range.start, range.end);
+        }
+        assert_eq!(guards, [7, 8, 9]);
+        assert_eq!(edges_map_used(), 10);
+
+        // a registered range, or an empty one, is left alone
+        let range = guards.as_mut_ptr_range();
+        unsafe {
+            register_guards(range.start, range.end);
+            register_guards(range.end, range.end);
+        }
+        assert_eq!(guards, [7, 8, 9]);
+        assert_eq!(edges_map_used(), 10);
+
+        unsafe {
+            MAX_EDGES_FOUND = 0;
+        }
+    }
+
+    /// The entries are counted through all registered PC tables, an empty one does not count
+    #[test]
+    fn test_pc_table_entry() {
+        let _lock = crate::coverage::lock_coverage_state();
+        register_pc_table();
+        assert!(has_pc_table());
+        assert_eq!(pc_table_len(), 5);
+        assert_eq!(pc_table_entry(0).map(PcTableEntry::addr), Some(0x1000));
+        assert_eq!(pc_table_entry(2).map(PcTableEntry::addr), Some(0x1008));
+        assert!(pc_table_entry(3).unwrap().is_function_entry());
+        assert!(!pc_table_entry(4).unwrap().is_function_entry());
+        assert!(pc_table_entry(5).is_none());
+    }
+
+    /// Resets both the ctx and the ngram state, whichever are enabled
+    #[cfg(any(
+        feature = "sancov_ngram4",
+        feature = "sancov_ctx",
+        feature = "sancov_ngram8"
+    ))]
+    #[test]
+    fn test_coverage_state_reset_hook() {
+        let _lock = crate::coverage::lock_coverage_state();
+        unsafe {
+            #[cfg(feature = "sancov_ctx")]
+            {
+                __afl_prev_ctx = 42;
+            }
+            #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+            set_ngram_state(&[1; 12]);
+        }
+
+        let mut hook = CoverageStateResetHook::<NopState<NopInput>>::new();
+        hook.pre_exec(&mut NopState::new(), &NopInput {});
+
+        #[cfg(feature = "sancov_ctx")]
+        assert_eq!(unsafe { __afl_prev_ctx }, 0);
+        #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+        assert!(ngram_state().iter().all(|loc| *loc == 0));
+    }
+
+    /// The ngram history can be restored from a snapshot, missing locs are zeroed
+    #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+    #[test]
+    fn test_ngram_state() {
+        let _lock = crate::coverage::lock_coverage_state();
+        let len = if cfg!(feature = "sancov_ngram4") {
+            4
+        } else {
+            0
+        } + if cfg!(feature = "sancov_ngram8") {
+            8
+        } else {
+            0
+        };
+
+        unsafe {
+            set_ngram_state(&(1..=20).collect::<Vec<u32>>());
+        }
+        let snapshot = ngram_state();
+        assert_eq!(snapshot, (1..=len).collect::<Vec<u32>>());
+
+        unsafe {
+            set_ngram_state(&[7, 8]);
+        }
+        assert_eq!(ngram_state()[..2], [7, 8]);
+        assert!(ngram_state()[2..].iter().all(|loc| *loc == 0));
+
+        unsafe {
+            set_ngram_state(&snapshot);
+        }
+        assert_eq!(ngram_state(), snapshot);
+
+        unsafe {
+            set_ngram_state(&[]);
+        }
+    }
+
+    /// The hooks keep the ctx and ngram state over executions, if asked to
+    #[cfg(any(
+        feature = "sancov_ngram4",
+        feature = "sancov_ctx",
+        feature = "sancov_ngram8"
+    ))]
+    #[test]
+    fn test_reset_each_exec() {
+        let _lock = crate::coverage::lock_coverage_state();
+        let mut state = NopState::<NopInput>::new();
+
+        #[cfg(feature = "sancov_ctx")]
+        {
+            unsafe {
+                __afl_prev_ctx = 42;
+            }
+            CtxHook::new()
+                .reset_each_exec(false)
+                .pre_exec(&mut state, &NopInput {});
+            assert_eq!(unsafe { __afl_prev_ctx }, 42);
+            CtxHook::new().pre_exec(&mut state, &NopInput {});
+            assert_eq!(unsafe { __afl_prev_ctx }, 0);
+        }
+
+        #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+        {
+            unsafe {
+                set_ngram_state(&[42]);
+            }
+            NgramHook::new()
+                .reset_each_exec(false)
+                .pre_exec(&mut state, &NopInput {});
+            assert_eq!(ngram_state()[0], 42);
+            NgramHook::new().pre_exec(&mut state, &NopInput {});
+            assert_eq!(ngram_state()[0], 0);
+        }
+    }
+
+    /// The last `TRACE_ORDER_SIZE` edges are returned in the order they were hit
+    #[cfg(all(feature = "sancov_trace_order", not(feature = "pointer_maps")))]
+    #[test]
+    fn test_trace_order() {
+        let _lock = crate::coverage::lock_coverage_state();
+        let edge = |idx: usize| 5000 + idx % 8;
+
+        reset_trace_order();
+        for idx in [3, 1, 3] {
+            unsafe { update_edges_map(edge(idx)) };
+        }
+        assert_eq!(trace_order(), [edge(3), edge(1), edge(3)]);
+
+        // the oldest edges are overwritten
+        reset_trace_order();
+        for idx in 0..TRACE_ORDER_SIZE + 2 {
+            unsafe { update_edges_map(edge(idx)) };
+        }
+        let order = trace_order();
+        assert_eq!(order.len(), TRACE_ORDER_SIZE);
+        assert_eq!(order[0], edge(2));
+        assert_eq!(order[TRACE_ORDER_SIZE - 1], edge(TRACE_ORDER_SIZE + 1));
+
+        reset_trace_order();
+        assert!(trace_order().is_empty());
+        let map = edges_map_mut_ptr();
+        for idx in 0..8 {
+            unsafe { *map.add(edge(idx)) = 0 };
+        }
+    }
+
+    /// The ngram and the ctx coverage of an edge end up in separate halves of the map
+    #[cfg(all(feature = "sancov_ctx_ngram_split", not(feature = "pointer_maps")))]
+    #[test]
+    fn test_ctx_ngram_split() {
+        let _lock = crate::coverage::lock_coverage_state();
+        let (ngram_observer, ctx_observer) = unsafe { ctx_ngram_split_observers("ngram", "ctx") };
+        assert_eq!(ngram_observer.len(), CTX_NGRAM_SPLIT_SIZE);
+        assert_eq!(ctx_observer.len(), CTX_NGRAM_SPLIT_SIZE);
+
+        let mut guard = 0x1700;
+        unsafe {
+            set_ngram_state(&[]);
+            __afl_prev_ctx = 0x10;
+            __sanitizer_cov_trace_pc_guard(&raw mut guard);
+        }
+        // the ngram history was empty, so only the ctx changes the position
+        assert_ne!(ngram_observer.get(0x1700), 0);
+        assert_ne!(ctx_observer.get(0x1700 ^ 0x10), 0);
+        assert_eq!(ctx_observer.get(0x1700), 0);
+
+        let map = edges_map_mut_ptr();
+        unsafe {
+            *map.add(0x1700) = 0;
+            *map.add(CTX_NGRAM_SPLIT_SIZE + (0x1700 ^ 0x10)) = 0;
+            __afl_prev_ctx = 0;
+            set_ngram_state(&[]);
+        }
+    }
+
+    /// The ngram positions are the xor of the shifted previous locs, on stable and on nightly
+    #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+    #[test]
+    fn test_update_ngram() {
+        let _lock = crate::coverage::lock_coverage_state();
+        unsafe {
+            set_ngram_state(&[]);
+            assert_eq!(
+                [update_ngram(0x10), update_ngram(0x21), update_ngram(0x40)],
+                [0x10, 0x01, 0x42]
+            );
+            set_ngram_state(&[]);
+        }
+    }
+
+    /// The scalar ngram behaves like `core::simd`
+    #[cfg(any(feature = "sancov_ngram4", feature = "sancov_ngram8"))]
+    #[rustversion::not(nightly)]
+    #[test]
+    fn test_scalar_ngram() {
+        let ngram = ScalarNgram::from_array([1, 2, 3, 4]);
+        assert_eq!(ngram.rotate_elements_right::<1>().as_array(), &[4, 1, 2, 3]);
+        assert_eq!(ngram.rotate_elements_right::<5>().as_array(), &[4, 1, 2, 3]);
+        assert_eq!(ngram.reduce_xor(), 4);
+
+        // the shift amount wraps
+        let mut ngram = ScalarNgram::from_array([1, 1]);
+        ngram <<= ScalarNgram::from_array([33, 0]);
+        assert_eq!(ngram.as_array(), &[2, 1]);
     }
 }